@@ -0,0 +1,46 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use zerohub::builder::{create_template_zip, fill_template_content, BuildOptions};
+use zerohub::templates::{ReadRetry, TemplateCache};
+use zerohub::{TemplateData, UserInfo};
+
+fn user_info() -> UserInfo {
+    UserInfo {
+        username: "alice".to_string(),
+        email: "alice@example.com".to_string(),
+        project_name: "Bench Project".to_string(),
+        project_description: "A project generated by the benchmark suite".to_string(),
+        ..Default::default()
+    }
+}
+
+// Whole builds of the shipped templates, base zip included
+fn template_zips(c: &mut Criterion) {
+    let cache = TemplateCache::load(ReadRetry::default());
+    let mut group = c.benchmark_group("create_template_zip");
+    for id in ["server", "client"] {
+        let template = cache.get(id).expect("run benches from the repository root");
+        let data: TemplateData = user_info().into();
+        let build_options = BuildOptions::default();
+        group.throughput(Throughput::Bytes(template.base_zip.len() as u64));
+        group.bench_function(id, |b| {
+            b.iter(|| create_template_zip(&template, black_box(&data), &build_options).unwrap())
+        });
+    }
+    group.finish();
+}
+
+// Substitution alone, on a ~1 MiB document dense with placeholders
+fn fill_large_document(c: &mut Criterion) {
+    let data: TemplateData = user_info().into();
+    let paragraph = "# {{project_name}}\n\n{{project_description}}, maintained by {{username}} <{{email}}>.\n\
+        Install with `pip install {{project_slug}}`; unknown {{tokens}} and stray {{ braces stay.\n\n";
+    let document = paragraph.repeat(1024 * 1024 / paragraph.len());
+
+    let mut group = c.benchmark_group("fill_template_content");
+    group.throughput(Throughput::Bytes(document.len() as u64));
+    group.bench_function("1MiB", |b| b.iter(|| fill_template_content(black_box(&document), &data)));
+    group.finish();
+}
+
+criterion_group!(benches, template_zips, fill_large_document);
+criterion_main!(benches);
@@ -0,0 +1,234 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    Json,
+};
+
+use serde::Serialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+use crate::downloads::CacheSummary;
+use crate::error::{AppError, ErrorBody};
+use crate::maintenance::{MaintenanceStatus, MaintenanceToggle};
+use crate::strict_startup;
+use crate::templates::{ReloadSummary, TemplateCache};
+use crate::AppState;
+
+// Environment variable holding the bearer token for `/admin/*` endpoints.
+// When unset, the admin endpoints are disabled.
+pub const ADMIN_TOKEN_ENV: &str = "ZEROHUB_ADMIN_TOKEN";
+
+// Compare without short-circuiting so response timing doesn't leak the token
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// Check the `Authorization: Bearer <token>` header against the configured token
+pub fn require_admin(state: &AppState, headers: &HeaderMap) -> Result<(), AppError> {
+    let Some(expected) = state.settings.admin_token.as_deref() else {
+        return Err(AppError::NotFound(format!(
+            "Admin endpoints are disabled; set {} to enable them",
+            ADMIN_TOKEN_ENV
+        )));
+    };
+
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or("");
+
+    if constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        Ok(())
+    } else {
+        eprintln!("[ERROR] Rejected admin request with missing or invalid bearer token");
+        Err(AppError::Unauthorized("Missing or invalid bearer token".to_string()))
+    }
+}
+
+// Re-read template files and base zips into the shared cache
+#[utoipa::path(
+    post,
+    path = "/admin/reload",
+    tag = "admin",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Templates reloaded", body = ReloadSummary),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorBody),
+        (status = 404, description = "Admin endpoints disabled", body = ErrorBody),
+        (status = 422, description = "Strict mode and a template doesn't render; the current ones stay live", body = ErrorBody),
+    )
+)]
+pub async fn reload_templates(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state, &headers)?;
+    Ok(Json(reload_cache(&state).await?))
+}
+
+// Load every template afresh and swap the shared cache. Requests already
+// running keep the templates they started with.
+pub async fn reload_cache(state: &AppState) -> Result<ReloadSummary, AppError> {
+    println!("[DEBUG] Reloading templates from disk...");
+    // Reads may back off and sleep between retries, so keep them off the runtime
+    let retry = state.settings.read_retry;
+    let version = state.templates.reserve();
+    let cache = tokio::task::spawn_blocking(move || TemplateCache::load(retry))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    install_reloaded(state, version, cache).await
+}
+
+// Make a reloaded cache live under its reserved version. In strict mode it
+// must pass the startup check first, or the current templates stay live.
+async fn install_reloaded(state: &AppState, version: u64, cache: TemplateCache) -> Result<ReloadSummary, AppError> {
+    let cache = if state.settings.strict_startup {
+        let settings = Arc::clone(&state.settings);
+        tokio::task::spawn_blocking(move || strict_startup::check_templates(&cache, &settings).map(|()| cache))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))?
+            .map_err(|e| {
+                eprintln!("[ERROR] Reload rejected, keeping template version {}: {}", state.templates.snapshot().version(), e);
+                AppError::Unprocessable(e)
+            })?
+    } else {
+        cache
+    };
+    let summary = state.templates.install(version, cache).summary();
+    if summary.version != version {
+        println!("[DEBUG] Reload {} was overtaken by a later one, version {} stays live", version, summary.version);
+    }
+    println!(
+        "[DEBUG] Templates reloaded as version {}: {} ok, {} failed",
+        summary.version,
+        summary.reloaded.len(),
+        summary.failed.len()
+    );
+    Ok(summary)
+}
+
+// Stored downloads awaiting redemption, with their sizes
+#[utoipa::path(
+    get,
+    path = "/admin/cache",
+    tag = "admin",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Cached downloads and total bytes held", body = CacheSummary),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorBody),
+        (status = 404, description = "Admin endpoints disabled", body = ErrorBody),
+    )
+)]
+pub async fn list_cache(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state, &headers)?;
+    Ok(Json(state.downloads.summary()))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClearedCache {
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+// Drop every stored download; their tokens stop working
+#[utoipa::path(
+    delete,
+    path = "/admin/cache",
+    tag = "admin",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Cache cleared", body = ClearedCache),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorBody),
+        (status = 404, description = "Admin endpoints disabled", body = ErrorBody),
+    )
+)]
+pub async fn clear_cache(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state, &headers)?;
+    let (entries, bytes) = state.downloads.clear();
+    println!("[DEBUG] Cleared download cache: {} entries, {} bytes", entries, bytes);
+    Ok(Json(ClearedCache { entries, bytes }))
+}
+
+// Whether generation is paused for maintenance
+#[utoipa::path(
+    get,
+    path = "/admin/maintenance",
+    tag = "admin",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Current maintenance mode", body = MaintenanceStatus),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorBody),
+        (status = 404, description = "Admin endpoints disabled", body = ErrorBody),
+    )
+)]
+pub async fn maintenance_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state, &headers)?;
+    Ok(Json(state.maintenance.status(&state.settings.retry_after)))
+}
+
+// Pause or resume generation without restarting; generate endpoints answer
+// 503 with Retry-After while paused
+#[utoipa::path(
+    put,
+    path = "/admin/maintenance",
+    tag = "admin",
+    security(("bearer" = [])),
+    request_body = MaintenanceToggle,
+    responses(
+        (status = 200, description = "Maintenance mode after the change", body = MaintenanceStatus),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorBody),
+        (status = 404, description = "Admin endpoints disabled", body = ErrorBody),
+    )
+)]
+pub async fn set_maintenance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(toggle): Json<MaintenanceToggle>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state, &headers)?;
+    state.maintenance.set(toggle.enabled);
+    Ok(Json(state.maintenance.status(&state.settings.retry_after)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use crate::templates::LoadedTemplate;
+
+    fn template(readme: &str) -> LoadedTemplate {
+        let base_zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new())).finish().unwrap().into_inner();
+        LoadedTemplate::for_test("server", base_zip, vec![("README.md".to_string(), readme.to_string())])
+    }
+
+    #[tokio::test]
+    async fn strict_reloads_keep_the_live_templates_when_one_fails_to_render() {
+        let settings = Settings { strict_startup: true, ..Default::default() };
+        let state = crate::tests::test_state(TemplateCache::from_templates(vec![template("# {{project_name}}")]), settings);
+        let broken = TemplateCache::from_templates(vec![template("# {{project_name}} by {{maintainer}}")]);
+
+        let version = state.templates.reserve();
+        let error = install_reloaded(&state, version, broken).await.unwrap_err();
+        assert!(matches!(&error, AppError::Unprocessable(message) if message.contains("README.md leaves maintainer")), "{}", error);
+        assert_eq!(state.templates.snapshot().version(), 1);
+
+        let version = state.templates.reserve();
+        let fixed = TemplateCache::from_templates(vec![template("# {{project_name}} by {{username}}")]);
+        assert_eq!(install_reloaded(&state, version, fixed).await.unwrap().version, version);
+    }
+}
@@ -0,0 +1,116 @@
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::io::{Cursor, Read, Write};
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::error::AppError;
+
+// A way of storing a template's base archive other than a zip. The builder
+// only reads zips, so these are converted once, when the template is loaded.
+pub trait BaseFormat: Sync {
+    // Replaces the `.zip` of the spec's base archive name, e.g. `.tar.gz`
+    fn extension(&self) -> &'static str;
+    fn to_zip(&self, archive: &[u8]) -> Result<Vec<u8>, AppError>;
+}
+
+// Formats tried, in order, when a template has no base zip
+pub static BASE_FORMATS: [&dyn BaseFormat; 2] = [&TarGz(".tar.gz"), &TarGz(".tgz")];
+
+// A gzip-compressed tarball. Regular files are kept with their permissions;
+// directories, links and other special entries are dropped.
+pub struct TarGz(&'static str);
+
+impl BaseFormat for TarGz {
+    fn extension(&self) -> &'static str {
+        self.0
+    }
+
+    fn to_zip(&self, archive: &[u8]) -> Result<Vec<u8>, AppError> {
+        let invalid = |e: std::io::Error| AppError::from(format!("Invalid tar.gz base archive: {}", e));
+        let mut tar = tar::Archive::new(GzDecoder::new(archive));
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        // The archive only lives in memory until the builder reads it back
+        let options = FileOptions::<()>::default().compression_method(CompressionMethod::Stored);
+        for entry in tar.entries().map_err(invalid)? {
+            let mut entry = entry.map_err(invalid)?;
+            let path = entry.path().map_err(invalid)?.to_string_lossy().into_owned();
+            let name = path.trim_start_matches("./").to_string();
+            if !entry.header().entry_type().is_file() {
+                if !entry.header().entry_type().is_dir() {
+                    println!("[WARN] Skipping non-file entry in base archive: {}", name);
+                }
+                continue;
+            }
+            let options = match entry.header().mode() {
+                Ok(mode) => options.unix_permissions(mode),
+                Err(_) => options,
+            };
+            let mut content = Vec::with_capacity(entry.size() as usize);
+            entry.read_to_end(&mut content).map_err(invalid)?;
+            zip.start_file(name, options)?;
+            zip.write_all(&content)?;
+        }
+        Ok(zip.finish()?.into_inner())
+    }
+}
+
+// The other way round, for offering a generated zip as a tarball: every file
+// with its permissions, stamped with the zip's modification times
+pub fn zip_to_tar_gz(zip: &[u8]) -> Result<Vec<u8>, AppError> {
+    let mut archive = ZipArchive::new(Cursor::new(zip))?;
+    let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if !file.is_file() {
+            continue;
+        }
+        let mut header = tar::Header::new_gnu();
+        header.set_size(file.size());
+        header.set_mode(file.unix_mode().map_or(0o644, |mode| mode & 0o7777));
+        header.set_mtime(file.last_modified().map_or(0, unix_time));
+        let name = file.name().to_string();
+        tar.append_data(&mut header, name, &mut file)?;
+    }
+    Ok(tar.into_inner()?.finish()?)
+}
+
+// Seconds since the epoch of a zip timestamp, which has no time zone; read as UTC
+fn unix_time(time: zip::DateTime) -> u64 {
+    chrono::NaiveDate::from_ymd_opt(time.year().into(), time.month().into(), time.day().into())
+        .and_then(|date| date.and_hms_opt(time.hour().into(), time.minute().into(), time.second().into()))
+        .map_or(0, |time| time.and_utc().timestamp().max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tar_gz_files_become_zip_entries_with_their_modes() {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_size(0);
+        builder.append_data(&mut header, "./bin/", std::io::empty()).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o755);
+        builder.append_data(&mut header, "./bin/run", &b"echo"[..]).unwrap();
+        let tar_gz = builder.into_inner().unwrap().finish().unwrap();
+
+        let zip = TarGz(".tar.gz").to_zip(&tar_gz).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(zip.clone())).unwrap();
+        assert_eq!(archive.len(), 1);
+        let mut file = archive.by_name("bin/run").unwrap();
+        assert_eq!(file.unix_mode().map(|mode| mode & 0o777), Some(0o755));
+        let mut content = String::new();
+        file.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "echo");
+
+        assert!(TarGz(".tar.gz").to_zip(b"not gzip").is_err());
+
+        let round_trip = TarGz(".tar.gz").to_zip(&zip_to_tar_gz(&zip).unwrap()).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(round_trip)).unwrap();
+        let file = archive.by_name("bin/run").unwrap();
+        assert_eq!(file.unix_mode().map(|mode| mode & 0o777), Some(0o755));
+    }
+}
@@ -0,0 +1,395 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Extension, Json,
+};
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::io::{Read, Seek, Write};
+use std::sync::Arc;
+use tokio::sync::{mpsc, OwnedSemaphorePermit};
+use tokio_util::io::ReaderStream;
+use utoipa::ToSchema;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::cache_headers::NO_STORE;
+use crate::downloads::download_url;
+use crate::error::{AppError, ErrorBody};
+use crate::extract::RequestInfo;
+use crate::signing;
+use crate::templates::LoadedTemplate;
+use crate::throttle::GenerationSlot;
+use crate::{generate_project, AppState, GeneratedZip, UserInfo};
+
+// Largest number of projects accepted in one batch request
+pub const MAX_BATCH_SIZE: usize = 50;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BatchRequest {
+    // Template id every project is generated from, e.g. "server"
+    pub template: String,
+    pub projects: Vec<UserInfo>,
+}
+
+impl BatchRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.projects.is_empty() {
+            return Err(AppError::BadRequest("projects must not be empty".to_string()));
+        }
+        if self.projects.len() > MAX_BATCH_SIZE {
+            return Err(AppError::BadRequest(format!(
+                "A batch may contain at most {} projects, got {}",
+                MAX_BATCH_SIZE,
+                self.projects.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+// Name of the report entry added to every batch zip
+pub const BATCH_REPORT_ENTRY: &str = "batch-report.json";
+
+#[derive(Debug, Serialize)]
+pub struct BatchItemSuccess {
+    pub index: usize,
+    pub project_name: String,
+    pub filename: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchItemFailure {
+    pub index: usize,
+    pub project_name: String,
+    pub code: &'static str,
+    pub error: String,
+}
+
+// Per-item outcome of a batch, written into the zip as `batch-report.json`
+#[derive(Debug, Serialize)]
+pub struct BatchReport {
+    pub template: String,
+    pub total: usize,
+    pub succeeded: Vec<BatchItemSuccess>,
+    pub failed: Vec<BatchItemFailure>,
+}
+
+// Outer zip being written plus the report describing every item. Each project
+// is written as soon as it is generated, so only one is held in memory at a
+// time. Entries are stored uncompressed since the inner zips already are.
+struct BatchWriter<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    used: HashSet<String>,
+    report: BatchReport,
+}
+
+impl<W: Write + Seek> BatchWriter<W> {
+    fn new(output: W, template_id: &str, total: usize) -> Self {
+        BatchWriter {
+            zip: ZipWriter::new(output),
+            used: HashSet::from([BATCH_REPORT_ENTRY.to_string()]),
+            report: BatchReport {
+                template: template_id.to_string(),
+                total,
+                succeeded: Vec::new(),
+                failed: Vec::new(),
+            },
+        }
+    }
+
+    // Record one item; a failure is reported instead of aborting the batch.
+    // Filenames are made unique within the batch.
+    fn record(&mut self, index: usize, project_name: String, result: Result<GeneratedZip, AppError>) -> Result<(), AppError> {
+        match result {
+            Ok(project) => {
+                let filename = unique_filename(&mut self.used, &project.filename);
+                let options = FileOptions::<()>::default().compression_method(CompressionMethod::Stored);
+                self.zip.start_file(filename.as_str(), options)?;
+                self.zip.write_all(&project.bytes)?;
+                self.report.succeeded.push(BatchItemSuccess { index, project_name, filename });
+            }
+            Err(e) => {
+                eprintln!("[ERROR] Batch item {} failed: {}", index, e);
+                self.report.failed.push(BatchItemFailure {
+                    index,
+                    project_name,
+                    code: e.code(),
+                    error: e.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // Append the batch report and finish the archive
+    fn finish(mut self) -> Result<(W, BatchReport), AppError> {
+        let report = serde_json::to_vec_pretty(&self.report).map_err(|e| AppError::Internal(e.to_string()))?;
+        let options = FileOptions::<()>::default().compression_method(CompressionMethod::Deflated);
+        self.zip.start_file(BATCH_REPORT_ENTRY, options)?;
+        self.zip.write_all(&report)?;
+        Ok((self.zip.finish()?, self.report))
+    }
+}
+
+// Make a filename unique within the batch by appending a counter
+fn unique_filename(used: &mut HashSet<String>, filename: &str) -> String {
+    let stem = filename.strip_suffix(".zip").unwrap_or(filename);
+    let mut candidate = filename.to_string();
+    let mut counter = 2;
+    while !used.insert(candidate.clone()) {
+        candidate = format!("{}-{}.zip", stem, counter);
+        counter += 1;
+    }
+    candidate
+}
+
+fn batch_filename(template_id: &str) -> String {
+    format!("{}-batch.zip", template_id)
+}
+
+// Generate every project of a batch and return the successful ones in one zip.
+// Failing items don't fail the batch; they are listed in `batch-report.json`.
+#[utoipa::path(
+    post,
+    path = "/generate-batch",
+    tag = "batch",
+    request_body = BatchRequest,
+    responses(
+        (status = 200, description = "Zip of project zips plus batch-report.json", content_type = "application/zip",
+            headers(("X-Batch-Succeeded" = usize), ("X-Batch-Failed" = usize))),
+        (status = 400, description = "Empty or oversized batch", body = ErrorBody),
+        (status = 404, description = "Unknown template", body = ErrorBody),
+    )
+)]
+pub async fn generate_batch(
+    State(state): State<AppState>,
+    request_info: RequestInfo,
+    Json(request): Json<BatchRequest>,
+) -> Result<Response, AppError> {
+    request.validate()?;
+    let template = state.template(&request.template)?;
+    println!("[DEBUG] Received batch request: {} {} projects", request.projects.len(), template.id);
+
+    // Spooled to an anonymous temp file, removed by the OS once the response
+    // body drops it, so memory use doesn't grow with the batch
+    let template_id = template.id.clone();
+    let settings = Arc::clone(&state.settings);
+    let (temp_file, report, signature) = tokio::task::spawn_blocking(move || {
+        let mut batch = BatchWriter::new(settings.temp_files.anonymous()?, &template.id, request.projects.len());
+        for (index, user_info) in request.projects.into_iter().enumerate() {
+            let project_name = user_info.project_name.clone();
+            batch.record(index, project_name, generate_project(&template, user_info, &settings, &request_info))?;
+        }
+        let (mut temp_file, report) = batch.finish()?;
+        // Ed25519 signs the whole message at once, so a signed batch is read
+        // back into memory here; unsigned ones only ever live on disk
+        let signature = match &settings.signer {
+            Some(signer) => {
+                let mut bytes = Vec::new();
+                temp_file.rewind()?;
+                temp_file.read_to_end(&mut bytes)?;
+                Some(signer.sign(&bytes))
+            }
+            None => None,
+        };
+        Ok::<_, AppError>((temp_file, report, signature))
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))??;
+    let (mut file, slot) = temp_file.into_parts();
+    let size = file.metadata()?.len();
+    file.rewind()?;
+
+    println!(
+        "[DEBUG] Batch zip created, size: {} bytes, {} succeeded, {} failed",
+        size,
+        report.succeeded.len(),
+        report.failed.len()
+    );
+    let headers = [
+        (header::CONTENT_TYPE, "application/zip".to_string()),
+        (header::CONTENT_LENGTH, size.to_string()),
+        (header::CACHE_CONTROL, NO_STORE.to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", batch_filename(&template_id))),
+        (header::HeaderName::from_static("x-batch-succeeded"), report.succeeded.len().to_string()),
+        (header::HeaderName::from_static("x-batch-failed"), report.failed.len().to_string()),
+    ];
+    // The temp file slot goes with the body, which holds the file until it is sent
+    let body = Body::from_stream(ReaderStream::new(tokio::fs::File::from_std(file)).map(move |chunk| {
+        let _slot = &slot;
+        chunk
+    }));
+    let mut response = (StatusCode::OK, headers, body).into_response();
+    if let Some((name, value)) = signing::signature_header(signature.as_deref()) {
+        response.headers_mut().insert(name, value);
+    }
+    Ok(response)
+}
+
+// Build the batch on a background task, reporting progress through the channel
+async fn run_batch(
+    template: Arc<LoadedTemplate>,
+    projects: Vec<UserInfo>,
+    state: AppState,
+    request_info: RequestInfo,
+    events: mpsc::Sender<Event>,
+    // Released when the batch is done, however it ends
+    _permit: Option<OwnedSemaphorePermit>,
+) {
+    let total = projects.len();
+    let error_event = |e: &dyn std::fmt::Display| {
+        Event::default().event("error").data(serde_json::json!({ "error": e.to_string() }).to_string())
+    };
+    // Spooled to a temp file like `generate_batch`, and only read back into
+    // memory once finished, to be stored for download
+    let mut batch = match state.settings.temp_files.anonymous() {
+        Ok(file) => BatchWriter::new(file, &template.id, total),
+        Err(e) => {
+            let _ = events.send(error_event(&e)).await;
+            return;
+        }
+    };
+
+    for (index, user_info) in projects.into_iter().enumerate() {
+        let project_name = user_info.project_name.clone();
+        let item_template = Arc::clone(&template);
+        let settings = Arc::clone(&state.settings);
+        let request_info = request_info.clone();
+        let outcome = tokio::task::spawn_blocking(move || generate_project(&item_template, user_info, &settings, &request_info))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))
+            .and_then(|r| r);
+
+        let event = match &outcome {
+            Ok(_) => Event::default().event("progress").data(
+                serde_json::json!({ "completed": index + 1, "total": total, "project_name": project_name }).to_string(),
+            ),
+            Err(e) => Event::default().event("item_error").data(
+                serde_json::json!({
+                    "completed": index + 1,
+                    "total": total,
+                    "index": index,
+                    "project_name": project_name,
+                    "code": e.code(),
+                    "error": e.to_string(),
+                })
+                .to_string(),
+            ),
+        };
+        let recorded = tokio::task::spawn_blocking(move || batch.record(index, project_name, outcome).map(|()| batch))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))
+            .and_then(|r| r);
+        batch = match recorded {
+            Ok(batch) => batch,
+            Err(e) => {
+                let _ = events.send(error_event(&e)).await;
+                return;
+            }
+        };
+        if events.send(event).await.is_err() {
+            println!("[DEBUG] Batch stream client disconnected, stopping");
+            return;
+        }
+    }
+
+    let finished = tokio::task::spawn_blocking(move || {
+        let (mut temp_file, report) = batch.finish()?;
+        let mut zip_data = Vec::new();
+        temp_file.rewind()?;
+        temp_file.read_to_end(&mut zip_data)?;
+        Ok::<_, AppError>((zip_data, report))
+    });
+    let event = match finished.await {
+        Ok(Ok((zip_data, report))) => {
+            let token = state.downloads.insert(batch_filename(&template.id), zip_data);
+            let complete = serde_json::json!({
+                "download_token": token,
+                "download_url": download_url(&state.settings.base_path, &token),
+                "succeeded": report.succeeded.len(),
+                "failed": report.failed.len(),
+            });
+            Event::default().event("complete").data(complete.to_string())
+        }
+        Ok(Err(e)) => error_event(&e),
+        Err(e) => error_event(&e),
+    };
+    let _ = events.send(event).await;
+}
+
+// Server-Sent Events variant of the batch endpoint: one `progress` (or
+// `item_error`) event per project, then a `complete` event carrying a one-time
+// download token
+#[utoipa::path(
+    post,
+    path = "/generate-batch/stream",
+    tag = "batch",
+    request_body = BatchRequest,
+    responses(
+        (status = 200, description = "progress, item_error, complete and error events", content_type = "text/event-stream"),
+        (status = 400, description = "Empty or oversized batch", body = ErrorBody),
+        (status = 404, description = "Unknown template", body = ErrorBody),
+    )
+)]
+pub async fn generate_batch_stream(
+    State(state): State<AppState>,
+    slot: Option<Extension<GenerationSlot>>,
+    request_info: RequestInfo,
+    Json(request): Json<BatchRequest>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    request.validate()?;
+    let template = state.template(&request.template)?;
+    println!("[DEBUG] Received streaming batch request: {} {} projects", request.projects.len(), template.id);
+
+    // The batch is built after this returns, so it keeps the generation slot
+    let permit = slot.and_then(|Extension(slot)| slot.take());
+    let (sender, receiver) = mpsc::channel(16);
+    tokio::spawn(run_batch(template, request.projects, state.clone(), request_info, sender, permit));
+
+    let stream = stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|event| (Ok(event), receiver))
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zip::ZipArchive;
+
+    #[test]
+    fn batch_zip_keeps_successes_and_reports_failures() {
+        let mut batch = BatchWriter::new(tempfile::tempfile().unwrap(), "client", 3);
+        let project = |filename: &str| GeneratedZip {
+            filename: filename.to_string(),
+            bytes: b"zip".to_vec(),
+            template_version: None,
+            entry_count: 1,
+            substitutions: Vec::new(),
+        };
+        batch.record(0, "A".to_string(), Ok(project("a-client.zip"))).unwrap();
+        batch.record(1, "Bad".to_string(), Err(AppError::BadRequest("invalid slug".to_string()))).unwrap();
+        batch.record(2, "A again".to_string(), Ok(project("a-client.zip"))).unwrap();
+
+        let (file, _) = batch.finish().unwrap();
+        let mut archive = ZipArchive::new(file).unwrap();
+        let names = archive.file_names().map(str::to_string).collect::<HashSet<_>>();
+        assert_eq!(
+            names,
+            HashSet::from(["a-client.zip".to_string(), "a-client-2.zip".to_string(), BATCH_REPORT_ENTRY.to_string()])
+        );
+
+        let report: serde_json::Value = serde_json::from_reader(archive.by_name(BATCH_REPORT_ENTRY).unwrap()).unwrap();
+        assert_eq!(report["total"], 3);
+        assert_eq!(report["succeeded"][1]["filename"], "a-client-2.zip");
+        assert_eq!(report["failed"][0]["index"], 1);
+        assert_eq!(report["failed"][0]["code"], "BAD_REQUEST");
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::HashSet;
+use std::fs;
+
+// Environment variable with a path to a file of extra blocked project names,
+// one per line; blank lines and lines starting with `#` are ignored
+pub const BLOCKED_NAMES_FILE_ENV: &str = "ZEROHUB_BLOCKED_NAMES_FILE";
+
+// Device names Windows refuses as file or directory names, whatever the extension
+const WINDOWS_RESERVED_NAMES: [&str; 22] = [
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8", "com9",
+    "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+// Project names that are rejected, stored lowercased for case-insensitive matching
+#[derive(Debug, Clone)]
+pub struct BlockedNames {
+    names: HashSet<String>,
+}
+
+impl Default for BlockedNames {
+    fn default() -> Self {
+        BlockedNames {
+            names: WINDOWS_RESERVED_NAMES.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
+
+impl BlockedNames {
+    // Built-in names plus every entry of a blocklist file's contents
+    pub fn with_entries(source: &str) -> Self {
+        let mut blocked = BlockedNames::default();
+        blocked.names.extend(
+            source
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_lowercase),
+        );
+        blocked
+    }
+
+    // Read the file named by `ZEROHUB_BLOCKED_NAMES_FILE`, keeping only the
+    // built-in names when it is unset or unreadable
+    pub fn from_env() -> Self {
+        let Ok(path) = std::env::var(BLOCKED_NAMES_FILE_ENV) else {
+            return BlockedNames::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(source) => {
+                let blocked = BlockedNames::with_entries(&source);
+                println!("[DEBUG] Loaded {} blocked project names from {}", blocked.names.len(), path);
+                blocked
+            }
+            Err(e) => {
+                eprintln!("[ERROR] Failed to read {} ({}): {}", BLOCKED_NAMES_FILE_ENV, path, e);
+                BlockedNames::default()
+            }
+        }
+    }
+
+    pub fn is_blocked(&self, name: &str) -> bool {
+        self.names.contains(&name.trim().to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_builtin_and_file_names_case_insensitively() {
+        let blocked = BlockedNames::with_entries("# team denylist\n\nInternal-Tools\n");
+        assert!(blocked.is_blocked("CON"));
+        assert!(blocked.is_blocked(" internal-tools "));
+        assert!(!blocked.is_blocked("# team denylist"));
+        assert!(!blocked.is_blocked("console"));
+    }
+}
@@ -0,0 +1,1300 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Component, Path};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use zip::{write::FileOptions, CompressionMethod, DateTime, ZipArchive, ZipWriter};
+
+use crate::error::AppError;
+use crate::functions::{self, FunctionContext};
+use crate::template_config::{is_valid_placeholder_name, BinaryMode, EntryCompression, SubstitutionRegion, TemplateConfig};
+use crate::temp_files::TempFileSlots;
+use crate::templates::LoadedTemplate;
+use crate::{TemplateData, UserInfo};
+
+// Per-request knobs for assembling the zip, derived from the request body
+#[derive(Debug, Default)]
+pub struct BuildOptions {
+    // Base archive entries matching any of these globs are skipped
+    pub exclude: GlobSet,
+    // Pin entry timestamps so identical input yields identical bytes
+    pub deterministic: bool,
+    // Write entries sorted by name instead of base-archive order
+    pub sort_entries: bool,
+    // Append a `SHA256SUMS` entry covering every other entry
+    pub include_checksums: bool,
+    // Zip-bomb guard: cap on the total decompressed size of the base archive.
+    // Unset for the bundled templates, which are trusted.
+    pub max_base_uncompressed_bytes: Option<u64>,
+    // Cap on any single base entry, which is read into memory whole
+    pub max_base_entry_bytes: Option<u64>,
+    // Line ending style for filled text files; None keeps the template's own
+    pub line_endings: Option<LineEndings>,
+    // Add a `zerohub-provenance.json` entry recording what produced the project
+    pub include_provenance: bool,
+    // Add a `GETTING_STARTED.md` entry with the template's next steps
+    pub include_getting_started: bool,
+    // (FUNDING.yml key, handle) pairs for a `.github/FUNDING.yml` entry, see
+    // `funding_from_extra`; no entry when empty
+    pub funding: Vec<(String, String)>,
+    // Spaces to re-indent `.json` entries with, see `reindent_json`
+    pub json_indent: Option<u8>,
+    // Rewrite `.json` entries with their object keys sorted, see `canonicalize_json`
+    pub canonicalize_json: bool,
+    // Extension -> comment prefix for the build banner; None leaves files unmarked
+    pub banner_comments: Option<BTreeMap<String, String>>,
+    // Refuse builds whose estimated uncompressed size exceeds this many bytes
+    pub max_output_bytes: Option<u64>,
+    // Per-extension compression from the template config; keys are lowercase
+    pub compression: BTreeMap<String, EntryCompression>,
+    // Method for entries without a per-extension rule, from the request
+    pub default_compression: CompressionMethod,
+    // Build only these base entries and template files, by their original
+    // names; everything when None
+    pub only_entries: Option<BTreeSet<String>>,
+    // Archive-level comment, already filled; none when unset
+    pub zip_comment: Option<String>,
+    // Bound on the temp files open at once; the zip is written through one
+    pub temp_files: TempFileSlots,
+}
+
+impl BuildOptions {
+    // Whether an entry, by its original name, survives `exclude` and `only_entries`
+    fn keeps(&self, name: &str) -> bool {
+        !self.exclude.is_match(name) && self.only_entries.as_ref().is_none_or(|only| only.contains(name))
+    }
+}
+
+// Name of the optional provenance entry
+pub const PROVENANCE_ENTRY: &str = "zerohub-provenance.json";
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndings {
+    Lf,
+    Crlf,
+}
+
+// Rewrite every line ending, including mixed ones, to the given style
+pub fn normalize_line_endings(content: &str, line_endings: LineEndings) -> String {
+    let lf = content.replace("\r\n", "\n");
+    match line_endings {
+        LineEndings::Lf => lf,
+        LineEndings::Crlf => lf.replace('\n', "\r\n"),
+    }
+}
+
+pub const SUPPORTED_JSON_INDENTS: [u8; 2] = [2, 4];
+
+// Prepend a generation banner in the file type's comment syntax, after a
+// shebang line if there is one. Files without a known prefix are unchanged.
+// Without a timestamp, as in deterministic builds, the banner names no date.
+pub fn add_banner(name: &str, content: String, timestamp: Option<&str>, prefixes: &BTreeMap<String, String>) -> String {
+    let extension = Path::new(name).extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+    let Some(prefix) = extension.and_then(|e| prefixes.get(&e)) else {
+        return content;
+    };
+    let banner = match timestamp {
+        Some(timestamp) => format!("{} Generated by ZeroHub on {}\n", prefix, timestamp),
+        None => format!("{} Generated by ZeroHub\n", prefix),
+    };
+    match content.strip_prefix("#!").and_then(|_| content.split_once('\n')) {
+        Some((shebang, rest)) => format!("{}\n{}{}", shebang, banner, rest),
+        None => banner + &content,
+    }
+}
+
+// Pretty-print a JSON document with the given indent, keeping key order and
+// a trailing newline if it had one. None when the content isn't valid JSON.
+pub fn reindent_json(content: &[u8], indent: u8) -> Option<Vec<u8>> {
+    let value: serde_json::Value = serde_json::from_slice(content).ok()?;
+    write_json(&value, indent, content.ends_with(b"\n"))
+}
+
+// Indent used for canonical JSON when the request sets no `json_indent`
+pub const CANONICAL_JSON_INDENT: u8 = 2;
+
+// Object keys sorted at every depth, by a round-trip through `BTreeMap`
+fn sort_json_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let sorted = map.into_iter().map(|(key, value)| (key, sort_json_keys(value))).collect::<BTreeMap<_, _>>();
+            serde_json::Value::Object(sorted.into_iter().collect())
+        }
+        serde_json::Value::Array(items) => serde_json::Value::Array(items.into_iter().map(sort_json_keys).collect()),
+        other => other,
+    }
+}
+
+// Rewrite a JSON document with sorted keys and the given indent, so the same
+// data always gives the same bytes. None when the content isn't valid JSON.
+pub fn canonicalize_json(content: &[u8], indent: u8) -> Option<Vec<u8>> {
+    let value: serde_json::Value = serde_json::from_slice(content).ok()?;
+    write_json(&sort_json_keys(value), indent, content.ends_with(b"\n"))
+}
+
+fn write_json(value: &serde_json::Value, indent: u8, trailing_newline: bool) -> Option<Vec<u8>> {
+    let indent = vec![b' '; indent as usize];
+    let mut output = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(
+        &mut output,
+        serde_json::ser::PrettyFormatter::with_indent(&indent),
+    );
+    serde::Serialize::serialize(value, &mut serializer).ok()?;
+    if trailing_newline {
+        output.push(b'\n');
+    }
+    Some(output)
+}
+
+// Name of the optional checksums entry, always written last
+pub const CHECKSUMS_ENTRY: &str = "SHA256SUMS";
+
+impl BuildOptions {
+    pub fn from_user_info(user_info: &UserInfo) -> Result<Self, AppError> {
+        let mut exclude = GlobSetBuilder::new();
+        for pattern in &user_info.exclude {
+            let glob = Glob::new(pattern)
+                .map_err(|e| AppError::BadRequest(format!("Invalid exclude pattern '{}': {}", pattern, e)))?;
+            exclude.add(glob);
+        }
+        let exclude = exclude
+            .build()
+            .map_err(|e| AppError::BadRequest(format!("Invalid exclude patterns: {}", e)))?;
+
+        if let Some(indent) = user_info.json_indent.filter(|indent| !SUPPORTED_JSON_INDENTS.contains(indent)) {
+            return Err(AppError::BadRequest(format!("json_indent must be 2 or 4, got {}", indent)));
+        }
+
+        Ok(BuildOptions {
+            exclude,
+            deterministic: user_info.deterministic,
+            sort_entries: user_info.sort_entries.unwrap_or(user_info.deterministic),
+            include_checksums: user_info.include_checksums,
+            max_base_uncompressed_bytes: None,
+            line_endings: user_info.line_endings,
+            include_provenance: user_info.include_provenance,
+            include_getting_started: user_info.include_getting_started,
+            funding: Vec::new(),
+            json_indent: user_info.json_indent,
+            canonicalize_json: user_info.canonicalize_json,
+            banner_comments: None,
+            max_base_entry_bytes: None,
+            only_entries: None,
+            zip_comment: None,
+            temp_files: TempFileSlots::default(),
+            max_output_bytes: None,
+            compression: BTreeMap::new(),
+            default_compression: match user_info.compression.as_deref() {
+                Some(name) => parse_compression(name)?,
+                None => CompressionMethod::Deflated,
+            },
+        })
+    }
+}
+
+// Request compression names and the methods they select, as compiled in.
+// zstd compresses text-heavy scaffolds noticeably better than deflate and
+// decompresses faster, but needs a zip tool that knows method 93: 7-Zip,
+// libarchive's bsdtar and recent Info-ZIP builds do; Windows Explorer, macOS
+// Archive Utility and older `unzip` don't, which is why deflate stays the default.
+pub const COMPRESSION_METHODS: &[(&str, CompressionMethod)] = &[
+    ("stored", CompressionMethod::Stored),
+    ("deflated", CompressionMethod::Deflated),
+    #[cfg(feature = "bzip2")]
+    ("bzip2", CompressionMethod::Bzip2),
+    #[cfg(feature = "zstd")]
+    ("zstd", CompressionMethod::Zstd),
+];
+
+pub fn parse_compression(name: &str) -> Result<CompressionMethod, AppError> {
+    COMPRESSION_METHODS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(name.trim()))
+        .map(|(_, method)| *method)
+        .ok_or_else(|| {
+            let supported = COMPRESSION_METHODS.iter().map(|(known, _)| *known).collect::<Vec<_>>().join(", ");
+            AppError::BadRequest(format!("Unsupported compression '{}'; supported: {}", name, supported))
+        })
+}
+
+// Names of the placeholders every template can use
+pub const BUILTIN_PLACEHOLDERS: [&str; 11] = [
+    "username",
+    "email",
+    "project_name",
+    "project_description",
+    "project_slug",
+    "project_name_upper",
+    "project_name_lower",
+    "project_name_kebab",
+    "project_name_snake",
+    "project_name_title",
+    "secret_key",
+];
+
+// Placeholder tokens and the values they are replaced with. Template-specific
+// values like `{{dependencies}}` and allowlisted extras are only present when
+// the template resolved them.
+pub fn placeholders(data: &TemplateData) -> Vec<(&str, &str)> {
+    let mut placeholders: Vec<(&str, &str)> = vec![
+        ("{{username}}", &data.username),
+        ("{{email}}", &data.email),
+        ("{{project_name}}", &data.project_name),
+        ("{{project_description}}", &data.project_description),
+        ("{{project_slug}}", &data.project_slug),
+        ("{{project_name_upper}}", &data.project_name_cases.upper),
+        ("{{project_name_lower}}", &data.project_name_cases.lower),
+        ("{{project_name_kebab}}", &data.project_name_cases.kebab),
+        ("{{project_name_snake}}", &data.project_name_cases.snake),
+        ("{{project_name_title}}", &data.project_name_cases.title),
+        ("{{secret_key}}", &data.secret_key),
+    ];
+    if let Some(dependencies) = &data.dependencies {
+        placeholders.push(("{{dependencies}}", dependencies.as_str()));
+    }
+    if let Some(client_ip) = &data.client_ip {
+        placeholders.push(("{{client_ip}}", client_ip.as_str()));
+    }
+    if let Some(user_agent) = &data.user_agent {
+        placeholders.push(("{{user_agent}}", user_agent.as_str()));
+    }
+    for (token, value) in &data.extra {
+        placeholders.push((token.as_str(), value.as_str()));
+    }
+    placeholders
+}
+
+// Helper function to fill template content with user data.
+// Works in a single pass, so a value that itself looks like a placeholder is
+// copied verbatim instead of being substituted again. Function tokens like
+// `{{upper:username}}` are evaluated with the built-in functions and left as
+// written when they can't be. With a `default_placeholder_value`, well-formed
+// placeholders without a value get it.
+pub fn fill_template_content(content: &str, data: &TemplateData) -> String {
+    fill_template_content_counted(content, data).0
+}
+
+// Like `fill_template_content`, also counting the tokens replaced
+pub fn fill_template_content_counted(content: &str, data: &TemplateData) -> (String, usize) {
+    let placeholders = placeholders(data);
+    let context = FunctionContext { placeholders: &placeholders, generated_at: data.generated_at };
+    let fallback = data.default_placeholder_value.as_deref();
+    let mut output = String::with_capacity(content.len());
+    let mut rest = content;
+    let mut replaced = 0;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        let matched = candidate.find("}}").map(|end| &candidate[..end + 2]).and_then(|token| {
+            let inner = &token[2..token.len() - 2];
+            match placeholders.iter().find(|(t, _)| *t == token) {
+                Some((_, value)) => Some((token, Cow::Borrowed(*value))),
+                None => match functions::builtin().call(inner, &context) {
+                    Some(value) => Some((token, Cow::Owned(value))),
+                    None => fallback
+                        .filter(|_| is_valid_placeholder_name(inner))
+                        .map(|value| (token, Cow::Borrowed(value))),
+                },
+            }
+        });
+        match matched {
+            Some((token, value)) => {
+                output.push_str(&value);
+                rest = &candidate[token.len()..];
+                replaced += 1;
+            }
+            None => {
+                output.push_str("{{");
+                rest = &candidate[2..];
+            }
+        }
+    }
+    output.push_str(rest);
+    (output, replaced)
+}
+
+// Byte range of `content` that a substitution region covers
+fn region_bounds(content: &str, region: SubstitutionRegion) -> (usize, usize) {
+    let mut offset = 0;
+    let lines = content.split_inclusive('\n').map(|line| {
+        let start = offset;
+        offset += line.len();
+        (start, offset, line.trim_end_matches(['\r', '\n']))
+    });
+    match region {
+        SubstitutionRegion::Lines(first, last) => {
+            let mut lines = lines.skip(first.saturating_sub(1)).take((last + 1).saturating_sub(first.max(1)));
+            let Some((start, mut end, _)) = lines.next() else {
+                return (0, 0);
+            };
+            end = lines.last().map_or(end, |(_, end, _)| end);
+            (start, end)
+        }
+        SubstitutionRegion::Frontmatter => {
+            let mut lines = lines;
+            match lines.next() {
+                Some((_, _, "---")) => lines.find(|(_, _, line)| *line == "---").map_or((0, 0), |(_, end, _)| (0, end)),
+                _ => (0, 0),
+            }
+        }
+    }
+}
+
+// Fill placeholders only within a file's configured region, or everywhere
+// when it has none. Also returns how many tokens were replaced.
+pub fn fill_region(content: &str, data: &TemplateData, region: Option<SubstitutionRegion>) -> (String, usize) {
+    let Some(region) = region else {
+        return fill_template_content_counted(content, data);
+    };
+    let (start, end) = region_bounds(content, region);
+    let (filled, replaced) = fill_template_content_counted(&content[start..end], data);
+    let mut output = String::with_capacity(content.len());
+    output.push_str(&content[..start]);
+    output.push_str(&filled);
+    output.push_str(&content[end..]);
+    (output, replaced)
+}
+
+// The part of a file whose placeholders are filled, see `fill_region`
+pub fn substituted_part(content: &str, region: Option<SubstitutionRegion>) -> &str {
+    let (start, end) = region.map_or((0, content.len()), |region| region_bounds(content, region));
+    &content[start..end]
+}
+
+// Names of every `{{placeholder}}` used in a text, valid names only
+pub fn placeholder_names(content: &str) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let mut rest = content;
+    while let Some(start) = rest.find("{{") {
+        rest = &rest[start + 2..];
+        if let Some(end) = rest.find("}}") {
+            if is_valid_placeholder_name(&rest[..end]) {
+                names.insert(rest[..end].to_string());
+                rest = &rest[end + 2..];
+            }
+        }
+    }
+    names
+}
+
+// Replace the UTF-8 bytes of each placeholder inside a binary entry.
+// Only used for entries that explicitly opt in via the template config.
+pub fn fill_binary_content(content: &[u8], data: &TemplateData, mode: BinaryMode) -> (Vec<u8>, usize) {
+    let mut output = content.to_vec();
+    let mut replaced = 0;
+    for (token, value) in placeholders(data) {
+        let (token, value) = (token.as_bytes(), value.as_bytes());
+        let mut result = Vec::with_capacity(output.len());
+        let mut pos = 0;
+        while pos < output.len() {
+            if output[pos..].starts_with(token) {
+                match mode {
+                    BinaryMode::Resize => {
+                        result.extend_from_slice(value);
+                        replaced += 1;
+                    }
+                    BinaryMode::Fixed if value.len() <= token.len() => {
+                        result.extend_from_slice(value);
+                        result.resize(result.len() + token.len() - value.len(), 0);
+                        replaced += 1;
+                    }
+                    BinaryMode::Fixed => {
+                        println!(
+                            "[WARN] Value for {} does not fit in place, leaving token untouched",
+                            String::from_utf8_lossy(token)
+                        );
+                        result.extend_from_slice(token);
+                    }
+                }
+                pos += token.len();
+            } else {
+                result.push(output[pos]);
+                pos += 1;
+            }
+        }
+        output = result;
+    }
+    (output, replaced)
+}
+
+// Open a template's base archive, reporting a corrupt zip distinctly from other failures
+pub fn open_base_archive<'a>(template_id: &str, base_zip_data: &'a [u8]) -> Result<ZipArchive<Cursor<&'a [u8]>>, AppError> {
+    ZipArchive::new(Cursor::new(base_zip_data)).map_err(|e| AppError::BaseArchiveCorrupt {
+        template_id: template_id.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+// A file destined for the output zip
+#[derive(Debug, Clone)]
+pub struct ZipEntry {
+    pub name: String,
+    pub content: Vec<u8>,
+    // Unix permission bits carried over from the base archive, e.g. for scripts
+    pub unix_mode: Option<u32>,
+    // Placeholder tokens replaced while filling it, reported with `debug=true`
+    pub substitutions: usize,
+}
+
+// Fail early when the sizes an archive declares already exceed the limit
+fn check_declared_size<R: Read + Seek>(archive: &mut ZipArchive<R>, limit: u64) -> Result<(), AppError> {
+    let mut declared = 0u64;
+    for i in 0..archive.len() {
+        declared = declared.saturating_add(archive.by_index_raw(i)?.size());
+    }
+    if declared > limit {
+        return Err(too_large(limit));
+    }
+    Ok(())
+}
+
+fn too_large(limit: u64) -> AppError {
+    AppError::PayloadTooLarge(format!("Base archive expands to more than {} bytes", limit))
+}
+
+fn entry_too_large(name: &str, limit: u64) -> AppError {
+    AppError::PayloadTooLarge(format!("Base archive entry {} is larger than the per-file limit of {} bytes", name, limit))
+}
+
+// Uncompressed size of the project a build would produce, from the sizes the
+// base archive declares for the entries it keeps plus the unfilled template
+// files. Needs only the central directory, so it is cheap to run up front.
+pub fn estimate_output_bytes<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    template: &LoadedTemplate,
+    build_options: &BuildOptions,
+) -> Result<u64, AppError> {
+    let mut total = 0u64;
+    for i in 0..archive.len() {
+        let entry = archive.by_index_raw(i)?;
+        if build_options.keeps(entry.name()) {
+            total = total.saturating_add(entry.size());
+        }
+    }
+    let fill_bytes = template
+        .fill_files
+        .iter()
+        .filter(|(name, _)| build_options.keeps(name))
+        .map(|(_, content)| content.len() as u64)
+        .sum::<u64>();
+    Ok(total.saturating_add(fill_bytes))
+}
+
+// Text as far as placeholder substitution is concerned: UTF-8 without NUL bytes
+pub fn as_text(content: &[u8]) -> Option<&str> {
+    if content.contains(&0) {
+        return None;
+    }
+    std::str::from_utf8(content).ok()
+}
+
+// The name a base entry gets from the template's rename map, with
+// placeholders filled. Names are checked so a value can't move the entry
+// outside the project.
+fn renamed_entry(config: &TemplateConfig, name: &str, data: &TemplateData) -> Result<Option<String>, AppError> {
+    let Some(pattern) = config.rename.get(name) else {
+        return Ok(None);
+    };
+    let renamed = fill_template_content(pattern, data);
+    let is_safe = !renamed.is_empty()
+        && !renamed.ends_with('/')
+        && Path::new(&renamed).components().all(|c| matches!(c, Component::Normal(_)));
+    if !is_safe {
+        return Err(AppError::BadRequest(format!("Renaming {} gives an unusable entry name: {}", name, renamed)));
+    }
+    println!("[DEBUG] Renaming base entry {} -> {}", name, renamed);
+    Ok(Some(renamed))
+}
+
+// Renamed entries must not land on the name of any other entry
+fn check_rename_collisions(entries: &[ZipEntry], renamed: &HashSet<String>) -> Result<(), AppError> {
+    let mut seen = HashSet::new();
+    for entry in entries {
+        if !seen.insert(entry.name.as_str()) && renamed.contains(&entry.name) {
+            return Err(AppError::BadRequest(format!("Renamed entry collides with another entry: {}", entry.name)));
+        }
+    }
+    Ok(())
+}
+
+// Whether every `include_if` condition gating the entry holds for this build
+fn is_included(config: &TemplateConfig, name: &str, data: &TemplateData) -> bool {
+    if config.include_if.is_empty() {
+        return true;
+    }
+    let placeholders = placeholders(data);
+    for (gate, condition) in config.include_conditions_for(name) {
+        let token = format!("{{{{{}}}}}", condition.field);
+        let value = placeholders.iter().find(|(t, _)| *t == token).map(|(_, value)| *value);
+        if value != Some(condition.equals.as_str()) {
+            println!("[DEBUG] Skipping {} ({}): {} is {:?}, not {:?}", name, gate, condition.field, value, condition.equals);
+            return false;
+        }
+    }
+    true
+}
+
+// Read every entry of a base archive, applying binary substitution to the
+// entries the template config opts in. Placeholders in text entries are filled
+// as `TemplateConfig::fills_base_entry` decides, by default with
+// `substitute_text`. Exclusion and substitution go by the
+// original name; the rename map is applied last. Returns the renamed names too.
+fn base_entries<R: Read + Seek>(
+    template_id: &str,
+    mut archive: ZipArchive<R>,
+    substitute_text: bool,
+    config: &TemplateConfig,
+    data: &TemplateData,
+    build_options: &BuildOptions,
+) -> Result<(Vec<ZipEntry>, HashSet<String>), AppError> {
+    if let Some(limit) = build_options.max_base_uncompressed_bytes {
+        check_declared_size(&mut archive, limit)?;
+    }
+    // Declared sizes can lie, so the limit is enforced on the bytes actually inflated too
+    let mut remaining = build_options.max_base_uncompressed_bytes.unwrap_or(u64::MAX);
+    let corrupt = |e: &dyn std::fmt::Display| AppError::BaseArchiveCorrupt {
+        template_id: template_id.to_string(),
+        reason: e.to_string(),
+    };
+
+    let mut entries = Vec::with_capacity(archive.len());
+    let mut renamed = HashSet::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(|e| corrupt(&e))?;
+        let name = file.name().to_string();
+        let unix_mode = file.unix_mode();
+
+        if !build_options.keeps(&name) {
+            println!("[DEBUG] Excluding base entry: {}", name);
+            continue;
+        }
+        if !is_included(config, &name, data) {
+            continue;
+        }
+        let entry_limit = build_options.max_base_entry_bytes.unwrap_or(u64::MAX);
+        // The declared size catches most oversized entries before any inflating
+        if file.size() > entry_limit {
+            return Err(entry_too_large(&name, entry_limit));
+        }
+
+        let mut buffer = Vec::new();
+        let mut substitutions = 0;
+        let read_limit = remaining.min(entry_limit);
+        let copied = std::io::copy(&mut (&mut file).take(read_limit.saturating_add(1)), &mut buffer)
+            .map_err(|e| corrupt(&e))?;
+        if copied > entry_limit {
+            return Err(entry_too_large(&name, entry_limit));
+        }
+        if copied > remaining {
+            return Err(too_large(build_options.max_base_uncompressed_bytes.unwrap_or(u64::MAX)));
+        }
+        remaining -= copied;
+        if let Some(binary) = config.binary_substitution_for(&name) {
+            println!("[DEBUG] Applying binary substitution ({:?}) to {}", binary.mode, name);
+            (buffer, substitutions) = fill_binary_content(&buffer, data, binary.mode);
+        } else if let Some(text) = as_text(&buffer).filter(|_| config.fills_base_entry(&name, substitute_text)) {
+            let (filled, replaced) = fill_region(text, data, config.substitution_region.get(&name).copied());
+            (buffer, substitutions) = (filled.into_bytes(), replaced);
+        }
+        let name = match renamed_entry(config, &name, data)? {
+            Some(new_name) => {
+                renamed.insert(new_name.clone());
+                new_name
+            }
+            None => name,
+        };
+        entries.push(ZipEntry { name, content: buffer, unix_mode, substitutions });
+    }
+    Ok((entries, renamed))
+}
+
+// Name of the optional onboarding entry
+pub const GETTING_STARTED_ENTRY: &str = "GETTING_STARTED.md";
+
+// Next steps for templates whose config has no `getting_started`
+const DEFAULT_GETTING_STARTED: &str = "See README.md for how to install and run the project.";
+
+// Onboarding notes: the project's name and description followed by the
+// template's `getting_started` text, filled like any template file
+fn getting_started_entry(template: &LoadedTemplate, data: &TemplateData, build_options: &BuildOptions) -> ZipEntry {
+    let steps = template.config.getting_started.as_deref().unwrap_or(DEFAULT_GETTING_STARTED);
+    let source = format!(
+        "# Getting started with {{{{project_name}}}}\n\n{{{{project_description}}}}\n\n{}\n",
+        steps.trim()
+    );
+    let (mut filled, substitutions) = fill_template_content_counted(&source, data);
+    if let Some(line_endings) = build_options.line_endings {
+        filled = normalize_line_endings(&filled, line_endings);
+    }
+    ZipEntry { name: GETTING_STARTED_ENTRY.to_string(), content: filled.into_bytes(), unix_mode: None, substitutions }
+}
+
+// Name of the optional sponsorship entry, read by GitHub's "Sponsor" button
+pub const FUNDING_ENTRY: &str = ".github/FUNDING.yml";
+
+// `extra` keys a request can fund the project with, and the FUNDING.yml key
+// each fills, in the order they are written
+pub const FUNDING_PLATFORMS: [(&str, &str); 7] = [
+    ("github_sponsor", "github"),
+    ("patreon", "patreon"),
+    ("open_collective", "open_collective"),
+    ("ko_fi", "ko_fi"),
+    ("liberapay", "liberapay"),
+    ("buy_me_a_coffee", "buy_me_a_coffee"),
+    ("funding_url", "custom"),
+];
+
+// The funding handles among a request's `extra` values, skipping blank ones
+pub fn funding_from_extra(extra: &BTreeMap<String, String>) -> Vec<(String, String)> {
+    FUNDING_PLATFORMS
+        .iter()
+        .filter_map(|(key, platform)| {
+            let handle = extra.get(*key).map(|value| value.trim()).filter(|value| !value.is_empty())?;
+            Some((platform.to_string(), handle.to_string()))
+        })
+        .collect()
+}
+
+// One `platform: "handle"` line per funding handle. Values are written as JSON
+// strings, which YAML reads as quoted scalars, so no handle can break the file.
+fn funding_entry(build_options: &BuildOptions) -> ZipEntry {
+    let mut content = build_options
+        .funding
+        .iter()
+        .map(|(platform, handle)| format!("{}: {}\n", platform, serde_json::Value::from(handle.as_str())))
+        .collect::<String>();
+    if let Some(line_endings) = build_options.line_endings {
+        content = normalize_line_endings(&content, line_endings);
+    }
+    ZipEntry { name: FUNDING_ENTRY.to_string(), content: content.into_bytes(), unix_mode: None, substitutions: 0 }
+}
+
+// Which generator and template revision produced the project. The per-run id
+// and time are left out of deterministic builds, which must not differ.
+fn provenance_entry(template: &LoadedTemplate, data: &TemplateData, build_options: &BuildOptions) -> Result<ZipEntry, AppError> {
+    let mut provenance = serde_json::json!({
+        "generator": "zerohub",
+        "generator_version": env!("CARGO_PKG_VERSION"),
+        "template": template.id,
+        "template_version": template.version(),
+    });
+    if !build_options.deterministic {
+        provenance["generated_id"] = data.generated_id.clone().into();
+        provenance["generated_at"] = data.timestamp.clone().into();
+    }
+    let content = serde_json::to_vec_pretty(&provenance).map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(ZipEntry { name: PROVENANCE_ENTRY.to_string(), content, unix_mode: None, substitutions: 0 })
+}
+
+// Lowercase hex SHA-256, as listed in SHA256SUMS
+pub fn sha256_hex(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+// `sha256sum`-compatible listing of the entries, so recipients can run
+// `sha256sum -c SHA256SUMS` after extracting
+fn checksums_entry(entries: &[ZipEntry]) -> ZipEntry {
+    let content = entries
+        .iter()
+        .map(|entry| format!("{}  {}\n", sha256_hex(&entry.content), entry.name))
+        .collect::<String>();
+    ZipEntry { name: CHECKSUMS_ENTRY.to_string(), content: content.into_bytes(), unix_mode: None, substitutions: 0 }
+}
+
+// Every entry of a template's output: the base archive contents followed by the
+// filled template files, sorted by name when requested, and the checksums last
+pub fn collect_entries(
+    template: &LoadedTemplate,
+    data: &TemplateData,
+    build_options: &BuildOptions,
+) -> Result<Vec<ZipEntry>, AppError> {
+    let archive = open_base_archive(&template.id, &template.base_zip)?;
+    collect_entries_with_base(template, archive, data, build_options)
+}
+
+// Like `collect_entries`, but with the template's base archive swapped for
+// another one, e.g. an upload read straight from a temp file
+pub fn collect_entries_with_base<R: Read + Seek>(
+    template: &LoadedTemplate,
+    mut base: ZipArchive<R>,
+    data: &TemplateData,
+    build_options: &BuildOptions,
+) -> Result<Vec<ZipEntry>, AppError> {
+    if let Some(limit) = build_options.max_output_bytes {
+        let estimate = estimate_output_bytes(&mut base, template, build_options)?;
+        if estimate > limit {
+            eprintln!("[ERROR] Rejected {} build: estimated {} bytes, limit {}", template.id, estimate, limit);
+            return Err(AppError::PayloadTooLarge(format!(
+                "Generated project would be about {} bytes, over the limit of {} bytes",
+                estimate, limit
+            )));
+        }
+    }
+    let (mut entries, renamed) =
+        base_entries(&template.id, base, template.base_is_directory, &template.config, data, build_options)?;
+
+    for (name, content) in &template.fill_files {
+        if build_options.only_entries.as_ref().is_some_and(|only| !only.contains(name)) {
+            continue;
+        }
+        if !is_included(&template.config, name, data) {
+            continue;
+        }
+        let (mut filled, substitutions) = fill_region(content, data, template.config.substitution_region.get(name).copied());
+        if let Some(prefixes) = &build_options.banner_comments {
+            let timestamp = (!build_options.deterministic).then_some(data.timestamp.as_str());
+            filled = add_banner(name, filled, timestamp, prefixes);
+        }
+        if let Some(line_endings) = build_options.line_endings {
+            filled = normalize_line_endings(&filled, line_endings);
+        }
+        entries.push(ZipEntry { name: name.clone(), content: filled.into_bytes(), unix_mode: None, substitutions });
+    }
+    if build_options.include_getting_started {
+        // Replaces a base entry of the same name
+        entries.retain(|entry| entry.name != GETTING_STARTED_ENTRY);
+        entries.push(getting_started_entry(template, data, build_options));
+    }
+    // Templates can still gate it with `include_if`, like any other file
+    if !build_options.funding.is_empty() && is_included(&template.config, FUNDING_ENTRY, data) {
+        // Replaces a base entry of the same name
+        entries.retain(|entry| entry.name != FUNDING_ENTRY);
+        entries.push(funding_entry(build_options));
+    }
+    if build_options.include_provenance {
+        entries.push(provenance_entry(template, data, build_options)?);
+    }
+    if !renamed.is_empty() {
+        check_rename_collisions(&entries, &renamed)?;
+    }
+    if build_options.canonicalize_json {
+        let indent = build_options.json_indent.unwrap_or(CANONICAL_JSON_INDENT);
+        for entry in entries.iter_mut().filter(|e| e.name.ends_with(".json")) {
+            match canonicalize_json(&entry.content, indent) {
+                Some(canonical) => entry.content = canonical,
+                None => println!("[WARN] Not canonicalizing {}: not valid JSON", entry.name),
+            }
+        }
+    } else if let Some(indent) = build_options.json_indent {
+        for entry in entries.iter_mut().filter(|e| e.name.ends_with(".json")) {
+            match reindent_json(&entry.content, indent) {
+                Some(reindented) => entry.content = reindented,
+                None => println!("[DEBUG] Leaving {} as is: not valid JSON", entry.name),
+            }
+        }
+    }
+
+    if build_options.sort_entries {
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    if build_options.include_checksums {
+        // A base archive entry of the same name would be listed with a stale hash
+        entries.retain(|entry| entry.name != CHECKSUMS_ENTRY);
+        let checksums = checksums_entry(&entries);
+        entries.push(checksums);
+    }
+    Ok(entries)
+}
+
+// Compression for an entry, chosen by its extension; deflate unless configured
+fn compression_for(name: &str, build_options: &BuildOptions) -> CompressionMethod {
+    let extension = Path::new(name).extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+    match extension.and_then(|e| build_options.compression.get(&e)) {
+        Some(EntryCompression::Stored) => CompressionMethod::Stored,
+        Some(EntryCompression::Deflate) => CompressionMethod::Deflated,
+        None => build_options.default_compression,
+    }
+}
+
+// Write entries into a zip archive in the given order. The temp file it goes
+// through is removed as soon as this returns, whether it succeeded or not.
+pub fn write_zip(entries: &[ZipEntry], build_options: &BuildOptions) -> Result<Vec<u8>, AppError> {
+    let mut temp_file = build_options.temp_files.named()?;
+
+    {
+        let mut zip = ZipWriter::new(&mut temp_file);
+        let mut options = FileOptions::<()>::default().compression_method(CompressionMethod::Deflated);
+        if build_options.deterministic {
+            options = options.last_modified_time(DateTime::default());
+        }
+
+        for entry in entries {
+            let options = options.compression_method(compression_for(&entry.name, build_options));
+            let options = match entry.unix_mode {
+                Some(mode) => options.unix_permissions(mode),
+                None => options,
+            };
+            zip.start_file(entry.name.as_str(), options)?;
+            zip.write_all(&entry.content)?;
+        }
+        if let Some(comment) = &build_options.zip_comment {
+            zip.set_comment(comment.as_str());
+        }
+
+        zip.finish()?;
+    }
+
+    let mut buffer = Vec::new();
+    temp_file.seek(SeekFrom::Start(0))?;
+    temp_file.read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+// Create a zip for a template: the base archive contents followed by the filled template files
+pub fn create_template_zip(
+    template: &LoadedTemplate,
+    data: &TemplateData,
+    build_options: &BuildOptions,
+) -> Result<Vec<u8>, AppError> {
+    println!("[DEBUG] Starting {} zip creation...", template.id);
+    let entries = collect_entries(template, data, build_options)?;
+    let buffer = write_zip(&entries, build_options)?;
+    println!("[DEBUG] {} zip created successfully, size: {} bytes", template.id, buffer.len());
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template_config::IncludeCondition;
+
+    fn template_data(username: &str, project_name: &str) -> TemplateData {
+        UserInfo {
+            username: username.to_string(),
+            email: "dev@example.com".to_string(),
+            project_name: project_name.to_string(),
+            project_description: "A test project".to_string(),
+            ..Default::default()
+        }
+        .into()
+    }
+
+    // Build an in-memory template whose base archive lists entries out of order
+    fn unordered_template() -> LoadedTemplate {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for name in ["src/main.py", "README.base.md", "alembic.ini"] {
+            zip.start_file(name, FileOptions::<()>::default()).unwrap();
+            zip.write_all(name.as_bytes()).unwrap();
+        }
+        let fill_files = vec![("LICENSE".to_string(), "(c) {{username}}".to_string())];
+        LoadedTemplate::for_test("test", zip.finish().unwrap().into_inner(), fill_files)
+    }
+
+    fn entry_names(zip_data: &[u8]) -> Vec<String> {
+        let archive = ZipArchive::new(Cursor::new(zip_data)).unwrap();
+        (0..archive.len())
+            .map(|i| archive.name_for_index(i).unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn sort_entries_orders_base_and_filled_files_by_name() {
+        let template = unordered_template();
+        let build_options = BuildOptions { sort_entries: true, ..Default::default() };
+        let zip_data = create_template_zip(&template, &template_data("alice", "demo"), &build_options).unwrap();
+        assert_eq!(entry_names(&zip_data), ["LICENSE", "README.base.md", "alembic.ini", "src/main.py"]);
+    }
+
+    #[test]
+    fn only_entries_limits_base_and_filled_files() {
+        let template = unordered_template();
+        let only = ["LICENSE", "alembic.ini"].map(str::to_string).into();
+        let build_options = BuildOptions { only_entries: Some(only), sort_entries: true, ..Default::default() };
+        let zip_data = create_template_zip(&template, &template_data("alice", "demo"), &build_options).unwrap();
+        assert_eq!(entry_names(&zip_data), ["LICENSE", "alembic.ini"]);
+    }
+
+    #[test]
+    fn deterministic_mode_produces_identical_bytes_across_runs() {
+        let mut template = unordered_template();
+        template.fill_files.push(("setup.py".to_string(), "# {{username}}\n".to_string()));
+        let user_info = UserInfo {
+            username: "alice".to_string(),
+            deterministic: true,
+            include_provenance: true,
+            ..Default::default()
+        };
+        let mut build_options = BuildOptions::from_user_info(&user_info).unwrap();
+        build_options.banner_comments = Some(TemplateConfig::default().banner_comment_prefixes());
+        assert!(build_options.sort_entries);
+
+        // A later run: new id, new time
+        let mut later = TemplateData::from(user_info.clone());
+        later.timestamp = "2099-01-01 00:00:00 UTC".to_string();
+        let first = create_template_zip(&template, &user_info.into(), &build_options).unwrap();
+        let second = create_template_zip(&template, &later, &build_options).unwrap();
+        assert_eq!(first, second);
+        let mut archive = ZipArchive::new(Cursor::new(first)).unwrap();
+        let provenance: serde_json::Value = serde_json::from_reader(archive.by_name(PROVENANCE_ENTRY).unwrap()).unwrap();
+        assert!(provenance.get("generated_id").is_none() && provenance.get("generated_at").is_none());
+        let mut setup = String::new();
+        archive.by_name("setup.py").unwrap().read_to_string(&mut setup).unwrap();
+        assert_eq!(setup, "# Generated by ZeroHub\n# alice\n");
+    }
+
+    #[test]
+    fn checksums_entry_lists_every_other_entry_last() {
+        let template = unordered_template();
+        let build_options = BuildOptions { include_checksums: true, ..Default::default() };
+        let entries = collect_entries(&template, &template_data("alice", "demo"), &build_options).unwrap();
+
+        let checksums = entries.last().unwrap();
+        assert_eq!(checksums.name, CHECKSUMS_ENTRY);
+        let lines = String::from_utf8(checksums.content.clone()).unwrap();
+        let lines = lines.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), entries.len() - 1);
+        // sha256("alembic.ini"), the entry's own content
+        assert!(lines.contains(&"91e6e909c6988f5ced15e45b2be3123a9dfacbeffbe781083f3c24d81069df12  alembic.ini"));
+        assert!(lines.iter().any(|line| line.ends_with("  LICENSE")));
+    }
+
+    #[test]
+    fn base_archive_over_the_uncompressed_limit_is_rejected() {
+        let template = unordered_template();
+        let archive = ZipArchive::new(Cursor::new(template.base_zip.clone())).unwrap();
+        let build_options = BuildOptions { max_base_uncompressed_bytes: Some(16), ..Default::default() };
+        let result = collect_entries_with_base(&template, archive, &template_data("alice", "demo"), &build_options);
+        assert!(matches!(result, Err(AppError::PayloadTooLarge(_))));
+    }
+
+    #[test]
+    fn single_base_entry_over_the_per_file_limit_is_named() {
+        let template = unordered_template();
+        let build_options = BuildOptions { max_base_entry_bytes: Some(12), ..Default::default() };
+        let result = collect_entries(&template, &template_data("alice", "demo"), &build_options);
+        let Err(AppError::PayloadTooLarge(error)) = result else { panic!("oversized entry accepted") };
+        assert_eq!(error, "Base archive entry README.base.md is larger than the per-file limit of 12 bytes");
+
+        let build_options = BuildOptions { max_base_entry_bytes: Some(14), ..Default::default() };
+        assert!(collect_entries(&template, &template_data("alice", "demo"), &build_options).is_ok());
+    }
+
+    #[test]
+    fn builds_over_the_output_limit_are_rejected_up_front() {
+        let template = unordered_template();
+        let mut archive = ZipArchive::new(Cursor::new(template.base_zip.clone())).unwrap();
+        let estimate = estimate_output_bytes(&mut archive, &template, &BuildOptions::default()).unwrap();
+        // The three base entries hold their own names, plus the LICENSE template
+        assert_eq!(estimate, ("src/main.py".len() + "README.base.md".len() + "alembic.ini".len() + 16) as u64);
+
+        let build_options = BuildOptions { max_output_bytes: Some(estimate - 1), ..Default::default() };
+        let result = collect_entries(&template, &template_data("alice", "demo"), &build_options);
+        assert!(matches!(result, Err(AppError::PayloadTooLarge(message)) if message.contains(&estimate.to_string())));
+    }
+
+    #[test]
+    fn provenance_records_the_template_version() {
+        let mut template = unordered_template();
+        template.config.version = Some("2.1.0".to_string());
+        let build_options = BuildOptions { include_provenance: true, include_checksums: true, ..Default::default() };
+        let entries = collect_entries(&template, &template_data("alice", "demo"), &build_options).unwrap();
+
+        let provenance = entries.iter().find(|e| e.name == PROVENANCE_ENTRY).unwrap();
+        let provenance: serde_json::Value = serde_json::from_slice(&provenance.content).unwrap();
+        assert_eq!(provenance["template"], "test");
+        assert_eq!(provenance["template_version"], "2.1.0");
+        // Provenance is written before the checksums, so it is covered by them
+        assert_eq!(entries.last().unwrap().name, CHECKSUMS_ENTRY);
+    }
+
+    #[test]
+    fn text_extensions_pick_the_base_entries_that_get_filled() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for name in ["README.md", "alembic.ini", "Makefile"] {
+            zip.start_file(name, FileOptions::<()>::default()).unwrap();
+            zip.write_all(b"{{project_name}}").unwrap();
+        }
+        let mut template = unordered_template();
+        template.base_zip = zip.finish().unwrap().into_inner();
+        template.config.text_extensions = Some(vec!["MD".to_string()]);
+
+        let entries = collect_entries(&template, &template_data("alice", "demo"), &BuildOptions::default()).unwrap();
+        let content = |name: &str| entries.iter().find(|e| e.name == name).map(|e| e.content.clone()).unwrap();
+        assert_eq!(content("README.md"), b"demo");
+        assert_eq!(content("alembic.ini"), b"{{project_name}}");
+        assert_eq!(content("Makefile"), b"{{project_name}}");
+
+        template.config.text_extensions = None;
+        let entries = collect_entries(&template, &template_data("alice", "demo"), &BuildOptions::default()).unwrap();
+        assert!(entries.iter().all(|e| e.name == "LICENSE" || e.content == b"{{project_name}}"));
+    }
+
+    #[test]
+    fn getting_started_lists_the_templates_steps_filled() {
+        let mut template = unordered_template();
+        template.config.getting_started = Some("1. Run `make run` in {{project_slug}}\n".to_string());
+        let build_options = BuildOptions { include_getting_started: true, ..Default::default() };
+        let entries = collect_entries(&template, &template_data("alice", "My Demo"), &build_options).unwrap();
+
+        let getting_started = entries.iter().find(|entry| entry.name == GETTING_STARTED_ENTRY).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&getting_started.content),
+            "# Getting started with My Demo\n\nA test project\n\n1. Run `make run` in my-demo\n"
+        );
+    }
+
+    #[test]
+    fn funding_file_lists_the_requested_platforms() {
+        let template = unordered_template();
+        let extra = [
+            ("funding_url".to_string(), "https://example.com/donate".to_string()),
+            ("github_sponsor".to_string(), "alice".to_string()),
+            ("patreon".to_string(), " ".to_string()),
+        ]
+        .into();
+        let build_options = BuildOptions { funding: funding_from_extra(&extra), ..Default::default() };
+        let entries = collect_entries(&template, &template_data("alice", "demo"), &build_options).unwrap();
+        let funding = entries.iter().find(|entry| entry.name == FUNDING_ENTRY).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&funding.content),
+            "github: \"alice\"\ncustom: \"https://example.com/donate\"\n"
+        );
+
+        let entries = collect_entries(&template, &template_data("alice", "demo"), &BuildOptions::default()).unwrap();
+        assert!(entries.iter().all(|entry| entry.name != FUNDING_ENTRY));
+    }
+
+    #[test]
+    fn placeholder_names_are_collected_once() {
+        let names = placeholder_names("{{username}} {{ not one }} {{username}}{{project_slug}} {{");
+        assert_eq!(names.into_iter().collect::<Vec<_>>(), ["project_slug", "username"]);
+    }
+
+    #[test]
+    fn mixed_line_endings_are_normalized() {
+        let mixed = "a\r\nb\nc\r\n";
+        assert_eq!(normalize_line_endings(mixed, LineEndings::Lf), "a\nb\nc\n");
+        assert_eq!(normalize_line_endings(mixed, LineEndings::Crlf), "a\r\nb\r\nc\r\n");
+    }
+
+    #[test]
+    fn builtin_placeholder_names_match_substituted_tokens() {
+        let data = template_data("alice", "demo");
+        let tokens = placeholders(&data).iter().map(|(token, _)| token.to_string()).collect::<Vec<_>>();
+        let expected = BUILTIN_PLACEHOLDERS.map(|name| format!("{{{{{}}}}}", name));
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn directory_templates_fill_text_base_entries_only() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("README.md", FileOptions::<()>::default()).unwrap();
+        zip.write_all(b"# {{project_name}}").unwrap();
+        zip.start_file("logo.bin", FileOptions::<()>::default()).unwrap();
+        zip.write_all(b"\0{{project_name}}").unwrap();
+        let template = LoadedTemplate {
+            base_zip: zip.finish().unwrap().into_inner(),
+            base_is_directory: true,
+            ..unordered_template()
+        };
+
+        let entries = collect_entries(&template, &template_data("alice", "demo"), &BuildOptions::default()).unwrap();
+        assert_eq!(entries[0].content, b"# demo");
+        assert_eq!(entries[1].content, b"\0{{project_name}}");
+    }
+
+    #[test]
+    fn rename_map_changes_names_only_and_rejects_collisions() {
+        let mut template = unordered_template();
+        template.config.rename = [("src/main.py".to_string(), "src/{{project_slug}}.py".to_string())].into();
+        let entries = collect_entries(&template, &template_data("alice", "demo"), &BuildOptions::default()).unwrap();
+        let renamed = entries.iter().find(|e| e.name == "src/demo.py").unwrap();
+        assert_eq!(renamed.content, b"src/main.py");
+
+        template.config.rename = [("alembic.ini".to_string(), "{{project_name}}".to_string())].into();
+        let collision = collect_entries(&template, &template_data("alice", "LICENSE"), &BuildOptions::default());
+        assert!(matches!(collision, Err(AppError::BadRequest(_))));
+        let escape = collect_entries(&template, &template_data("alice", ".."), &BuildOptions::default());
+        assert!(matches!(escape, Err(AppError::BadRequest(_))));
+    }
+
+    #[test]
+    fn include_if_gates_entries_and_directories_on_values() {
+        let mut template = unordered_template();
+        let condition = |equals: &str| IncludeCondition { field: "use_docker".to_string(), equals: equals.to_string() };
+        template.config.include_if = [("src/".to_string(), condition("true")), ("LICENSE".to_string(), condition("true"))].into();
+        let names = |data: &TemplateData| {
+            let entries = collect_entries(&template, data, &BuildOptions::default()).unwrap();
+            entries.into_iter().map(|e| e.name).collect::<Vec<_>>()
+        };
+
+        let mut data = template_data("alice", "demo");
+        assert_eq!(names(&data), ["README.base.md", "alembic.ini"]);
+        data.extra = vec![("{{use_docker}}".to_string(), "true".to_string())];
+        assert_eq!(names(&data), ["src/main.py", "README.base.md", "alembic.ini", "LICENSE"]);
+    }
+
+    #[test]
+    fn entries_are_compressed_by_extension() {
+        let template = unordered_template();
+        let build_options = BuildOptions {
+            compression: [("ini".to_string(), EntryCompression::Stored)].into(),
+            ..Default::default()
+        };
+        let zip_data = create_template_zip(&template, &template_data("alice", "demo"), &build_options).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(zip_data)).unwrap();
+        assert_eq!(archive.by_name("alembic.ini").unwrap().compression(), CompressionMethod::Stored);
+        assert_eq!(archive.by_name("src/main.py").unwrap().compression(), CompressionMethod::Deflated);
+    }
+
+    #[test]
+    fn request_compression_sets_the_default_method() {
+        let template = unordered_template();
+        let build_options = BuildOptions {
+            compression: [("ini".to_string(), EntryCompression::Deflate)].into(),
+            default_compression: parse_compression("Stored").unwrap(),
+            ..Default::default()
+        };
+        let zip_data = create_template_zip(&template, &template_data("alice", "demo"), &build_options).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(zip_data)).unwrap();
+        assert_eq!(archive.by_name("alembic.ini").unwrap().compression(), CompressionMethod::Deflated);
+        assert_eq!(archive.by_name("src/main.py").unwrap().compression(), CompressionMethod::Stored);
+
+        let Err(AppError::BadRequest(error)) = parse_compression("brotli") else { panic!("brotli accepted") };
+        let mut supported = vec!["stored", "deflated"];
+        supported.extend(cfg!(feature = "bzip2").then_some("bzip2"));
+        supported.extend(cfg!(feature = "zstd").then_some("zstd"));
+        assert!(error.ends_with(&supported.join(", ")), "{}", error);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_entries_read_back() {
+        let template = unordered_template();
+        let build_options = BuildOptions { default_compression: parse_compression("zstd").unwrap(), ..Default::default() };
+        let zip_data = create_template_zip(&template, &template_data("alice", "demo"), &build_options).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(zip_data)).unwrap();
+        let mut entry = archive.by_name("src/main.py").unwrap();
+        assert_eq!(entry.compression(), CompressionMethod::Zstd);
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).unwrap();
+        assert!(!content.is_empty());
+    }
+
+    #[test]
+    fn json_is_reindented_in_key_order_when_it_parses() {
+        let source = b"{\n  \"name\": \"demo\",\n  \"dependencies\": {\n      \"react\": \"^19.0.0\"\n  }\n}\n";
+        let reindented = String::from_utf8(reindent_json(source, 4).unwrap()).unwrap();
+        assert_eq!(reindented, "{\n    \"name\": \"demo\",\n    \"dependencies\": {\n        \"react\": \"^19.0.0\"\n    }\n}\n");
+        // tsconfig-style comments aren't JSON, so such files are left alone
+        assert!(reindent_json(b"{ // comment\n}", 2).is_none());
+
+        let user_info = UserInfo { json_indent: Some(3), ..Default::default() };
+        assert!(BuildOptions::from_user_info(&user_info).is_err());
+    }
+
+    #[test]
+    fn canonical_json_sorts_keys_at_every_depth() {
+        let source = b"{\"version\": \"1.0.0\", \"name\": \"demo\", \"deps\": [{\"b\": 1, \"a\": 2}]}\n";
+        let canonical = String::from_utf8(canonicalize_json(source, 2).unwrap()).unwrap();
+        assert_eq!(
+            canonical,
+            "{\n  \"deps\": [\n    {\n      \"a\": 2,\n      \"b\": 1\n    }\n  ],\n  \"name\": \"demo\",\n  \"version\": \"1.0.0\"\n}\n"
+        );
+        assert!(canonicalize_json(b"{ // comment\n}", 2).is_none());
+    }
+
+    #[test]
+    fn banner_uses_the_file_types_comment_syntax() {
+        let prefixes = TemplateConfig::default().banner_comment_prefixes();
+        let stamped = add_banner("pyproject.toml", "[project]\n".to_string(), Some("2026-01-01"), &prefixes);
+        assert_eq!(stamped, "# Generated by ZeroHub on 2026-01-01\n[project]\n");
+        let script = add_banner("run.sh", "#!/bin/sh\necho hi\n".to_string(), Some("t"), &prefixes);
+        assert_eq!(script, "#!/bin/sh\n# Generated by ZeroHub on t\necho hi\n");
+        assert_eq!(add_banner("package.json", "{}".to_string(), Some("t"), &prefixes), "{}");
+        assert_eq!(add_banner("LICENSE", "MIT".to_string(), Some("t"), &prefixes), "MIT");
+        assert_eq!(add_banner("setup.py", "pass\n".to_string(), None, &prefixes), "# Generated by ZeroHub\npass\n");
+
+        let config = TemplateConfig { banner_comments: [("toml".to_string(), String::new())].into(), ..Default::default() };
+        assert!(!config.banner_comment_prefixes().contains_key("toml"));
+    }
+
+    #[test]
+    fn allowed_extra_values_are_substituted() {
+        let mut data = template_data("alice", "demo");
+        data.extra = vec![("{{python_version}}".to_string(), "3.12".to_string())];
+        assert_eq!(fill_template_content("python {{python_version}} {{other}}", &data), "python 3.12 {{other}}");
+    }
+
+    #[test]
+    fn value_with_delimiter_characters_is_inserted_verbatim() {
+        let data = template_data("a}}b{{c", "demo");
+        assert_eq!(fill_template_content("by {{username}}.", &data), "by a}}b{{c.");
+    }
+
+    #[test]
+    fn placeholder_appearing_multiple_times_is_replaced_everywhere() {
+        let data = template_data("alice", "demo");
+        let filled = fill_template_content("{{username}}/{{project_name}} by {{username}}", &data);
+        assert_eq!(filled, "alice/demo by alice");
+    }
+
+    #[test]
+    fn adjacent_placeholders_are_both_replaced() {
+        let data = template_data("alice", "demo");
+        assert_eq!(fill_template_content("{{username}}{{project_name}}", &data), "alicedemo");
+    }
+
+    #[test]
+    fn value_that_looks_like_a_placeholder_is_not_substituted_again() {
+        let data = template_data("{{project_name}}", "demo");
+        assert_eq!(fill_template_content("{{username}} {{project_name}}", &data), "{{project_name}} demo");
+    }
+
+    #[test]
+    fn empty_values_remove_the_placeholder() {
+        let data = template_data("", "");
+        assert_eq!(fill_template_content("[{{username}}][{{project_name}}]", &data), "[][]");
+    }
+
+    #[test]
+    fn unknown_and_unterminated_tokens_are_left_intact() {
+        let data = template_data("alice", "demo");
+        assert_eq!(fill_template_content("{{unknown}} {{ {{username}} {{", &data), "{{unknown}} {{ alice {{");
+    }
+
+    #[test]
+    fn regions_limit_substitution_to_frontmatter_or_lines() {
+        let data = template_data("alice", "demo");
+        let doc = "---\ntitle: {{project_name}}\n---\n# {{project_name}}\n```\n{{username}}\n```\n";
+        assert_eq!(
+            fill_region(doc, &data, Some(SubstitutionRegion::Frontmatter)).0,
+            "---\ntitle: demo\n---\n# {{project_name}}\n```\n{{username}}\n```\n"
+        );
+        assert_eq!(
+            fill_region(doc, &data, Some(SubstitutionRegion::Lines(2, 4))).0,
+            "---\ntitle: demo\n---\n# demo\n```\n{{username}}\n```\n"
+        );
+        let no_frontmatter = "# {{project_name}}\n";
+        assert_eq!(fill_region(no_frontmatter, &data, Some(SubstitutionRegion::Frontmatter)).0, no_frontmatter);
+        assert_eq!(fill_region(no_frontmatter, &data, Some(SubstitutionRegion::Lines(5, 9))).0, no_frontmatter);
+        assert_eq!(fill_region(no_frontmatter, &data, None).0, "# demo\n");
+    }
+
+    #[test]
+    fn default_value_fills_only_unresolved_placeholder_names() {
+        let mut data = template_data("alice", "demo");
+        data.default_placeholder_value = Some("TODO".to_string());
+        assert_eq!(
+            fill_template_content("{{username}} {{license}} {{author.name}} {{ not a name }} {{", &data),
+            "alice TODO TODO {{ not a name }} {{"
+        );
+    }
+
+    #[test]
+    fn function_placeholders_are_evaluated_or_left_as_written() {
+        let mut data = template_data("alice", "My App");
+        data.generated_at = chrono::DateTime::parse_from_rfc3339("2026-03-01T12:00:00Z").unwrap().into();
+        assert_eq!(
+            fill_template_content("{{upper:username}} {{snake:project_name}} (c) {{date:%Y}}", &data),
+            "ALICE my_app (c) 2026"
+        );
+        data.default_placeholder_value = Some("TODO".to_string());
+        assert_eq!(fill_template_content("{{nope:username}} {{upper:license}}", &data), "{{nope:username}} {{upper:license}}");
+    }
+}
@@ -0,0 +1,91 @@
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+// HTML may change on every deploy, so browsers only keep it briefly
+pub const HTML_CACHE_CONTROL: &str = "public, max-age=300";
+// Assets without a content hash in their name
+pub const ASSET_CACHE_CONTROL: &str = "public, max-age=3600";
+// Fingerprinted assets never change under the same name
+pub const FINGERPRINTED_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+// Generated archives are unique per request and must never be cached
+pub const NO_STORE: &str = "no-store";
+
+// Strong ETag for content known in full, e.g. the embedded index page
+pub fn etag_for(content: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+// Whether the request's If-None-Match already matches the given ETag
+pub fn is_not_modified(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(',').any(|candidate| {
+            let candidate = candidate.trim();
+            candidate == "*" || candidate.trim_start_matches("W/") == etag.trim_start_matches("W/")
+        }))
+        .unwrap_or(false)
+}
+
+// A file name like `app.3f9c2b1d.js` or `app-3f9c2b1d.css` carries a content hash
+fn is_fingerprinted(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    file_name
+        .split(['.', '-'])
+        .skip(1)
+        .any(|part| part.len() >= 8 && part.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn cache_control_for(path: &str) -> &'static str {
+    if path.ends_with(".html") || path.ends_with('/') {
+        HTML_CACHE_CONTROL
+    } else if is_fingerprinted(path) {
+        FINGERPRINTED_CACHE_CONTROL
+    } else {
+        ASSET_CACHE_CONTROL
+    }
+}
+
+// Middleware for the `/static` mount: adds Cache-Control and a weak ETag derived
+// from the Last-Modified and Content-Length headers that ServeDir already sends
+pub async fn static_cache_headers(request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let request_headers = request.headers().clone();
+    let mut response = next.run(request).await;
+
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let headers = response.headers_mut();
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(cache_control_for(&path)));
+
+    let last_modified = headers.get(header::LAST_MODIFIED).and_then(|v| v.to_str().ok());
+    let content_length = headers.get(header::CONTENT_LENGTH).and_then(|v| v.to_str().ok());
+    if let (Some(last_modified), Some(content_length)) = (last_modified, content_length) {
+        let etag = format!("W/{}", etag_for(format!("{}-{}", last_modified, content_length).as_bytes()));
+        if is_not_modified(&request_headers, &etag) {
+            return (
+                StatusCode::NOT_MODIFIED,
+                [
+                    (header::ETAG, etag),
+                    (header::CACHE_CONTROL, cache_control_for(&path).to_string()),
+                ],
+            )
+                .into_response();
+        }
+        if let Ok(value) = HeaderValue::from_str(&etag) {
+            headers.insert(header::ETAG, value);
+        }
+    }
+
+    response
+}
@@ -0,0 +1,180 @@
+use axum::http::HeaderMap;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+use crate::builder::sha256_hex;
+use crate::error::AppError;
+use crate::extract::RequestInfo;
+use crate::templates::LoadedTemplate;
+use crate::{GeneratedZip, UserInfo};
+
+// Request header naming a retry-safe request; identical keyed requests that
+// arrive while the first is still building share its result
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+// Longer keys aren't idempotency keys but something else, and are ignored
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+type Shared = Arc<OnceCell<Result<GeneratedZip, AppError>>>;
+
+// Generations in flight by request hash, so a burst of identical requests,
+// e.g. on a CDN miss, builds the project once and every waiter gets a copy.
+// Entries live only while their build runs; nothing is cached afterwards.
+#[derive(Default)]
+pub struct Coalescer {
+    inflight: Mutex<HashMap<String, Shared>>,
+}
+
+impl Coalescer {
+    // Run `generate`, or wait for the identical one already running under
+    // `key`. Without a key every request builds its own project. Should the
+    // request running the build go away, a waiting one takes it over.
+    pub async fn run<F, Fut>(&self, key: Option<String>, generate: F) -> Result<GeneratedZip, AppError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<GeneratedZip, AppError>>,
+    {
+        let Some(key) = key else {
+            return generate().await;
+        };
+        let shared = {
+            let mut inflight = self.inflight.lock().unwrap_or_else(|e| e.into_inner());
+            if inflight.contains_key(&key) {
+                println!("[DEBUG] Waiting on an identical generation already in flight");
+            }
+            Arc::clone(inflight.entry(key.clone()).or_default())
+        };
+        let result = shared.get_or_init(generate).await.clone();
+        let mut inflight = self.inflight.lock().unwrap_or_else(|e| e.into_inner());
+        if inflight.get(&key).is_some_and(|current| Arc::ptr_eq(current, &shared)) {
+            inflight.remove(&key);
+        }
+        result
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.inflight.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+// What identifies a request as a duplicate: its `Idempotency-Key`, or for
+// deterministic requests the input alone. Other requests get fresh secrets and
+// ids every time and are never shared. The body is always part of the hash,
+// so a reused key with a different body still builds its own project, and so
+// is the peer: even deterministic output carries a `{{secret_key}}`, which must
+// never go to another client. Without a known peer nothing is shared.
+pub fn coalescing_key(
+    template: &LoadedTemplate,
+    user_info: &UserInfo,
+    request: &RequestInfo,
+    headers: &HeaderMap,
+) -> Option<String> {
+    let idempotency_key = headers
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|key| !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN);
+    if idempotency_key.is_none() && !user_info.deterministic {
+        return None;
+    }
+    let client_ip = request.client_ip.as_deref()?;
+    // Request placeholders also put the user agent into the output
+    let user_agent = template.config.request_placeholders.then_some(request.user_agent.as_deref());
+    let input = serde_json::json!({
+        "template": template.id,
+        "version": template.version(),
+        "idempotency_key": idempotency_key,
+        "client_ip": client_ip,
+        "user_agent": user_agent,
+        "user_info": user_info,
+    });
+    Some(sha256_hex(input.to_string().as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn generated(filename: &str) -> GeneratedZip {
+        GeneratedZip {
+            filename: filename.to_string(),
+            bytes: vec![1, 2, 3],
+            template_version: None,
+            entry_count: 1,
+            substitutions: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn identical_requests_in_flight_share_one_build() {
+        let coalescer = Coalescer::default();
+        let builds = &AtomicUsize::new(0);
+        let build = move || async move {
+            builds.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(generated("demo.zip"))
+        };
+
+        let key = || Some("same".to_string());
+        let (a, b, c) = tokio::join!(coalescer.run(key(), build), coalescer.run(key(), build), coalescer.run(key(), build));
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+        assert!([a, b, c].iter().all(|r| r.as_ref().unwrap().filename == "demo.zip"));
+        assert_eq!(coalescer.in_flight(), 0);
+
+        // Finished builds aren't reused, and unkeyed requests are never shared
+        coalescer.run(key(), build).await.unwrap();
+        let _ = tokio::join!(coalescer.run(None, build), coalescer.run(None, build));
+        assert_eq!(builds.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn only_keyed_or_deterministic_requests_are_coalesced() {
+        let template = LoadedTemplate::for_test("server", Vec::new(), Vec::new());
+        let user_info = UserInfo { username: "alice".to_string(), ..Default::default() };
+        let request = RequestInfo { client_ip: Some("203.0.113.7".to_string()), user_agent: None };
+        let mut keyed = HeaderMap::new();
+        keyed.insert(IDEMPOTENCY_KEY_HEADER, "retry-1".parse().unwrap());
+        let key = |user_info: &UserInfo, headers: &HeaderMap| coalescing_key(&template, user_info, &request, headers);
+
+        assert_eq!(key(&user_info, &HeaderMap::new()), None);
+        assert!(key(&user_info, &keyed).is_some());
+        let deterministic = UserInfo { deterministic: true, ..user_info.clone() };
+        assert_eq!(key(&deterministic, &HeaderMap::new()), key(&deterministic.clone(), &HeaderMap::new()));
+        assert_ne!(key(&deterministic, &HeaderMap::new()), key(&deterministic, &keyed));
+        let other = UserInfo { username: "bob".to_string(), ..deterministic.clone() };
+        assert_ne!(key(&deterministic, &HeaderMap::new()), key(&other, &HeaderMap::new()));
+        assert_eq!(coalescing_key(&template, &deterministic, &RequestInfo::default(), &keyed), None);
+    }
+
+    #[tokio::test]
+    async fn different_requesters_never_share_a_secret() {
+        let fill_files = vec![("SECRET".to_string(), "{{secret_key}}".to_string())];
+        let base_zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new())).finish().unwrap().into_inner();
+        let template = LoadedTemplate::for_test("server", base_zip, fill_files);
+        let user_info = UserInfo {
+            username: "alice".to_string(),
+            email: "a@b.c".to_string(),
+            project_name: "demo".to_string(),
+            project_description: "d".to_string(),
+            deterministic: true,
+            ..Default::default()
+        };
+        let settings = crate::settings::Settings::default();
+        let coalescer = Coalescer::default();
+        let generate = |ip: &str| {
+            let request = RequestInfo { client_ip: Some(ip.to_string()), user_agent: None };
+            let key = coalescing_key(&template, &user_info, &request, &HeaderMap::new());
+            let (template, user_info, settings) = (&template, user_info.clone(), &settings);
+            coalescer.run(key, move || async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                crate::generate_project(template, user_info, settings, &request)
+            })
+        };
+
+        let (first, second) = tokio::join!(generate("203.0.113.7"), generate("198.51.100.4"));
+        assert_ne!(first.unwrap().bytes, second.unwrap().bytes);
+    }
+}
@@ -0,0 +1,92 @@
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+// Comma-separated origins allowed to call the API from a browser, e.g.
+// "https://app.example.com", or "*" for any; CORS is off when unset
+pub const CORS_ORIGINS_ENV: &str = "ZEROHUB_CORS_ORIGINS";
+// How long browsers may cache a preflight response, in seconds
+pub const CORS_MAX_AGE_ENV: &str = "ZEROHUB_CORS_MAX_AGE";
+// Ten minutes, which Chromium also caps the cache at
+pub const DEFAULT_CORS_MAX_AGE_SECS: u64 = 600;
+
+// Request headers a front-end sends to the generate endpoints beyond the
+// CORS-safelisted ones
+const ALLOWED_HEADERS: [HeaderName; 2] = [header::CONTENT_TYPE, HeaderName::from_static("idempotency-key")];
+// Response headers the front-end reads: the download name, the template
+// version, entry list and substitution counts, signature, and when to retry
+const EXPOSED_HEADERS: [HeaderName; 8] = [
+    header::CONTENT_DISPOSITION,
+    header::ETAG,
+    header::RETRY_AFTER,
+    HeaderName::from_static("x-template-version"),
+    HeaderName::from_static("x-zip-entries"),
+    HeaderName::from_static("x-substitutions-total"),
+    HeaderName::from_static("x-substitutions"),
+    HeaderName::from_static("x-signature"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorsSettings {
+    // Empty allows any origin
+    pub origins: Vec<HeaderValue>,
+    pub max_age_secs: u64,
+}
+
+impl CorsSettings {
+    pub fn parse(origins: &str, max_age_secs: u64) -> Option<Self> {
+        let origins = origins.split(',').map(str::trim).filter(|origin| !origin.is_empty()).collect::<Vec<_>>();
+        if origins.is_empty() {
+            return None;
+        }
+        if origins.contains(&"*") {
+            return Some(CorsSettings { origins: Vec::new(), max_age_secs });
+        }
+        let origins = origins
+            .into_iter()
+            .filter_map(|origin| match HeaderValue::from_str(origin.trim_end_matches('/')) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    println!("[WARN] Ignoring invalid {} entry: {:?}", CORS_ORIGINS_ENV, origin);
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        (!origins.is_empty()).then_some(CorsSettings { origins, max_age_secs })
+    }
+
+    pub fn from_env() -> Option<Self> {
+        let max_age_secs = crate::settings::env_or(CORS_MAX_AGE_ENV, DEFAULT_CORS_MAX_AGE_SECS);
+        let cors = CorsSettings::parse(&std::env::var(CORS_ORIGINS_ENV).ok()?, max_age_secs)?;
+        println!(
+            "[DEBUG] CORS enabled for {} origin(s), preflights cached for {}s",
+            if cors.origins.is_empty() { "all".to_string() } else { cors.origins.len().to_string() },
+            cors.max_age_secs
+        );
+        Some(cors)
+    }
+
+    pub fn layer(&self) -> CorsLayer {
+        let origins = if self.origins.is_empty() { AllowOrigin::any() } else { AllowOrigin::list(self.origins.clone()) };
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers(ALLOWED_HEADERS)
+            .expose_headers(EXPOSED_HEADERS)
+            .max_age(Duration::from_secs(self.max_age_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn origins_are_parsed_with_any_and_invalid_entries() {
+        assert_eq!(CorsSettings::parse(" ", 600), None);
+        assert_eq!(CorsSettings::parse("https://a.example, *", 600).unwrap().origins, Vec::<HeaderValue>::new());
+        let cors = CorsSettings::parse("https://a.example/, bad\u{7f}", 60).unwrap();
+        assert_eq!(cors.origins, [HeaderValue::from_static("https://a.example")]);
+        assert_eq!(cors.max_age_secs, 60);
+    }
+}
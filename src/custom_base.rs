@@ -0,0 +1,150 @@
+use axum::{
+    extract::{multipart::Field, Multipart, Query, State},
+    response::Response,
+};
+use serde::Deserialize;
+use std::io::{Seek, SeekFrom, Write};
+use tempfile::NamedTempFile;
+use utoipa::IntoParams;
+use zip::ZipArchive;
+
+use crate::builder::{collect_entries_with_base, write_zip};
+use crate::error::{AppError, ErrorBody};
+use crate::extract::RequestInfo;
+use crate::settings::Settings;
+use crate::temp_files::TempFile;
+use crate::{check_not_empty, prepare_project, project_filename, substitution_counts, traced_generation, zip_response, AppState, GeneratedZip, ResponseEncoding, UserInfo};
+
+// Multipart field names accepted by `/generate-custom-base-zip`
+pub const USER_INFO_FIELD: &str = "user_info";
+pub const BASE_ZIP_FIELD: &str = "base_zip";
+
+fn default_template() -> String {
+    "server".to_string()
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CustomBaseParams {
+    // Template whose files and config are filled in on top of the uploaded base
+    #[serde(default = "default_template")]
+    #[param(default = "server")]
+    pub template: String,
+    #[serde(default)]
+    pub encoding: ResponseEncoding,
+    #[serde(default)]
+    pub list_entries: bool,
+    #[serde(default)]
+    pub debug: bool,
+}
+
+pub fn multipart_error(e: impl std::fmt::Display) -> AppError {
+    AppError::BadRequest(format!("Invalid multipart body: {}", e))
+}
+
+// Copy an upload to a temp file chunk by chunk, so memory use doesn't grow
+// with the upload size. The file takes one of `settings.temp_files`' slots.
+pub async fn spool_to_temp_file(mut field: Field<'_>, settings: &Settings) -> Result<TempFile<NamedTempFile>, AppError> {
+    let max_bytes = settings.max_upload_bytes;
+    let mut file = settings.temp_files.named()?;
+    let mut written = 0usize;
+    while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+        written += chunk.len();
+        if written > max_bytes {
+            return Err(AppError::PayloadTooLarge(format!("Uploaded base zip exceeds {} bytes", max_bytes)));
+        }
+        file.write_all(&chunk)?;
+    }
+    file.flush()?;
+    println!("[DEBUG] Spooled uploaded base zip to disk: {} bytes", written);
+    Ok(file)
+}
+
+// Generate a project from a template, with its base archive replaced by an
+// uploaded zip. Expects a multipart body with a `user_info` JSON field and a
+// `base_zip` file field.
+#[utoipa::path(
+    post,
+    path = "/generate-custom-base-zip",
+    tag = "generate",
+    params(CustomBaseParams),
+    request_body(
+        description = "`user_info` field with a UserInfo JSON document and a `base_zip` file field",
+        content_type = "multipart/form-data",
+    ),
+    responses(
+        (status = 200, description = "Generated project zip", content_type = "application/zip"),
+        (status = 204, description = "The upload and request leave no files"),
+        (status = 400, description = "Invalid request or upload", body = ErrorBody),
+        (status = 413, description = "Upload or its decompressed contents too large", body = ErrorBody),
+        (status = 422, description = "No files left from a `strict_extra` template", body = ErrorBody),
+    )
+)]
+pub async fn generate_custom_base_zip(
+    State(state): State<AppState>,
+    Query(params): Query<CustomBaseParams>,
+    request_info: RequestInfo,
+    mut multipart: Multipart,
+) -> Result<Response, AppError> {
+    let template = state.template(&params.template)?;
+    let mut user_info: Option<UserInfo> = None;
+    let mut base_zip: Option<TempFile<NamedTempFile>> = None;
+
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+        match field.name() {
+            Some(USER_INFO_FIELD) => {
+                let text = field.text().await.map_err(multipart_error)?;
+                let parsed = serde_json::from_str(&text)
+                    .map_err(|e| AppError::BadRequest(format!("Invalid {} field: {}", USER_INFO_FIELD, e)))?;
+                user_info = Some(parsed);
+            }
+            Some(BASE_ZIP_FIELD) => {
+                base_zip = Some(spool_to_temp_file(field, &state.settings).await?);
+            }
+            other => {
+                println!("[DEBUG] Ignoring unexpected multipart field: {:?}", other);
+            }
+        }
+    }
+
+    let user_info = user_info.ok_or_else(|| AppError::BadRequest(format!("Missing {} field", USER_INFO_FIELD)))?;
+    let mut base_zip = base_zip.ok_or_else(|| AppError::BadRequest(format!("Missing {} field", BASE_ZIP_FIELD)))?;
+    println!(
+        "[DEBUG] Received custom base request for {} template, user: {}",
+        template.id, user_info.username
+    );
+
+    let settings = state.settings.clone();
+    let generated = tokio::task::spawn_blocking(move || {
+        let project_name = user_info.project_name.clone();
+        traced_generation(&template.id, &project_name, || {
+            let (template_data, mut build_options) = prepare_project(&template, user_info, &settings, &request_info)?;
+            build_options.max_base_uncompressed_bytes = Some(settings.max_base_uncompressed_bytes);
+
+            base_zip.seek(SeekFrom::Start(0))?;
+            // A broken upload is the client's fault, not a corrupt template
+            let archive = ZipArchive::new(base_zip.as_file_mut())
+                .map_err(|e| AppError::BadRequest(format!("Uploaded base zip is not a valid zip: {}", e)))?;
+            let entries = collect_entries_with_base(&template, archive, &template_data, &build_options).map_err(|e| match e {
+                AppError::BaseArchiveCorrupt { reason, .. } => {
+                    AppError::BadRequest(format!("Uploaded base zip is not a valid zip: {}", reason))
+                }
+                other => other,
+            })?;
+            check_not_empty(&template, &entries)?;
+            let bytes = write_zip(&entries, &build_options)?;
+            Ok(GeneratedZip {
+                filename: project_filename(&template, &template_data),
+                bytes,
+                template_version: template.version().map(str::to_string),
+                entry_count: entries.len(),
+                substitutions: substitution_counts(&entries),
+            })
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    println!("[DEBUG] Custom base zip created: {}, size: {} bytes", generated.filename, generated.bytes.len());
+    Ok(zip_response(&state.settings, generated, params.encoding, params.list_entries, params.debug))
+}
@@ -0,0 +1,66 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Duration;
+
+use crate::settings::{env_or, DEBUG_DELAY_MS_ENV, DEBUG_JITTER_MS_ENV};
+
+// Artificial latency for load-testing downstream clients. Only compiled with
+// the `debug-delay` feature, and only active when ZEROHUB_DEBUG_DELAY_MS is set.
+#[derive(Debug, Clone, Copy)]
+pub struct DebugDelay {
+    pub delay: Duration,
+    // Up to this much is added at random on top of `delay`
+    pub jitter: Duration,
+}
+
+impl DebugDelay {
+    pub fn from_env() -> Option<Self> {
+        std::env::var(DEBUG_DELAY_MS_ENV).ok()?;
+        let delay = DebugDelay {
+            delay: Duration::from_millis(env_or(DEBUG_DELAY_MS_ENV, 0)),
+            jitter: Duration::from_millis(env_or(DEBUG_JITTER_MS_ENV, 0)),
+        };
+        println!(
+            "[WARN] Debug delay enabled: generate responses wait {:?} plus up to {:?} jitter",
+            delay.delay, delay.jitter
+        );
+        Some(delay)
+    }
+
+    // The delay for one response, with its jitter drawn from `random`
+    fn for_sample(&self, random: u64) -> Duration {
+        let jitter_ms = self.jitter.as_millis() as u64;
+        self.delay + Duration::from_millis(random % (jitter_ms + 1))
+    }
+}
+
+// Hold back responses from the generate endpoints
+pub async fn delay_generate(State(delay): State<DebugDelay>, request: Request, next: Next) -> Response {
+    let is_generate = request.uri().path().starts_with("/generate");
+    let response = next.run(request).await;
+    if is_generate {
+        let mut bytes = [0u8; 8];
+        getrandom::fill(&mut bytes).expect("OS random number generator unavailable");
+        tokio::time::sleep(delay.for_sample(u64::from_le_bytes(bytes))).await;
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let delay = DebugDelay { delay: Duration::from_millis(100), jitter: Duration::from_millis(50) };
+        assert_eq!(delay.for_sample(0), Duration::from_millis(100));
+        assert_eq!(delay.for_sample(50), Duration::from_millis(150));
+        assert_eq!(delay.for_sample(51), Duration::from_millis(100));
+
+        let no_jitter = DebugDelay { jitter: Duration::ZERO, ..delay };
+        assert_eq!(no_jitter.for_sample(u64::MAX), Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,364 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use percent_encoding::utf8_percent_encode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use zip::ZipArchive;
+
+use crate::cache_headers::NO_STORE;
+use crate::error::{AppError, ErrorBody};
+use crate::extract::{JsonOrForm, RequestInfo};
+use crate::listing::ENTRY_NAME;
+use crate::signing::{self, ZipSigner};
+use crate::{generate_project, AppState, UserInfo};
+
+// How long a download token stays valid
+pub const DOWNLOAD_TTL: Duration = Duration::from_secs(10 * 60);
+// Upper bound on stored downloads; the oldest are evicted first
+pub const MAX_STORED_DOWNLOADS: usize = 32;
+
+struct StoredDownload {
+    filename: String,
+    bytes: Vec<u8>,
+    expires_at: Instant,
+    kind: StoredKind,
+    // Of `bytes`, for archives when signing is on, see `signing`
+    signature: Option<String>,
+}
+
+// What a token gives access to
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StoredKind {
+    // The bytes themselves, served once by `GET /download/{token}`
+    Archive { content_type: &'static str },
+    // The files of a zip, any of them by `GET /download/{token}/{path}` until
+    // the token expires
+    Entries,
+}
+
+// One stored download as listed by `GET /admin/cache`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CachedDownload {
+    pub key: String,
+    pub filename: String,
+    pub bytes: usize,
+    pub expires_in_secs: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CacheSummary {
+    pub entries: Vec<CachedDownload>,
+    pub total_bytes: usize,
+}
+
+// Generated archives parked under a random token until fetched once or expired
+#[derive(Default)]
+pub struct DownloadStore {
+    entries: Mutex<HashMap<String, StoredDownload>>,
+    // Signs archives as they are stored, so every path that hands out a
+    // download token serves them signed
+    signer: Option<ZipSigner>,
+}
+
+impl DownloadStore {
+    pub fn new(signer: Option<ZipSigner>) -> Self {
+        DownloadStore { signer, ..Default::default() }
+    }
+
+    // Store a zip and return the token it can be fetched with
+    pub fn insert(&self, filename: String, bytes: Vec<u8>) -> String {
+        self.insert_archive(filename, "application/zip", bytes)
+    }
+
+    // Store an archive of any type, served with `content_type`
+    pub fn insert_archive(&self, filename: String, content_type: &'static str, bytes: Vec<u8>) -> String {
+        self.store(filename, bytes, StoredKind::Archive { content_type })
+    }
+
+    // Store a zip whose files can each be fetched under the returned token
+    pub fn insert_entries(&self, filename: String, zip: Vec<u8>) -> String {
+        self.store(filename, zip, StoredKind::Entries)
+    }
+
+    fn store(&self, filename: String, bytes: Vec<u8>, kind: StoredKind) -> String {
+        let token = Uuid::new_v4().simple().to_string();
+        let signature = match kind {
+            StoredKind::Archive { .. } => signing::sign(self.signer.as_ref(), &bytes),
+            StoredKind::Entries => None,
+        };
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        entries.retain(|_, d| d.expires_at > now);
+        while entries.len() >= MAX_STORED_DOWNLOADS {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, d)| d.expires_at)
+                .map(|(token, _)| token.clone());
+            match oldest {
+                Some(oldest) => entries.remove(&oldest),
+                None => break,
+            };
+        }
+
+        entries.insert(
+            token.clone(),
+            StoredDownload {
+                filename,
+                bytes,
+                expires_at: now + DOWNLOAD_TTL,
+                kind,
+                signature,
+            },
+        );
+        token
+    }
+
+    // Live entries, soonest to expire first
+    pub fn summary(&self) -> CacheSummary {
+        let now = Instant::now();
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let mut listed = entries
+            .iter()
+            .filter(|(_, d)| d.expires_at > now)
+            .map(|(token, d)| CachedDownload {
+                key: token.clone(),
+                filename: d.filename.clone(),
+                bytes: d.bytes.len(),
+                expires_in_secs: (d.expires_at - now).as_secs(),
+            })
+            .collect::<Vec<_>>();
+        listed.sort_by_key(|d| d.expires_in_secs);
+        CacheSummary {
+            total_bytes: listed.iter().map(|d| d.bytes).sum(),
+            entries: listed,
+        }
+    }
+
+    // Drop every stored download, returning how many entries and bytes were freed
+    pub fn clear(&self) -> (usize, usize) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let freed = (entries.len(), entries.values().map(|d| d.bytes.len()).sum());
+        entries.clear();
+        freed
+    }
+
+    // Remove and return a download; a token can only be redeemed once
+    fn take(&self, token: &str) -> Option<StoredDownload> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let stored = entries.get(token)?;
+        if !matches!(stored.kind, StoredKind::Archive { .. }) {
+            return None;
+        }
+        entries.remove(token).filter(|d| d.expires_at > Instant::now())
+    }
+
+    // Contents of one file of a stored zip, leaving the zip in place
+    fn entry(&self, token: &str, path: &str) -> Option<Vec<u8>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let stored = entries.get(token).filter(|d| d.kind == StoredKind::Entries && d.expires_at > Instant::now())?;
+        let mut archive = ZipArchive::new(Cursor::new(stored.bytes.as_slice())).ok()?;
+        let mut file = archive.by_name(path).ok().filter(|file| file.is_file())?;
+        let mut content = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut content).ok()?;
+        Some(content)
+    }
+}
+
+// Client-facing URL of a stored download, including the configured base path
+pub fn download_url(base_path: &str, token: &str) -> String {
+    format!("{}/download/{}", base_path, token)
+}
+
+// URL of one file under an `insert_entries` token
+pub fn entry_url(base_path: &str, token: &str, path: &str) -> String {
+    format!("{}/{}", download_url(base_path, token), utf8_percent_encode(path, ENTRY_NAME))
+}
+
+fn default_template() -> String {
+    "server".to_string()
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AsyncParams {
+    #[serde(default = "default_template")]
+    #[param(default = "server")]
+    pub template: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AsyncDownload {
+    pub download_url: String,
+    pub download_token: String,
+    pub filename: String,
+    pub expires_in_secs: u64,
+}
+
+// Generate a project and park it for a separate GET, for clients behind
+// proxies that can't take a large POST response
+#[utoipa::path(
+    post,
+    path = "/generate-async",
+    tag = "generate",
+    params(AsyncParams),
+    request_body(content(
+        (UserInfo = "application/json"),
+        (UserInfo = "application/x-www-form-urlencoded"),
+        (UserInfo = "application/json5"),
+        (UserInfo = "application/yaml"),
+    )),
+    responses(
+        (status = 200, description = "Single-use download link, valid for ten minutes", body = AsyncDownload),
+        (status = 204, description = "The request leaves the template with no files"),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Unknown template", body = ErrorBody),
+    )
+)]
+pub async fn generate_async(
+    State(state): State<AppState>,
+    Query(params): Query<AsyncParams>,
+    request_info: RequestInfo,
+    JsonOrForm(user_info): JsonOrForm<UserInfo>,
+) -> Result<Response, AppError> {
+    println!("[DEBUG] Received async {} request for user: {}", params.template, user_info.username);
+    let template = state.template(&params.template)?;
+    let generated = generate_project(&template, user_info, &state.settings, &request_info)
+        .map_err(|e| e.context(&format!("Failed to create {} zip", template.id)))?;
+
+    if generated.entry_count == 0 {
+        return Ok((StatusCode::NO_CONTENT, [(header::CACHE_CONTROL, NO_STORE)]).into_response());
+    }
+    let filename = generated.filename.clone();
+    let download_token = state.downloads.insert(generated.filename, generated.bytes);
+    println!("[DEBUG] Stored {} for download under token {}", filename, download_token);
+    let body = AsyncDownload {
+        download_url: download_url(&state.settings.base_path, &download_token),
+        download_token,
+        filename,
+        expires_in_secs: DOWNLOAD_TTL.as_secs(),
+    };
+    Ok(([(header::CACHE_CONTROL, NO_STORE)], Json(body)).into_response())
+}
+
+// Serve a stored download once, then forget it
+#[utoipa::path(
+    get,
+    path = "/download/{token}",
+    tag = "batch",
+    params(("token" = String, Path, description = "Token from `/generate-async` or a `complete` batch event")),
+    responses(
+        (status = 200, description = "The stored archive", content_type = "application/zip"),
+        (status = 404, description = "Unknown, expired or already used token", body = ErrorBody),
+    )
+)]
+pub async fn download(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Response, AppError> {
+    let stored = state
+        .downloads
+        .take(&token)
+        .ok_or_else(|| AppError::NotFound("Download token is unknown, expired or already used".to_string()))?;
+
+    println!("[DEBUG] Serving download {} ({} bytes)", stored.filename, stored.bytes.len());
+    let content_type = match stored.kind {
+        StoredKind::Archive { content_type } => content_type,
+        StoredKind::Entries => "application/zip",
+    };
+    let mut response = attachment(&stored.filename, content_type, stored.bytes);
+    if let Some((name, value)) = signing::signature_header(stored.signature.as_deref()) {
+        response.headers_mut().insert(name, value);
+    }
+    Ok(response)
+}
+
+// Serve one file of a project stored with its per-file links, see `links`.
+// Unlike archives, files can be fetched any number of times until the token
+// expires.
+#[utoipa::path(
+    get,
+    path = "/download/{token}/{path}",
+    tag = "batch",
+    params(
+        ("token" = String, Path, description = "`files` token from `/generate-links`"),
+        ("path" = String, Path, description = "Path of the file inside the project, e.g. \"src/main.rs\""),
+    ),
+    responses(
+        (status = 200, description = "The file's contents", content_type = "application/octet-stream"),
+        (status = 404, description = "Unknown or expired token, or no such file", body = ErrorBody),
+    )
+)]
+pub async fn download_entry(
+    State(state): State<AppState>,
+    Path((token, path)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    let content = state
+        .downloads
+        .entry(&token, &path)
+        .ok_or_else(|| AppError::NotFound(format!("No file {} for this download token; it may have expired", path)))?;
+    println!("[DEBUG] Serving {} from download {} ({} bytes)", path, token, content.len());
+    let filename = path.rsplit('/').next().unwrap_or(&path);
+    Ok(attachment(filename, "application/octet-stream", content))
+}
+
+fn attachment(filename: &str, content_type: &str, bytes: Vec<u8>) -> Response {
+    let encoded_filename = percent_encoding::utf8_percent_encode(
+        filename,
+        percent_encoding::NON_ALPHANUMERIC
+    ).to_string();
+
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CACHE_CONTROL, NO_STORE.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename*=UTF-8''{}", encoded_filename)),
+        ],
+        bytes,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_lists_entries_and_clear_frees_them() {
+        let store = DownloadStore::default();
+        let token = store.insert("a.zip".to_string(), vec![0; 10]);
+        store.insert("b.zip".to_string(), vec![0; 5]);
+
+        let summary = store.summary();
+        assert_eq!(summary.total_bytes, 15);
+        assert!(summary.entries.iter().any(|d| d.key == token && d.filename == "a.zip"));
+
+        assert_eq!(store.clear(), (2, 15));
+        assert!(store.take(&token).is_none());
+    }
+
+    #[test]
+    fn entry_tokens_serve_files_repeatedly_but_not_the_zip() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("src/main.rs", zip::write::SimpleFileOptions::default()).unwrap();
+        std::io::Write::write_all(&mut zip, b"fn main() {}").unwrap();
+        let store = DownloadStore::default();
+        let token = store.insert_entries("demo.zip".to_string(), zip.finish().unwrap().into_inner());
+
+        assert_eq!(store.entry(&token, "src/main.rs").as_deref(), Some(&b"fn main() {}"[..]));
+        assert!(store.entry(&token, "src/main.rs").is_some());
+        assert!(store.entry(&token, "src/lib.rs").is_none());
+        assert!(store.take(&token).is_none());
+        assert_eq!(entry_url("/zh", &token, "a b/c.txt"), format!("/zh/download/{}/a%20b/c.txt", token));
+    }
+}
@@ -0,0 +1,319 @@
+use axum::{
+    extract::{Multipart, Query, State},
+    response::Response,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom};
+use tempfile::NamedTempFile;
+use utoipa::{IntoParams, ToSchema};
+use zip::ZipArchive;
+
+use crate::builder::{collect_entries, sha256_hex, write_zip, ZipEntry};
+use crate::custom_base::{multipart_error, spool_to_temp_file, USER_INFO_FIELD};
+use crate::error::{AppError, ErrorBody};
+use crate::extract::{JsonOrForm, RequestInfo};
+use crate::temp_files::TempFile;
+use crate::{prepare_project, project_filename, zip_response, AppState, GeneratedZip, ResponseEncoding, UserInfo};
+
+// Multipart field with the previously generated zip
+pub const OLD_ZIP_FIELD: &str = "old_zip";
+
+fn default_template() -> String {
+    "server".to_string()
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DriftParams {
+    // Template the old zip was generated from
+    #[serde(default = "default_template")]
+    #[param(default = "server")]
+    pub template: String,
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq, Eq)]
+pub struct DriftEntry {
+    pub name: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq, Eq)]
+pub struct ChangedEntry {
+    pub name: String,
+    pub old_sha256: String,
+    pub new_sha256: String,
+}
+
+// Differences between an old zip and what the current template builds
+#[derive(Debug, Serialize, ToSchema, Default, PartialEq, Eq)]
+pub struct TemplateDrift {
+    // In the current template's output but not in the old zip
+    pub added: Vec<DriftEntry>,
+    // In the old zip but no longer produced
+    pub removed: Vec<DriftEntry>,
+    pub changed: Vec<ChangedEntry>,
+    pub unchanged: usize,
+}
+
+// Compare entry hashes by name; both sides are sorted by name
+pub fn compare(old: &BTreeMap<String, String>, current: &BTreeMap<String, String>) -> TemplateDrift {
+    let mut drift = TemplateDrift::default();
+    for (name, new_sha256) in current {
+        match old.get(name) {
+            None => drift.added.push(DriftEntry { name: name.clone(), sha256: new_sha256.clone() }),
+            Some(old_sha256) if old_sha256 != new_sha256 => drift.changed.push(ChangedEntry {
+                name: name.clone(),
+                old_sha256: old_sha256.clone(),
+                new_sha256: new_sha256.clone(),
+            }),
+            Some(_) => drift.unchanged += 1,
+        }
+    }
+    for (name, sha256) in old {
+        if !current.contains_key(name) {
+            drift.removed.push(DriftEntry { name: name.clone(), sha256: sha256.clone() });
+        }
+    }
+    drift
+}
+
+fn entry_hashes(entries: &[ZipEntry]) -> BTreeMap<String, String> {
+    entries
+        .iter()
+        .filter(|entry| !entry.name.ends_with('/'))
+        .map(|entry| (entry.name.clone(), sha256_hex(&entry.content)))
+        .collect()
+}
+
+// Hash every file of an uploaded zip, reading at most `max_bytes` in total
+fn uploaded_hashes<R: Read + Seek>(mut archive: ZipArchive<R>, max_bytes: u64) -> Result<BTreeMap<String, String>, AppError> {
+    let invalid = |e: &dyn std::fmt::Display| AppError::BadRequest(format!("Uploaded zip is not a valid zip: {}", e));
+    let mut remaining = max_bytes;
+    let mut hashes = BTreeMap::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index(i).map_err(|e| invalid(&e))?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+        let mut content = Vec::new();
+        let copied = file.take(remaining.saturating_add(1)).read_to_end(&mut content).map_err(|e| invalid(&e))? as u64;
+        if copied > remaining {
+            return Err(AppError::PayloadTooLarge(format!("Uploaded zip expands to more than {} bytes", max_bytes)));
+        }
+        remaining -= copied;
+        hashes.insert(name, sha256_hex(&content));
+    }
+    Ok(hashes)
+}
+
+// Compare a previously generated zip with what the template produces now for
+// the same `user_info`. Per-run values such as `{{secret_key}}` and the
+// timestamp make the files using them show up as changed.
+#[utoipa::path(
+    post,
+    path = "/template-drift",
+    tag = "templates",
+    params(DriftParams),
+    request_body(
+        description = "`user_info` field with the UserInfo JSON used originally and an `old_zip` file field",
+        content_type = "multipart/form-data",
+    ),
+    responses(
+        (status = 200, description = "Added, removed and changed files by name and SHA-256", body = TemplateDrift),
+        (status = 400, description = "Invalid request or upload", body = ErrorBody),
+        (status = 413, description = "Upload or its decompressed contents too large", body = ErrorBody),
+    )
+)]
+pub async fn template_drift(
+    State(state): State<AppState>,
+    Query(params): Query<DriftParams>,
+    request_info: RequestInfo,
+    mut multipart: Multipart,
+) -> Result<Json<TemplateDrift>, AppError> {
+    let template = state.template(&params.template)?;
+    let mut user_info: Option<UserInfo> = None;
+    let mut old_zip: Option<TempFile<NamedTempFile>> = None;
+
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+        match field.name() {
+            Some(USER_INFO_FIELD) => {
+                let text = field.text().await.map_err(multipart_error)?;
+                let parsed = serde_json::from_str(&text)
+                    .map_err(|e| AppError::BadRequest(format!("Invalid {} field: {}", USER_INFO_FIELD, e)))?;
+                user_info = Some(parsed);
+            }
+            Some(OLD_ZIP_FIELD) => {
+                old_zip = Some(spool_to_temp_file(field, &state.settings).await?);
+            }
+            other => {
+                println!("[DEBUG] Ignoring unexpected multipart field: {:?}", other);
+            }
+        }
+    }
+
+    let user_info = user_info.ok_or_else(|| AppError::BadRequest(format!("Missing {} field", USER_INFO_FIELD)))?;
+    let mut old_zip = old_zip.ok_or_else(|| AppError::BadRequest(format!("Missing {} field", OLD_ZIP_FIELD)))?;
+
+    let settings = state.settings.clone();
+    let drift = tokio::task::spawn_blocking(move || {
+        let (template_data, build_options) = prepare_project(&template, user_info, &settings, &request_info)?;
+        let current = entry_hashes(&collect_entries(&template, &template_data, &build_options)?);
+
+        old_zip.seek(SeekFrom::Start(0))?;
+        let archive = ZipArchive::new(old_zip.as_file_mut())
+            .map_err(|e| AppError::BadRequest(format!("Uploaded zip is not a valid zip: {}", e)))?;
+        let old = uploaded_hashes(archive, settings.max_base_uncompressed_bytes)?;
+        Ok::<_, AppError>(compare(&old, &current))
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    println!(
+        "[DEBUG] Template drift for {}: {} added, {} removed, {} changed, {} unchanged",
+        params.template,
+        drift.added.len(),
+        drift.removed.len(),
+        drift.changed.len(),
+        drift.unchanged
+    );
+    Ok(Json(drift))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MigrationParams {
+    // Template the project was generated from, e.g. "server"
+    pub from: String,
+    // Template it is moving to
+    pub to: String,
+    #[serde(default)]
+    pub encoding: ResponseEncoding,
+}
+
+// Manifest added to every migration pack, listing what changed and what to delete
+pub const MIGRATION_MANIFEST_ENTRY: &str = "zerohub-migration.json";
+
+// The `to` entries that are new or differ from `from`, plus the drift between
+// the two builds. Directory entries are left out, as in `entry_hashes`.
+fn migration_entries(from: &[ZipEntry], to: Vec<ZipEntry>) -> (Vec<ZipEntry>, TemplateDrift) {
+    let drift = compare(&entry_hashes(from), &entry_hashes(&to));
+    let wanted = drift
+        .added
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .chain(drift.changed.iter().map(|entry| entry.name.as_str()))
+        .collect::<std::collections::HashSet<_>>();
+    let entries = to.into_iter().filter(|entry| wanted.contains(entry.name.as_str())).collect();
+    (entries, drift)
+}
+
+// Build a "migration pack": only the files that differ between two templates'
+// output for the same request, filled, plus a manifest of added, changed and
+// removed files. Both builds share one secret key, id and timestamp, so only
+// real template differences show up.
+#[utoipa::path(
+    post,
+    path = "/migration-pack",
+    tag = "templates",
+    params(MigrationParams),
+    request_body(content(
+        (UserInfo = "application/json"),
+        (UserInfo = "application/x-www-form-urlencoded"),
+        (UserInfo = "application/json5"),
+        (UserInfo = "application/yaml"),
+    )),
+    responses(
+        (status = 200, description = "Zip of the new and changed files with zerohub-migration.json", content_type = "application/zip"),
+        (status = 204, description = "Both templates produce the same files"),
+        (status = 400, description = "Invalid request for either template", body = ErrorBody),
+        (status = 404, description = "Unknown template", body = ErrorBody),
+    )
+)]
+pub async fn migration_pack(
+    State(state): State<AppState>,
+    Query(params): Query<MigrationParams>,
+    request_info: RequestInfo,
+    JsonOrForm(user_info): JsonOrForm<UserInfo>,
+) -> Result<Response, AppError> {
+    println!("[DEBUG] Received migration pack request from {} to {}", params.from, params.to);
+    // Both from one snapshot, so a reload can't land between them
+    let cache = state.templates.snapshot();
+    let from_template = cache.get(&params.from)?;
+    let to_template = cache.get(&params.to)?;
+
+    let settings = state.settings.clone();
+    let generated = tokio::task::spawn_blocking(move || {
+        let (to_data, build_options) = prepare_project(&to_template, user_info.clone(), &settings, &request_info)?;
+        let (mut from_data, from_options) = prepare_project(&from_template, user_info, &settings, &request_info)?;
+        from_data.secret_key = to_data.secret_key.clone();
+        from_data.generated_id = to_data.generated_id.clone();
+        from_data.timestamp = to_data.timestamp.clone();
+
+        let from_entries = collect_entries(&from_template, &from_data, &from_options)?;
+        let to_entries = collect_entries(&to_template, &to_data, &build_options)?;
+        let (mut entries, drift) = migration_entries(&from_entries, to_entries);
+        if !drift.added.is_empty() || !drift.changed.is_empty() || !drift.removed.is_empty() {
+            let manifest = serde_json::json!({
+                "from": from_template.id,
+                "from_version": from_template.version(),
+                "to": to_template.id,
+                "to_version": to_template.version(),
+                "added": drift.added,
+                "changed": drift.changed,
+                "removed": drift.removed,
+            });
+            let content = serde_json::to_vec_pretty(&manifest).map_err(|e| AppError::Internal(e.to_string()))?;
+            entries.push(ZipEntry { name: MIGRATION_MANIFEST_ENTRY.to_string(), content, unix_mode: None, substitutions: 0 });
+        }
+        let filename = project_filename(&to_template, &to_data);
+        Ok::<_, AppError>(GeneratedZip {
+            filename: format!("{}-migration.zip", filename.trim_end_matches(".zip")),
+            bytes: write_zip(&entries, &build_options)?,
+            template_version: to_template.version().map(str::to_string),
+            entry_count: entries.len(),
+            substitutions: Vec::new(),
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    println!("[DEBUG] Migration pack created: {}, {} entries", generated.filename, generated.entry_count);
+    Ok(zip_response(&state.settings, generated, params.encoding, false, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hashes(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries.iter().map(|(name, hash)| (name.to_string(), hash.to_string())).collect()
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_files() {
+        let old = hashes(&[("README.md", "aa"), ("setup.py", "bb"), ("LICENSE", "cc")]);
+        let current = hashes(&[("README.md", "aa"), ("pyproject.toml", "dd"), ("LICENSE", "ee")]);
+        let drift = compare(&old, &current);
+
+        assert_eq!(drift.added, [DriftEntry { name: "pyproject.toml".to_string(), sha256: "dd".to_string() }]);
+        assert_eq!(drift.removed, [DriftEntry { name: "setup.py".to_string(), sha256: "bb".to_string() }]);
+        assert_eq!(drift.changed.len(), 1);
+        assert_eq!((drift.changed[0].old_sha256.as_str(), drift.changed[0].new_sha256.as_str()), ("cc", "ee"));
+        assert_eq!(drift.unchanged, 1);
+    }
+
+    #[test]
+    fn migration_pack_keeps_only_new_and_changed_files() {
+        let entry = |name: &str, content: &str| ZipEntry { name: name.to_string(), content: content.into(), unix_mode: None, substitutions: 0 };
+        let from = [entry("README.md", "old"), entry("setup.py", "x"), entry("LICENSE", "mit")];
+        let to = vec![entry("README.md", "new"), entry("pyproject.toml", "y"), entry("LICENSE", "mit"), entry("src/", "")];
+
+        let (entries, drift) = migration_entries(&from, to);
+        let names = entries.iter().map(|entry| entry.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["README.md", "pyproject.toml"]);
+        assert_eq!(drift.removed, [DriftEntry { name: "setup.py".to_string(), sha256: sha256_hex(b"x") }]);
+    }
+}
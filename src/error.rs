@@ -0,0 +1,171 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std::fmt;
+use utoipa::ToSchema;
+
+use crate::cache_headers::NO_STORE;
+use crate::throttle::Throttle;
+use crate::validation::{FieldError, ValidationError};
+
+// Errors surfaced by the HTTP handlers. Every variant maps to a status code and
+// a stable machine-readable `code` in the JSON body. Clone so a shared
+// generation can hand its error to every request waiting on it.
+#[derive(Debug, Clone)]
+pub enum AppError {
+    BadRequest(String),
+    // Request fields failed validation; each problem is listed in `fields`
+    Validation(ValidationError),
+    Unauthorized(String),
+    NotFound(String),
+    // An upload or its decompressed contents exceed a configured limit
+    PayloadTooLarge(String),
+    // Well-formed, but nothing useful can come of it, e.g. an empty project
+    // from a strict template
+    Unprocessable(String),
+    // The template's base archive could not be opened or read as a zip
+    BaseArchiveCorrupt { template_id: String, reason: String },
+    // Turned away for now, by rate limiting, a full queue or maintenance;
+    // clients are told to retry after this many seconds
+    Throttled { reason: Throttle, message: String, retry_after_secs: u64 },
+    Internal(String),
+}
+
+// JSON body of every error response. Also stored in the response extensions,
+// where `problem::problem_json` picks it up for problem+json clients.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: String,
+    // Stable machine-readable code, e.g. "BAD_REQUEST"
+    pub code: &'static str,
+    // Set for BASE_ARCHIVE_CORRUPT
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_id: Option<String>,
+    // Set for VALIDATION_FAILED
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<FieldError>>,
+    // Set for throttled requests: the `Retry-After` seconds, and the same as
+    // an RFC 3339 time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_at: Option<String>,
+}
+
+impl AppError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) | AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Throttled { reason, .. } => reason.status(),
+            AppError::BaseArchiveCorrupt { .. } | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "BAD_REQUEST",
+            AppError::Validation(_) => "VALIDATION_FAILED",
+            AppError::Unauthorized(_) => "UNAUTHORIZED",
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::PayloadTooLarge(_) => "PAYLOAD_TOO_LARGE",
+            AppError::Unprocessable(_) => "UNPROCESSABLE",
+            AppError::BaseArchiveCorrupt { .. } => "BASE_ARCHIVE_CORRUPT",
+            AppError::Throttled { reason, .. } => reason.code(),
+            AppError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    // Prefix the message of an internal error, keeping the variant and code
+    pub fn context(self, prefix: &str) -> Self {
+        match self {
+            AppError::Internal(msg) => AppError::Internal(format!("{}: {}", prefix, msg)),
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::BadRequest(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::NotFound(msg)
+            | AppError::PayloadTooLarge(msg)
+            | AppError::Unprocessable(msg)
+            | AppError::Throttled { message: msg, .. }
+            | AppError::Internal(msg) => write!(f, "{}", msg),
+            AppError::Validation(errors) => write!(f, "{}", errors),
+            AppError::BaseArchiveCorrupt { template_id, reason } => {
+                write!(f, "Base archive for template '{}' is corrupt: {}", template_id, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for AppError {
+    fn from(e: zip::result::ZipError) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for AppError {
+    fn from(e: Box<dyn std::error::Error>) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+impl From<String> for AppError {
+    fn from(msg: String) -> Self {
+        AppError::Internal(msg)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let template_id = match &self {
+            AppError::BaseArchiveCorrupt { template_id, .. } => Some(template_id.clone()),
+            _ => None,
+        };
+        let fields = match &self {
+            AppError::Validation(errors) => Some(errors.fields.clone()),
+            _ => None,
+        };
+        let retry_after_secs = match &self {
+            AppError::Throttled { retry_after_secs, .. } => Some(*retry_after_secs),
+            _ => None,
+        };
+        let body = ErrorBody {
+            error: self.to_string(),
+            code: self.code(),
+            template_id,
+            fields,
+            retry_after_secs,
+            // Left out rather than panicking should the wait not fit a timestamp
+            retry_at: retry_after_secs
+                .and_then(|secs| chrono::TimeDelta::try_seconds(i64::try_from(secs).ok()?))
+                .and_then(|wait| chrono::Utc::now().checked_add_signed(wait))
+                .map(|at| at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
+        };
+        let mut response = (self.status(), [(header::CACHE_CONTROL, NO_STORE)], Json(body.clone())).into_response();
+        if let Some(retry_after_secs) = retry_after_secs {
+            response.headers_mut().insert(header::RETRY_AFTER, retry_after_secs.into());
+        }
+        response.extensions_mut().insert(body);
+        response
+    }
+}
@@ -0,0 +1,205 @@
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{ConnectInfo, FromRequest, FromRequestParts, Request},
+    http::{header, request::Parts},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use crate::error::AppError;
+
+// Content type selecting the relaxed JSON5 syntax (comments, trailing commas)
+pub const JSON5_CONTENT_TYPE: &str = "application/json5";
+// Content types read as YAML, for pipelines that assemble requests that way.
+// The first is the registered one; the others are still common.
+pub const YAML_CONTENT_TYPES: [&str; 3] = ["application/yaml", "application/x-yaml", "text/yaml"];
+
+// Request body accepted as JSON, as a urlencoded HTML form, so a plain
+// `<form method="post">` works without JavaScript, as hand-written JSON5, or
+// as YAML. See `form` for how repeated form keys are decoded. Every format is
+// read through `Bytes`, so the body size limits apply to all of them.
+pub struct JsonOrForm<T>(pub T);
+
+enum BodyFormat {
+    Json,
+    Json5,
+    Yaml,
+    Form,
+}
+
+fn body_format(request: &Request) -> BodyFormat {
+    let essence = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<mime::Mime>().ok())
+        .map(|m| m.essence_str().to_string());
+    match essence.as_deref() {
+        Some(JSON5_CONTENT_TYPE) => BodyFormat::Json5,
+        Some(e) if YAML_CONTENT_TYPES.contains(&e) => BodyFormat::Yaml,
+        Some(e) if e == mime::APPLICATION_WWW_FORM_URLENCODED.essence_str() => BodyFormat::Form,
+        // Strict JSON stays the default, including its content type check
+        _ => BodyFormat::Json,
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonOrForm<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    // Keep each extractor's own rejection, e.g. 415 for an unsupported content type
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match body_format(&request) {
+            BodyFormat::Form => {
+                let bytes = Bytes::from_request(request, state)
+                    .await
+                    .map_err(IntoResponse::into_response)?;
+                let value = crate::form::from_bytes(&bytes)
+                    .map_err(|e| AppError::BadRequest(format!("Invalid form body: {}", e)).into_response())?;
+                Ok(JsonOrForm(value))
+            }
+            BodyFormat::Json5 => {
+                let bytes = Bytes::from_request(request, state)
+                    .await
+                    .map_err(IntoResponse::into_response)?;
+                let source = std::str::from_utf8(&bytes)
+                    .map_err(|e| AppError::BadRequest(format!("JSON5 body is not valid UTF-8: {}", e)).into_response())?;
+                let value = json5::from_str(source)
+                    .map_err(|e| AppError::BadRequest(format!("Invalid JSON5 body: {}", e)).into_response())?;
+                Ok(JsonOrForm(value))
+            }
+            BodyFormat::Yaml => {
+                let bytes = Bytes::from_request(request, state)
+                    .await
+                    .map_err(IntoResponse::into_response)?;
+                let value = serde_yaml::from_slice(&bytes)
+                    .map_err(|e| AppError::BadRequest(format!("Invalid YAML body: {}", e)).into_response())?;
+                Ok(JsonOrForm(value))
+            }
+            BodyFormat::Json => {
+                let Json(value) = Json::<T>::from_request(request, state)
+                    .await
+                    .map_err(IntoResponse::into_response)?;
+                Ok(JsonOrForm(value))
+            }
+        }
+    }
+}
+
+// Server-derived facts about the request, for templates that opt in to the
+// `{{client_ip}}` and `{{user_agent}}` placeholders. The IP is the peer
+// address of the connection; forwarding headers are not trusted.
+#[derive(Debug, Clone, Default)]
+pub struct RequestInfo {
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // Absent when the server isn't run with connect info, e.g. in tests
+        let client_ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string());
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        Ok(RequestInfo { client_ip, user_agent })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+
+    async fn extract(content_type: &str, body: &'static str) -> Result<crate::UserInfo, StatusCode> {
+        let request = Request::post("/")
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap();
+        JsonOrForm::<crate::UserInfo>::from_request(request, &())
+            .await
+            .map(|JsonOrForm(user_info)| user_info)
+            .map_err(|r| r.status())
+    }
+
+    #[tokio::test]
+    async fn accepts_json_and_urlencoded_bodies() {
+        let from_json = extract("application/json", r#"{"username":"alice","email":"a@b.c","project_name":"Demo","project_description":"d"}"#)
+            .await
+            .unwrap();
+        let from_form = extract(
+            "application/x-www-form-urlencoded; charset=UTF-8",
+            "username=alice&email=a%40b.c&project_name=Demo&project_description=d&deterministic=true",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(from_form.username, from_json.username);
+        assert_eq!(from_form.email, "a@b.c");
+        assert!(from_form.deterministic);
+        assert_eq!(extract("text/plain", "username=alice").await.unwrap_err(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn accepts_json5_with_comments_and_trailing_commas() {
+        let body = r#"{
+            // hand-written request
+            username: 'alice',
+            email: "a@b.c",
+            project_name: "Demo",
+            project_description: "d",
+            exclude: ["docs/**",],
+        }"#;
+        let user_info = extract(JSON5_CONTENT_TYPE, body).await.unwrap();
+        assert_eq!(user_info.username, "alice");
+        assert_eq!(user_info.exclude, ["docs/**"]);
+
+        // The relaxed syntax is only accepted when asked for
+        assert_eq!(extract("application/json", body).await.unwrap_err(), StatusCode::BAD_REQUEST);
+        assert_eq!(extract(JSON5_CONTENT_TYPE, "{ username: }").await.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn accepts_yaml_bodies_under_each_content_type() {
+        let body = "username: alice\nemail: a@b.c\nproject_name: Demo\nproject_description: |\n  Two\n  lines\nexclude:\n  - docs/**\n";
+        for content_type in YAML_CONTENT_TYPES {
+            let user_info = extract(content_type, body).await.unwrap();
+            assert_eq!(user_info.project_description, "Two\nlines");
+            assert_eq!(user_info.exclude, ["docs/**"]);
+        }
+        assert_eq!(extract("application/yaml", "username: [").await.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn request_info_reads_peer_address_and_user_agent() {
+        let mut request = Request::get("/").header(header::USER_AGENT, "curl/8.0").body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([203, 0, 113, 7], 51000))));
+        let (mut parts, _) = request.into_parts();
+
+        let info = RequestInfo::from_request_parts(&mut parts, &()).await.unwrap();
+        assert_eq!(info.client_ip.as_deref(), Some("203.0.113.7"));
+        assert_eq!(info.user_agent.as_deref(), Some("curl/8.0"));
+    }
+}
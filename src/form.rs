@@ -0,0 +1,165 @@
+use serde::de::{
+    self,
+    value::{Error, MapDeserializer, SeqDeserializer},
+    DeserializeOwned, Deserializer, IntoDeserializer, Visitor,
+};
+use serde::forward_to_deserialize_any;
+use std::collections::{BTreeMap, HashMap};
+
+// Decoding of urlencoded form bodies, with fixed rules for repeated keys:
+// - scalar fields take the last value sent (`project_name=a&project_name=b` is "b")
+// - list fields collect every value in order (`exclude=a&exclude=b`)
+// - `field[key]=value` pairs build a map such as `extra`, last value per key
+// Checkbox values "on" and "true" are true, "off" and "false" are false.
+
+enum FormValue {
+    Values(Vec<String>),
+    Map(BTreeMap<String, String>),
+}
+
+impl FormValue {
+    fn last(self) -> Result<String, Error> {
+        match self {
+            FormValue::Values(mut values) => Ok(values.pop().unwrap_or_default()),
+            FormValue::Map(_) => Err(de::Error::custom("expected a single value, got a map")),
+        }
+    }
+}
+
+// `extra[license]` is entry `license` of field `extra`
+fn split_map_key(key: &str) -> Option<(&str, &str)> {
+    let (field, rest) = key.split_once('[')?;
+    Some((field, rest.strip_suffix(']')?))
+}
+
+// Parse a form body into fields in first-seen order, grouping repeated keys
+fn parse_fields(body: &[u8]) -> Result<Vec<(String, FormValue)>, Error> {
+    let mut fields: Vec<(String, FormValue)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for (key, value) in form_urlencoded::parse(body) {
+        let (field, map_key) = match split_map_key(&key) {
+            Some((field, map_key)) => (field.to_string(), Some(map_key.to_string())),
+            None => (key.into_owned(), None),
+        };
+        let position = *positions.entry(field.clone()).or_insert_with(|| {
+            let empty = if map_key.is_some() { FormValue::Map(BTreeMap::new()) } else { FormValue::Values(Vec::new()) };
+            fields.push((field.clone(), empty));
+            fields.len() - 1
+        });
+        match (&mut fields[position].1, map_key) {
+            (FormValue::Values(values), None) => values.push(value.into_owned()),
+            (FormValue::Map(map), Some(map_key)) => {
+                map.insert(map_key, value.into_owned());
+            }
+            _ => return Err(de::Error::custom(format!("`{}` is sent both as a value and as a map", field))),
+        }
+    }
+    Ok(fields)
+}
+
+pub fn from_bytes<T: DeserializeOwned>(body: &[u8]) -> Result<T, Error> {
+    T::deserialize(MapDeserializer::new(parse_fields(body)?.into_iter()))
+}
+
+impl<'de> IntoDeserializer<'de, Error> for FormValue {
+    type Deserializer = FormValue;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                let value = self.last()?;
+                match value.trim().parse() {
+                    Ok(parsed) => visitor.$visit(parsed),
+                    Err(_) => Err(de::Error::custom(format!("invalid number: {:?}", value))),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for FormValue {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            FormValue::Map(map) => MapDeserializer::new(map.into_iter()).deserialize_any(visitor),
+            values => visitor.visit_string(values.last()?),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.last()?.as_str() {
+            "true" | "on" => visitor.visit_bool(true),
+            "false" | "off" => visitor.visit_bool(false),
+            other => Err(de::Error::custom(format!("invalid boolean: {:?}", other))),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8, deserialize_i16 => visit_i16, deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64, deserialize_u8 => visit_u8, deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32, deserialize_u64 => visit_u64, deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            FormValue::Values(values) => SeqDeserializer::new(values.into_iter()).deserialize_any(visitor),
+            FormValue::Map(_) => Err(de::Error::custom("expected a list, got a map")),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.last()?.into_deserializer().deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UserInfo;
+
+    #[test]
+    fn repeated_keys_follow_the_documented_rules() {
+        let body = b"username=alice&email=a%40b.c&project_name=First&project_name=Second&project_description=d\
+            &exclude=docs%2F**&exclude=tests%2F**&extra%5Blicense%5D=MIT&extra%5Blicense%5D=BSD&extra%5Bteam%5D=core\
+            &deterministic=on&line_endings=crlf";
+        let user_info: UserInfo = from_bytes(body).unwrap();
+
+        assert_eq!(user_info.project_name, "Second");
+        assert_eq!(user_info.exclude, ["docs/**", "tests/**"]);
+        assert_eq!(user_info.extra["license"], "BSD");
+        assert_eq!(user_info.extra["team"], "core");
+        assert!(user_info.deterministic);
+        assert_eq!(user_info.line_endings, Some(crate::builder::LineEndings::Crlf));
+    }
+
+    #[test]
+    fn mixing_value_and_map_forms_is_rejected() {
+        assert!(from_bytes::<UserInfo>(b"username=a&extra=x&extra%5Bk%5D=v").is_err());
+        assert!(from_bytes::<UserInfo>(b"username=a&deterministic=maybe").is_err());
+    }
+}
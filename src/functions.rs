@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use crate::ProjectNameCases;
+
+// What a placeholder function can see while a file is being filled: the
+// values of the other placeholders and when the generation started
+pub struct FunctionContext<'a> {
+    pub placeholders: &'a [(&'a str, &'a str)],
+    pub generated_at: DateTime<Utc>,
+}
+
+impl FunctionContext<'_> {
+    // Value of a plain placeholder by name, e.g. "project_name"
+    pub fn value(&self, name: &str) -> Option<&str> {
+        let token = format!("{{{{{}}}}}", name);
+        self.placeholders.iter().find(|(t, _)| *t == token).map(|(_, value)| *value)
+    }
+}
+
+// Gets the text after the colon of `{{name:argument}}`; `None` leaves the
+// token as written
+type PlaceholderFn = Box<dyn Fn(&str, &FunctionContext) -> Option<String> + Send + Sync>;
+
+// Functions usable as `{{name:argument}}` placeholders
+#[derive(Default)]
+pub struct PlaceholderFunctions {
+    functions: BTreeMap<&'static str, PlaceholderFn>,
+}
+
+impl PlaceholderFunctions {
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        function: impl Fn(&str, &FunctionContext) -> Option<String> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.functions.insert(name, Box::new(function));
+        self
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    // Evaluate the inside of a `{{...}}` token. `None` when it isn't a call,
+    // names an unknown function, or the function has no value for it.
+    pub fn call(&self, token: &str, context: &FunctionContext) -> Option<String> {
+        let (name, argument) = token.split_once(':')?;
+        self.functions.get(name)?(argument, context)
+    }
+}
+
+// Apply one of the `project_name` casings to any placeholder's value
+fn cased(case: fn(ProjectNameCases) -> String) -> impl Fn(&str, &FunctionContext) -> Option<String> {
+    move |name, context| context.value(name).map(|value| case(ProjectNameCases::of(value)))
+}
+
+// The generation time in a strftime format, e.g. `{{date:%Y}}`
+fn date(format: &str, context: &FunctionContext) -> Option<String> {
+    use std::fmt::Write;
+
+    let mut formatted = String::new();
+    write!(formatted, "{}", context.generated_at.format(format)).ok()?;
+    Some(formatted)
+}
+
+// The functions every template can use
+pub fn builtin() -> &'static PlaceholderFunctions {
+    static BUILTIN: OnceLock<PlaceholderFunctions> = OnceLock::new();
+    BUILTIN.get_or_init(|| {
+        let mut functions = PlaceholderFunctions::default();
+        functions
+            .register("upper", cased(|cases| cases.upper))
+            .register("lower", cased(|cases| cases.lower))
+            .register("kebab", cased(|cases| cases.kebab))
+            .register("snake", cased(|cases| cases.snake))
+            .register("title", cased(|cases| cases.title))
+            .register("date", date);
+        functions
+    })
+}
+
+// Names of the functions called in a text that `functions` doesn't know.
+// Arguments are not checked, only that `{{name:...}}` names a function.
+pub fn unknown_functions(content: &str, functions: &PlaceholderFunctions) -> Vec<String> {
+    let mut unknown = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find("{{") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find("}}") else { break };
+        if let Some((name, _)) = rest[..end].split_once(':') {
+            let is_name = !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '_');
+            if is_name && !functions.contains(name) && !unknown.iter().any(|n| n == name) {
+                unknown.push(name.to_string());
+            }
+        }
+    }
+    unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn builtin_functions_transform_values_and_format_dates() {
+        let placeholders = [("{{project_name}}", "My App"), ("{{username}}", "alice")];
+        let context = FunctionContext {
+            placeholders: &placeholders,
+            generated_at: Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap(),
+        };
+        let functions = builtin();
+        assert_eq!(functions.call("upper:username", &context).as_deref(), Some("ALICE"));
+        assert_eq!(functions.call("kebab:project_name", &context).as_deref(), Some("my-app"));
+        assert_eq!(functions.call("date:%Y-%m", &context).as_deref(), Some("2026-03"));
+        assert_eq!(functions.call("upper:missing", &context), None);
+        assert_eq!(functions.call("reverse:username", &context), None);
+        assert_eq!(functions.call("username", &context), None);
+    }
+
+    #[test]
+    fn custom_functions_can_be_registered() {
+        let mut functions = PlaceholderFunctions::default();
+        functions.register("repeat", |argument, _| Some(argument.repeat(2)));
+        let context = FunctionContext { placeholders: &[], generated_at: Utc::now() };
+        assert_eq!(functions.call("repeat:ab", &context).as_deref(), Some("abab"));
+        assert_eq!(unknown_functions("{{repeat:x}} {{upper:y}} {{upper:z}} {{a:b: c}}", &functions), ["upper", "a"]);
+    }
+}
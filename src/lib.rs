@@ -0,0 +1,2016 @@
+use axum::{
+    extract::{DefaultBodyLimit, Json, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware,
+    response::{Html, IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tower::ServiceBuilder;
+use tower_http::{
+    decompression::RequestDecompressionLayer, limit::RequestBodyLimitLayer, services::ServeDir,
+    trace::TraceLayer,
+};
+use unicode_normalization::UnicodeNormalization;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+mod admin;
+mod base_formats;
+mod batch;
+mod blocklist;
+pub mod builder;
+mod cache_headers;
+mod coalesce;
+mod cors;
+mod custom_base;
+#[cfg(feature = "debug-delay")]
+mod debug_delay;
+mod downloads;
+mod drift;
+mod error;
+mod extract;
+mod form;
+mod functions;
+mod licenses;
+mod links;
+mod listener;
+mod listing;
+mod local_write;
+mod log_level;
+mod maintenance;
+mod nested;
+mod openapi;
+mod partial;
+mod prefill;
+mod problem;
+mod profiles;
+mod render;
+#[cfg(feature = "s3")]
+mod s3_upload;
+pub mod settings;
+mod signing;
+mod static_mounts;
+mod strict_startup;
+mod template_config;
+mod temp_files;
+mod throttle;
+pub mod templates;
+mod tls;
+mod tree;
+mod validation;
+mod watch;
+
+use builder::{collect_entries, open_base_archive, write_zip, BuildOptions, LineEndings, ZipEntry, BUILTIN_PLACEHOLDERS};
+use error::{AppError, ErrorBody};
+use extract::{JsonOrForm, RequestInfo};
+use cache_headers::{etag_for, is_not_modified, HTML_CACHE_CONTROL, NO_STORE};
+use downloads::DownloadStore;
+use settings::Settings;
+use templates::{FailedTemplate, LoadedTemplate, PlaceholderCoverage, TemplateCache, TemplateInfo, TemplateRegistry, TemplateStats};
+
+// State shared by all handlers
+#[derive(Clone)]
+pub struct AppState {
+    pub templates: Arc<TemplateRegistry>,
+    pub settings: Arc<Settings>,
+    // One-time downloads handed out by token, e.g. streamed batch results
+    pub downloads: Arc<DownloadStore>,
+    pub maintenance: Arc<maintenance::Maintenance>,
+    // Rate and concurrency limits on the generate endpoints
+    pub throttles: Arc<throttle::Throttles>,
+    // Identical generations in flight, built once and shared, see `coalesce`
+    pub coalescer: Arc<coalesce::Coalescer>,
+}
+
+impl AppState {
+    // Grab a template from the current cache snapshot
+    fn template(&self, id: &str) -> Result<Arc<LoadedTemplate>, AppError> {
+        self.templates.snapshot().get(id)
+    }
+}
+
+// A generation request. Every string field, apart from `extra`, is trimmed of
+// surrounding whitespace as it is read; lengths are checked by `validation`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, ToSchema)]
+pub struct UserInfo {
+    #[serde(deserialize_with = "trimmed")]
+    pub username: String,
+    #[serde(deserialize_with = "trimmed")]
+    pub email: String,
+    #[serde(deserialize_with = "trimmed")]
+    pub project_name: String,
+    // May come from a profile instead; validation still requires it
+    #[serde(default, deserialize_with = "trimmed")]
+    pub project_description: String,
+    // Named preset from `ZEROHUB_PROFILES_FILE` supplying defaults, see `profiles`
+    #[serde(default, deserialize_with = "trimmed_option")]
+    pub profile: Option<String>,
+    // Machine-friendly name; derived from project_name when omitted
+    #[serde(default, deserialize_with = "trimmed_option")]
+    pub project_slug: Option<String>,
+    // Glob patterns of base archive entries to leave out, e.g. "src/tests/**"
+    #[serde(default, deserialize_with = "trimmed_list")]
+    pub exclude: Vec<String>,
+    // Pin timestamps and sort entries so identical input gives identical bytes.
+    // Templates using `{{secret_key}}` still differ between runs by design.
+    #[serde(default)]
+    pub deterministic: bool,
+    // Write entries sorted by name; defaults to on in deterministic mode
+    #[serde(default)]
+    pub sort_entries: Option<bool>,
+    // Dependency set for templates that offer several, e.g. "react", "vue", "svelte"
+    #[serde(default, deserialize_with = "trimmed_option")]
+    pub framework: Option<String>,
+    // Add a SHA256SUMS entry listing every other entry's hash
+    #[serde(default)]
+    pub include_checksums: bool,
+    // "lf" or "crlf" to normalize filled text files; omitted keeps them as they are
+    #[serde(default)]
+    pub line_endings: Option<LineEndings>,
+    // Add a zerohub-provenance.json entry naming the template and its version
+    #[serde(default)]
+    pub include_provenance: bool,
+    // Add a GETTING_STARTED.md with the template's next steps for the project
+    #[serde(default)]
+    pub include_getting_started: bool,
+    // Add a .github/FUNDING.yml from the funding handles in `extra`, e.g.
+    // `github_sponsor`, `patreon` or `funding_url`; left out when none is set.
+    // See `builder::FUNDING_PLATFORMS`.
+    #[serde(default)]
+    pub include_funding: bool,
+    // 2 or 4 to re-indent every generated `.json` file that parses; omitted
+    // keeps them as the template wrote them
+    #[serde(default)]
+    pub json_indent: Option<u8>,
+    // Rewrite every generated `.json` file that parses with its object keys
+    // sorted, for diff-friendly output; indented by `json_indent`, else 2
+    #[serde(default)]
+    pub canonicalize_json: bool,
+    // Prepend a "Generated by ZeroHub on <timestamp>" comment to filled files
+    // whose type has a line comment syntax; deterministic builds leave the
+    // timestamp out
+    #[serde(default)]
+    pub include_banner: bool,
+    // Text for every `{{placeholder}}` left without a value, e.g. "" or "TODO";
+    // omitted leaves them as written. Ignored by templates with `strict_extra`.
+    #[serde(default, deserialize_with = "trimmed_option")]
+    pub default_placeholder_value: Option<String>,
+    // Compression for entries the template has no `[compression]` rule for:
+    // "stored", "deflated" (the default) or, in builds with the bzip2 and zstd
+    // features, "bzip2" and "zstd". zstd zips are smaller but not every unzip
+    // tool opens them, see `builder::COMPRESSION_METHODS`.
+    #[serde(default, deserialize_with = "trimmed_option")]
+    pub compression: Option<String>,
+    // Archive comment shown by `unzip -l`, e.g. "Generated by ZeroHub for
+    // {{project_name}}"; placeholders are filled. Overrides the template's
+    // `zip_comment`; without either the zip has no comment.
+    #[serde(default, deserialize_with = "trimmed_option")]
+    pub zip_comment: Option<String>,
+    // Values for the template's custom placeholders, keyed by placeholder name.
+    // Only names the template config allows are used. Nested objects are
+    // flattened to dotted keys: `{"author": {"name": "Ada"}}` fills `{{author.name}}`.
+    #[serde(default, deserialize_with = "deserialize_extra")]
+    #[schema(value_type = BTreeMap<String, Object>)]
+    pub extra: BTreeMap<String, String>,
+}
+
+// Flatten one `extra` value under a dotted key. Strings, numbers and booleans
+// are leaves; arrays and nulls have no placeholder form.
+fn flatten_extra(key: String, value: serde_json::Value, flat: &mut BTreeMap<String, String>) -> Result<(), String> {
+    let leaf = match value {
+        serde_json::Value::Object(map) => {
+            for (child, value) in map {
+                let child_key = if key.is_empty() { child } else { format!("{}.{}", key, child) };
+                flatten_extra(child_key, value, flat)?;
+            }
+            return Ok(());
+        }
+        serde_json::Value::String(value) => value,
+        serde_json::Value::Number(value) => value.to_string(),
+        serde_json::Value::Bool(value) => value.to_string(),
+        serde_json::Value::Null | serde_json::Value::Array(_) => {
+            return Err(format!("extra.{} must be a string, number, boolean or object", key));
+        }
+    };
+    flat.insert(key, leaf);
+    Ok(())
+}
+
+fn trimmed<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(String::deserialize(deserializer)?.trim().to_string())
+}
+
+fn trimmed_option<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.map(|value| value.trim().to_string()))
+}
+
+fn trimmed_list<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Ok(Vec::<String>::deserialize(deserializer)?.into_iter().map(|value| value.trim().to_string()).collect())
+}
+
+fn deserialize_extra<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error> {
+    let map = serde_json::Map::deserialize(deserializer)?;
+    let mut flat = BTreeMap::new();
+    flatten_extra(String::new(), serde_json::Value::Object(map), &mut flat).map_err(serde::de::Error::custom)?;
+    Ok(flat)
+}
+
+impl UserInfo {
+    // Check every field, including the ones the template lists as `required`,
+    // reporting all problems at once; see `validation`
+    pub fn validate(&self, settings: &Settings, template: &LoadedTemplate) -> Result<(), AppError> {
+        validation::validate_user_info(self, settings, &template.config.required).map_err(AppError::Validation)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateData {
+    pub username: String,
+    pub email: String,
+    pub project_name: String,
+    pub project_description: String,
+    pub project_slug: String,
+    // Casings of `project_name`, see `ProjectNameCases`
+    pub project_name_cases: ProjectNameCases,
+    // Fresh per generation and never stored; see `generate_secret_key`
+    #[serde(skip_serializing)]
+    pub secret_key: String,
+    // Rendered `{{dependencies}}` JSON, set for templates with frameworks
+    pub dependencies: Option<String>,
+    // Allowlisted (`{{token}}`, value) pairs from the request's `extra`
+    pub extra: Vec<(String, String)>,
+    // Only set for templates with `request_placeholders`; never serialized
+    #[serde(skip_serializing)]
+    pub client_ip: Option<String>,
+    #[serde(skip_serializing)]
+    pub user_agent: Option<String>,
+    pub generated_id: String,
+    pub timestamp: String,
+    // What `{{date:...}}` placeholders format
+    #[serde(skip_serializing)]
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    // Filled in for unresolved placeholders, see `UserInfo::default_placeholder_value`
+    #[serde(skip_serializing)]
+    pub default_placeholder_value: Option<String>,
+}
+
+impl From<UserInfo> for TemplateData {
+    fn from(user_info: UserInfo) -> Self {
+        TemplateData::new(user_info, true)
+    }
+}
+
+// NFC form of a value, so a composed "é" and "e" + combining accent fill
+// templates, slugs and filenames identically
+fn nfc(value: String) -> String {
+    if unicode_normalization::is_nfc(&value) {
+        value
+    } else {
+        value.nfc().collect()
+    }
+}
+
+impl TemplateData {
+    // Values are NFC-normalized first when `normalize_unicode` is set, see
+    // `Settings::normalize_unicode`
+    pub fn new(mut user_info: UserInfo, normalize_unicode: bool) -> Self {
+        if normalize_unicode {
+            user_info.username = nfc(user_info.username);
+            user_info.email = nfc(user_info.email);
+            user_info.project_name = nfc(user_info.project_name);
+            user_info.project_description = nfc(user_info.project_description);
+            user_info.project_slug = user_info.project_slug.map(nfc);
+            user_info.default_placeholder_value = user_info.default_placeholder_value.map(nfc);
+        }
+        let project_slug = user_info
+            .project_slug
+            .filter(|slug| !slug.trim().is_empty())
+            .map(|slug| slug.trim().to_string())
+            .unwrap_or_else(|| slugify(&user_info.project_name));
+        let project_name_cases = ProjectNameCases::of(&user_info.project_name);
+        TemplateData {
+            username: user_info.username,
+            email: user_info.email,
+            project_name: user_info.project_name,
+            project_description: user_info.project_description,
+            project_slug,
+            project_name_cases,
+            secret_key: generate_secret_key(),
+            dependencies: None,
+            extra: Vec::new(),
+            client_ip: None,
+            user_agent: None,
+            generated_id: Uuid::new_v4().to_string(),
+            timestamp: settings::format_timestamp(settings::DEFAULT_TIMESTAMP_FORMAT),
+            generated_at: chrono::Utc::now(),
+            default_placeholder_value: user_info.default_placeholder_value,
+        }
+    }
+}
+
+// Random value for the `{{secret_key}}` placeholder: 32 bytes from the OS CSPRNG,
+// URL-safe base64 without padding so it can be pasted into any config format.
+// A new key is produced for every generation and the server keeps no copy.
+fn generate_secret_key() -> String {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).expect("OS random number generator unavailable");
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// Kebab-case a project name: "My Cool App" -> "my-cool-app"
+fn slugify(name: &str) -> String {
+    name_words(&name.to_lowercase()).join("-")
+}
+
+// Alphanumeric runs of a name, in order
+fn name_words(name: &str) -> Vec<&str> {
+    name.split(|c: char| !c.is_alphanumeric()).filter(|part| !part.is_empty()).collect()
+}
+
+// Derived `{{project_name_*}}` tokens, so templates can pick a casing without
+// the client sending it. For "my cool-app": upper "MY COOL-APP", lower
+// "my cool-app", kebab "my-cool-app", snake "my_cool_app", title "My Cool App".
+// Kebab always follows the name, even when the request sets `project_slug`.
+#[derive(Debug, Serialize, Default)]
+pub struct ProjectNameCases {
+    pub upper: String,
+    pub lower: String,
+    pub kebab: String,
+    pub snake: String,
+    pub title: String,
+}
+
+impl ProjectNameCases {
+    pub fn of(name: &str) -> Self {
+        let lower = name.to_lowercase();
+        let title = name_words(name)
+            .iter()
+            .map(|word| {
+                let mut chars = word.chars();
+                let first = chars.next().map(|c| c.to_uppercase().collect::<String>()).unwrap_or_default();
+                first + &chars.as_str().to_lowercase()
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        ProjectNameCases {
+            upper: name.to_uppercase(),
+            kebab: name_words(&lower).join("-"),
+            snake: name_words(&lower).join("_"),
+            lower,
+            title,
+        }
+    }
+}
+
+// Check a slug against npm package naming rules, since the client template
+// uses it as the `name` field of package.json
+fn validate_npm_slug(slug: &str) -> Result<(), String> {
+    if slug.is_empty() {
+        return Err("project_slug must not be empty".to_string());
+    }
+    // The client template appends "-client", which must still fit in 214 characters
+    if slug.len() + "-client".len() > 214 {
+        return Err("project_slug is too long for an npm package name".to_string());
+    }
+    if slug.starts_with('.') || slug.starts_with('_') {
+        return Err("project_slug must not start with '.' or '_'".to_string());
+    }
+    if !slug
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '.' | '_' | '~'))
+    {
+        return Err(format!(
+            "project_slug '{}' is not a valid npm package name: use lowercase letters, digits, '-', '.', '_' or '~'",
+            slug
+        ));
+    }
+    Ok(())
+}
+
+// Health check endpoint; stays 200 in maintenance mode and with templates
+// unavailable, reporting both
+#[utoipa::path(get, path = "/health", tag = "service", responses((status = 200, description = "Service is up")))]
+async fn health(State(state): State<AppState>) -> impl IntoResponse {
+    let unavailable = state.templates.snapshot().summary().failed;
+    Json(serde_json::json!({
+        "status": if unavailable.is_empty() { "healthy" } else { "degraded" },
+        "service": "rust-template-generator",
+        "maintenance": state.maintenance.is_enabled(),
+        "unavailable_templates": unavailable,
+    }))
+}
+
+// Plaintext liveness probe for monitors that don't parse JSON; unlike
+// `/health` it checks nothing and allocates nothing
+#[utoipa::path(
+    get,
+    path = "/ping",
+    tag = "service",
+    responses((status = 200, description = "Always `pong`", body = String, content_type = "text/plain"))
+)]
+async fn ping() -> &'static str {
+    "pong"
+}
+
+// Liveness probe for orchestrators: 200 for as long as the process serves
+// requests, in maintenance mode and with templates unavailable alike
+#[utoipa::path(
+    get,
+    path = "/livez",
+    tag = "service",
+    responses((status = 200, description = "Always `ok`", body = String, content_type = "text/plain"))
+)]
+async fn livez() -> &'static str {
+    "ok"
+}
+
+// List the loaded templates with their versions and options
+#[utoipa::path(
+    get,
+    path = "/templates",
+    tag = "templates",
+    responses((status = 200, description = "Loaded templates", body = [TemplateInfo]))
+)]
+async fn list_templates(State(state): State<AppState>) -> Result<Json<Vec<TemplateInfo>>, AppError> {
+    let cache = state.templates.snapshot();
+    Ok(Json(cache.templates().map(|t| TemplateInfo::from(t.as_ref())).collect()))
+}
+
+// Base archive and placeholder footprint of one template
+#[utoipa::path(
+    get,
+    path = "/templates/{id}/stats",
+    tag = "templates",
+    params(("id" = String, Path, description = "Template id, e.g. \"server\"")),
+    responses(
+        (status = 200, description = "Template footprint", body = TemplateStats),
+        (status = 404, description = "Unknown template", body = ErrorBody),
+    )
+)]
+async fn template_stats(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<TemplateStats>, AppError> {
+    let template = state.template(&id)?;
+    Ok(Json(template.stats()?))
+}
+
+// Placeholders of one template that a request can't fill from UserInfo alone
+#[utoipa::path(
+    get,
+    path = "/templates/{id}/coverage",
+    tag = "templates",
+    params(("id" = String, Path, description = "Template id, e.g. \"server\"")),
+    responses(
+        (status = 200, description = "Placeholders by whether a request can fill them", body = PlaceholderCoverage),
+        (status = 404, description = "Unknown template", body = ErrorBody),
+    )
+)]
+async fn template_coverage(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<PlaceholderCoverage>, AppError> {
+    let template = state.template(&id)?;
+    Ok(Json(template.coverage()?))
+}
+
+// Whether the client prefers JSON over HTML, judged from the Accept header
+fn wants_json(headers: &HeaderMap) -> bool {
+    let accept = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    let mut json_q = None;
+    let mut html_q = None;
+    for item in accept.split(',') {
+        let mut parts = item.split(';');
+        let media_type = parts.next().unwrap_or("").trim();
+        let q = parts
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .find_map(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        match media_type {
+            "application/json" => json_q = Some(q),
+            "text/html" => html_q = Some(q),
+            _ => {}
+        }
+    }
+    match (json_q, html_q) {
+        (Some(json), Some(html)) => json > html,
+        (Some(json), None) => json > 0.0,
+        _ => false,
+    }
+}
+
+// Small API description returned by `/` to JSON clients
+fn api_description(base_path: &str) -> serde_json::Value {
+    let mut description = serde_json::json!({
+        "service": "zerohub",
+        "version": env!("CARGO_PKG_VERSION"),
+        "endpoints": [
+            { "method": "GET", "path": "/", "description": "HTML form, or this description for JSON clients" },
+            { "method": "GET", "path": "/health", "description": "Health check" },
+            { "method": "GET", "path": "/ping", "description": "Plaintext liveness probe returning pong" },
+            { "method": "GET", "path": "/pubkey", "description": "Public key for the X-Signature of generated zips, when signing is on" },
+            { "method": "GET", "path": "/openapi.json", "description": "OpenAPI 3 document for this API" },
+            { "method": "GET", "path": "/templates", "description": "List the available templates and their versions" },
+            { "method": "GET", "path": "/templates/{id}/stats", "description": "Entry count, sizes and placeholders of a template" },
+            { "method": "GET", "path": "/templates/{id}/coverage", "description": "Placeholders that need extra values or can't be filled" },
+            { "method": "POST", "path": "/generate", "description": "Generate from the named `profile` in the body" },
+            { "method": "POST", "path": "/generate-async?template=server", "description": "Generate and return a single-use download URL instead of the zip" },
+            { "method": "POST", "path": "/generate-links?template=server", "description": "Generate and return download URLs for the zip, a tar.gz and each file" },
+            { "method": "POST", "path": "/generate-server-zip", "description": "Generate the server scaffold zip" },
+            { "method": "POST", "path": "/generate-client-zip", "description": "Generate the client scaffold zip" },
+            { "method": "POST", "path": "/template-drift?template=server", "description": "Compare an old generated zip (multipart old_zip + user_info) with the current template" },
+            { "method": "GET", "path": "/render/{template}/{path}?username=...", "description": "One filled file, with UserInfo from the query; gzip=true compresses it" },
+            { "method": "GET", "path": "/license/{spdx_id}?username=...&year=...", "description": "One license text with the copyright holder and year filled in" },
+            { "method": "POST", "path": "/tree?template=server", "description": "Preview the generated file tree with sizes, as nested JSON" },
+            { "method": "POST", "path": "/migration-pack?from=server&to=client", "description": "Zip of only the files that differ between two templates' output" },
+            { "method": "POST", "path": "/generate-nested", "description": "Server and client zips nested inside one zip" },
+            { "method": "POST", "path": "/generate-partial", "description": "Generate only the listed `files` of a template" },
+            { "method": "POST", "path": "/generate-custom-base-zip", "description": "Generate from an uploaded base zip (multipart)" },
+            { "method": "POST", "path": "/generate-batch", "description": "Generate many projects into one zip" },
+            { "method": "POST", "path": "/generate-batch/stream", "description": "Batch generation with Server-Sent Events progress" },
+            { "method": "GET", "path": "/download/{token}", "description": "Fetch a stored result once by token" },
+            { "method": "GET", "path": "/download/{token}/{path}", "description": "Fetch one file of a project from /generate-links" },
+            { "method": "POST", "path": "/validate-template-config", "description": "Check a zerohub.toml for problems" },
+            { "method": "POST", "path": "/admin/reload", "description": "Reload templates from disk (bearer token)" },
+            { "method": "GET", "path": "/admin/cache", "description": "List stored downloads and total bytes held (bearer token)" },
+            { "method": "DELETE", "path": "/admin/cache", "description": "Clear stored downloads (bearer token)" },
+            { "method": "GET", "path": "/admin/maintenance", "description": "Show whether generation is paused (bearer token)" },
+            { "method": "PUT", "path": "/admin/maintenance", "description": "Pause or resume generation with {\"enabled\": bool} (bearer token)" },
+        ],
+    });
+    if let Some(endpoints) = description["endpoints"].as_array_mut() {
+        for endpoint in endpoints {
+            let path = format!("{}{}", base_path, endpoint["path"].as_str().unwrap_or_default());
+            endpoint["path"] = serde_json::json!(path);
+        }
+    }
+    description
+}
+
+// Serve the main form page, or the API description when JSON is requested
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "service",
+    params(
+        ("username" = Option<String>, Query, description = "Pre-fill the form; likewise `email`, `project_name`, `project_slug`, `project_description` and `framework`"),
+    ),
+    responses(
+        (status = 200, description = "HTML form, or a JSON endpoint list for `Accept: application/json`"),
+        (status = 304, description = "Form unchanged since the ETag in If-None-Match"),
+        (status = 503, description = "A page explaining which templates are unavailable, instead of the form"),
+    )
+)]
+async fn index(
+    State(state): State<AppState>,
+    query: Option<Query<BTreeMap<String, String>>>,
+    request_headers: HeaderMap,
+) -> impl IntoResponse {
+    if wants_json(&request_headers) {
+        let description = api_description(&state.settings.base_path);
+        return ([(header::VARY, "Accept")], Json(description)).into_response();
+    }
+
+    // A form whose every submission fails is worse than saying why up front
+    let unavailable = state.templates.snapshot().summary().failed;
+    if !unavailable.is_empty() {
+        return unavailable_page(&state.settings, &unavailable);
+    }
+
+    let query = query.map(|Query(query)| query).unwrap_or_default();
+    let html = prefill::prefill(include_str!("../static/index.html"), &query);
+    let etag = etag_for(html.as_bytes());
+
+    if is_not_modified(&request_headers, &etag) {
+        return (
+            StatusCode::NOT_MODIFIED,
+            [
+                (header::ETAG, etag),
+                (header::CACHE_CONTROL, HTML_CACHE_CONTROL.to_string()),
+                (header::VARY, "Accept".to_string()),
+            ],
+        )
+            .into_response();
+    }
+
+    (
+        [
+            (header::ETAG, etag),
+            (header::CACHE_CONTROL, HTML_CACHE_CONTROL.to_string()),
+            (header::VARY, "Accept".to_string()),
+        ],
+        Html(html),
+    )
+        .into_response()
+}
+
+// `/` while templates are unavailable: the configured page or the built-in
+// one, never cached so the form comes back as soon as the templates do
+fn unavailable_page(settings: &Settings, unavailable: &[FailedTemplate]) -> Response {
+    let page = settings.unavailable_page.as_deref().unwrap_or(include_str!("../static/unavailable.html"));
+    let problems = unavailable
+        .iter()
+        .map(|t| format!("<li><strong>{}</strong>: {}</li>", prefill::escape_html(&t.id), prefill::escape_html(&t.error)))
+        .collect::<String>();
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(header::CACHE_CONTROL, NO_STORE), (header::VARY, "Accept")],
+        Html(page.replace("{{problems}}", &problems)),
+    )
+        .into_response()
+}
+
+// How the generated archive is sent back to the client
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseEncoding {
+    // Raw `application/zip` attachment
+    #[default]
+    Binary,
+    // JSON `{ filename, content_base64 }` for gateways that mangle binary bodies
+    Base64,
+}
+
+// Query parameters shared by the generate endpoints
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GenerateParams {
+    #[serde(default)]
+    pub encoding: ResponseEncoding,
+    // Write the project into this directory on the server instead of returning
+    // a zip; needs `ZEROHUB_ALLOW_LOCAL_WRITE`. Single-template endpoints only.
+    pub output_dir: Option<String>,
+    // Add an X-Zip-Entries header listing the zip's entries, see `listing`
+    #[serde(default)]
+    pub list_entries: bool,
+    // Upload the zip to ZEROHUB_S3_BUCKET and answer with its location
+    // instead of the zip; needs a build with the `s3` feature
+    #[serde(default)]
+    pub upload_s3: bool,
+    // Add headers counting the placeholders replaced per file, see
+    // `listing::substitutions_headers`
+    #[serde(default)]
+    pub debug: bool,
+}
+
+// Response header naming the template revision a project was generated from
+pub const TEMPLATE_VERSION_HEADER: &str = "x-template-version";
+
+// Answer with the generated archive, or with where it was uploaded for
+// `upload_s3=true`. Empty projects get their 204 either way.
+async fn deliver(settings: &Settings, generated: GeneratedZip, params: &GenerateParams) -> Result<Response, AppError> {
+    if !params.upload_s3 || generated.entry_count == 0 {
+        return Ok(zip_response(settings, generated, params.encoding, params.list_entries, params.debug));
+    }
+    #[cfg(feature = "s3")]
+    {
+        let target = settings.s3.as_ref().ok_or_else(|| {
+            AppError::BadRequest(format!("upload_s3 is disabled; set {} to enable it", s3_upload::S3_BUCKET_ENV))
+        })?;
+        let signature = signing::sign(settings.signer.as_ref(), &generated.bytes);
+        let upload = target.upload(generated, signature).await?;
+        Ok((StatusCode::CREATED, [(header::CACHE_CONTROL, NO_STORE)], Json(upload)).into_response())
+    }
+    #[cfg(not(feature = "s3"))]
+    {
+        let _ = settings;
+        Err(AppError::BadRequest("upload_s3 is not available: built without the s3 feature".to_string()))
+    }
+}
+
+// Build the response for a generated archive in the requested encoding,
+// signed when a signing key is configured
+fn zip_response(
+    settings: &Settings,
+    generated: GeneratedZip,
+    encoding: ResponseEncoding,
+    list_entries: bool,
+    debug: bool,
+) -> Response {
+    if generated.entry_count == 0 {
+        println!("[DEBUG] {} has no entries, answering 204", generated.filename);
+        return (StatusCode::NO_CONTENT, [(header::CACHE_CONTROL, NO_STORE)]).into_response();
+    }
+    let entries_header = list_entries.then(|| listing::entries_header(&generated.bytes)).flatten();
+    let debug_headers = if debug { listing::substitutions_headers(&generated.substitutions) } else { Vec::new() };
+    let signature = signing::sign(settings.signer.as_ref(), &generated.bytes);
+    let signature_header = signing::signature_header(signature.as_deref());
+    let mut response = encoded_zip_response(&generated.filename, generated.bytes, encoding);
+    for (name, value) in entries_header.into_iter().chain(debug_headers).chain(signature_header) {
+        response.headers_mut().insert(name, value);
+    }
+    if let Some(version) = generated.template_version.and_then(|v| header::HeaderValue::from_str(&v).ok()) {
+        response
+            .headers_mut()
+            .insert(header::HeaderName::from_static(TEMPLATE_VERSION_HEADER), version);
+    }
+    response
+}
+
+fn encoded_zip_response(filename: &str, zip_data: Vec<u8>, encoding: ResponseEncoding) -> Response {
+    if encoding == ResponseEncoding::Base64 {
+        let body = serde_json::json!({
+            "filename": filename,
+            "content_base64": base64::engine::general_purpose::STANDARD.encode(&zip_data),
+        });
+        return (StatusCode::OK, [(header::CACHE_CONTROL, NO_STORE)], Json(body)).into_response();
+    }
+
+    // Use RFC 5987 encoding for international filenames
+    let encoded_filename = percent_encoding::utf8_percent_encode(
+        filename,
+        percent_encoding::NON_ALPHANUMERIC
+    ).to_string();
+
+    let headers = [
+        (header::CONTENT_TYPE, "application/zip"),
+        (header::CACHE_CONTROL, NO_STORE),
+        (header::CONTENT_DISPOSITION, &format!("attachment; filename*=UTF-8''{}", encoded_filename)),
+    ];
+
+    (StatusCode::OK, headers, zip_data).into_response()
+}
+
+// A generated archive and the filename it is offered under
+#[derive(Clone)]
+pub struct GeneratedZip {
+    pub filename: String,
+    pub bytes: Vec<u8>,
+    pub template_version: Option<String>,
+    // Files and directories in the zip; zero is answered with 204
+    pub entry_count: usize,
+    // Placeholder tokens replaced in each entry, sent with `debug=true`
+    pub substitutions: Vec<(String, usize)>,
+}
+
+// The per-entry counts a `GeneratedZip` reports
+fn substitution_counts(entries: &[ZipEntry]) -> Vec<(String, usize)> {
+    entries.iter().map(|entry| (entry.name.clone(), entry.substitutions)).collect()
+}
+
+// A build that leaves nothing (everything excluded or gated off) is a 204 for
+// most templates, but an error for strict ones
+fn check_not_empty(template: &LoadedTemplate, entries: &[ZipEntry]) -> Result<(), AppError> {
+    if entries.is_empty() && template.config.strict_extra {
+        return Err(AppError::Unprocessable(format!("The request leaves the {} template with no files", template.id)));
+    }
+    Ok(())
+}
+
+// Validate a request against a template's rules and turn it into the data
+// and options the builder needs
+fn prepare_project(
+    template: &LoadedTemplate,
+    user_info: UserInfo,
+    settings: &Settings,
+    request: &RequestInfo,
+) -> Result<(TemplateData, BuildOptions), AppError> {
+    let mut user_info = settings.profiles.apply(&template.id, user_info)?;
+    user_info.validate(settings, template)?;
+    let mut build_options = BuildOptions::from_user_info(&user_info)?;
+    build_options.max_output_bytes = Some(settings.max_output_bytes);
+    build_options.max_base_entry_bytes = Some(settings.max_base_entry_bytes);
+    build_options.temp_files = settings.temp_files.clone();
+    if user_info.include_banner {
+        build_options.banner_comments = Some(template.config.banner_comment_prefixes());
+    }
+    build_options.compression = template
+        .config
+        .compression
+        .iter()
+        .map(|(extension, method)| (extension.to_lowercase(), *method))
+        .collect();
+    let framework = template
+        .config
+        .resolve_framework(user_info.framework.as_deref())
+        .map_err(AppError::BadRequest)?;
+    let mut raw_extra = std::mem::take(&mut user_info.extra);
+    if user_info.include_funding {
+        build_options.funding = builder::funding_from_extra(&raw_extra);
+        if build_options.funding.is_empty() {
+            println!("[DEBUG] include_funding set without any funding handle, leaving {} out", builder::FUNDING_ENTRY);
+        }
+        // Funding handles aren't placeholders unless the template says so, and
+        // shouldn't trip `strict_extra`
+        raw_extra.retain(|key, _| {
+            !builder::FUNDING_PLATFORMS.iter().any(|(funding_key, _)| funding_key == key)
+                || template.config.placeholders.contains(key)
+        });
+    }
+    let extra = template.config.allowed_extras(raw_extra).map_err(AppError::BadRequest)?;
+    let zip_comment = user_info.zip_comment.take().or_else(|| template.config.zip_comment.clone());
+    let mut template_data = TemplateData::new(user_info, settings.normalize_unicode);
+    template_data.timestamp = settings::format_timestamp(&settings.timestamp_format);
+    template_data.extra = if settings.normalize_unicode {
+        extra.into_iter().map(|(token, value)| (token, nfc(value))).collect()
+    } else {
+        extra
+    };
+    // Strict templates want missing values reported, not papered over
+    if template.config.strict_extra && template_data.default_placeholder_value.take().is_some() {
+        println!("[DEBUG] Ignoring default_placeholder_value for strict template {}", template.id);
+    }
+    if template.config.request_placeholders {
+        template_data.client_ip = Some(request.client_ip.clone().unwrap_or_else(|| "unknown".to_string()));
+        template_data.user_agent = Some(request.user_agent.clone().unwrap_or_default());
+    }
+    if let Some((name, framework)) = framework {
+        println!("[DEBUG] Using {} dependencies for {}", name, template.id);
+        template_data.dependencies = Some(template_config::render_dependencies(&framework.dependencies));
+    }
+    build_options.zip_comment = zip_comment
+        .map(|comment| builder::fill_template_content(&comment, &template_data))
+        .filter(|comment| {
+            let fits = comment.len() <= template_config::MAX_ZIP_COMMENT_LEN;
+            if !fits {
+                println!("[WARN] Filled zip comment for {} is {} bytes, leaving it out", template.id, comment.len());
+            }
+            fits
+        });
+
+    if template.npm_package {
+        validate_npm_slug(&template_data.project_slug).map_err(|e| {
+            eprintln!("[ERROR] Invalid project slug: {}", e);
+            AppError::BadRequest(e)
+        })?;
+    }
+    Ok((template_data, build_options))
+}
+
+// Filename a generated project is offered under: the template's `filename`
+// pattern filled in, or `<slug><suffix>.zip` without one
+fn project_filename(template: &LoadedTemplate, template_data: &TemplateData) -> String {
+    let mut default = format!("{}{}.zip", template_data.project_slug, template.filename_suffix);
+    if !is_safe_filename(&default) {
+        let fallback = format!("{}{}.zip", template.id, template.filename_suffix);
+        println!("[WARN] Filename {:?} from the project slug is unsafe, using {}", default, fallback);
+        default = fallback;
+    }
+    let Some(pattern) = &template.config.filename else {
+        return default;
+    };
+    let token = format!("{{{{{}}}}}", template_config::FILENAME_TEMPLATE_ID);
+    let filename = builder::fill_template_content(&pattern.replace(&token, &template.id), template_data);
+    if !is_safe_filename(&filename) {
+        println!("[WARN] Filename {:?} from the {} template's pattern is unsafe, using {}", filename, template.id, default);
+        return default;
+    }
+    filename
+}
+
+// A plain `.zip` file name that works in Content-Disposition and on disk
+fn is_safe_filename(name: &str) -> bool {
+    name.len() <= 255
+        && name.len() > ".zip".len()
+        && name.ends_with(".zip")
+        && !name.starts_with('.')
+        && !name.contains("{{")
+        && !name.chars().any(|c| c.is_control() || matches!(c, '/' | '\\' | '"' | ':' | '*' | '?' | '<' | '>' | '|'))
+}
+
+// Run one generation inside a `generate` span carrying the template and
+// project name, and record its `outcome`, `duration_ms` and `zip_bytes` when
+// it ends, whether it succeeded or not
+fn traced_generation(
+    template_id: &str,
+    project_name: &str,
+    generate: impl FnOnce() -> Result<GeneratedZip, AppError>,
+) -> Result<GeneratedZip, AppError> {
+    let span = tracing::info_span!(
+        "generate",
+        template = template_id,
+        project_name,
+        outcome = tracing::field::Empty,
+        duration_ms = tracing::field::Empty,
+        zip_bytes = tracing::field::Empty,
+    );
+    let _entered = span.enter();
+    let started = std::time::Instant::now();
+    let result = generate();
+    span.record("duration_ms", started.elapsed().as_millis() as u64);
+    match &result {
+        Ok(generated) => {
+            span.record("outcome", "ok");
+            span.record("zip_bytes", generated.bytes.len() as u64);
+            tracing::info!("Generation finished");
+        }
+        Err(e) => {
+            span.record("outcome", "error");
+            tracing::info!(code = e.code(), "Generation failed");
+        }
+    }
+    result
+}
+
+// Validate a request against a template's rules and build its zip
+fn generate_project(
+    template: &LoadedTemplate,
+    user_info: UserInfo,
+    settings: &Settings,
+    request: &RequestInfo,
+) -> Result<GeneratedZip, AppError> {
+    let project_name = user_info.project_name.clone();
+    traced_generation(&template.id, &project_name, || {
+        let (template_data, build_options) = prepare_project(template, user_info, settings, request)?;
+        let entries = collect_entries(template, &template_data, &build_options)?;
+        check_not_empty(template, &entries)?;
+        let bytes = write_zip(&entries, &build_options)?;
+        Ok(GeneratedZip {
+            filename: project_filename(template, &template_data),
+            bytes,
+            template_version: template.version().map(str::to_string),
+            entry_count: entries.len(),
+            substitutions: substitution_counts(&entries),
+        })
+    })
+}
+
+// Validate a request and build its zip, sharing one build among identical
+// requests in flight, see `coalesce`
+async fn generate_coalesced(
+    state: &AppState,
+    template: &LoadedTemplate,
+    user_info: UserInfo,
+    request: &RequestInfo,
+    headers: &HeaderMap,
+) -> Result<GeneratedZip, AppError> {
+    let key = coalesce::coalescing_key(template, &user_info, request, headers);
+    state
+        .coalescer
+        .run(key, || async { generate_project(template, user_info, &state.settings, request) })
+        .await
+}
+
+// Generate server zip file endpoint
+#[utoipa::path(
+    post,
+    path = "/generate-server-zip",
+    tag = "generate",
+    params(
+        GenerateParams,
+        ("Idempotency-Key" = Option<String>, Header, description = "Identical requests with the same key share one build while it runs"),
+    ),
+    request_body(content(
+        (UserInfo = "application/json"),
+        (UserInfo = "application/x-www-form-urlencoded"),
+        (UserInfo = "application/json5"),
+        (UserInfo = "application/yaml"),
+    )),
+    responses(
+        (status = 200, description = "Generated project zip, or base64 JSON with `encoding=base64`", content_type = "application/zip",
+            headers(("X-Template-Version" = String), ("X-Zip-Entries" = String, description = "With `list_entries=true`"),
+                    ("X-Substitutions-Total" = String, description = "With `debug=true`"),
+                    ("X-Signature" = String, description = "Base64 Ed25519 signature of the zip, when ZEROHUB_SIGNING_KEY is set"))),
+        (status = 204, description = "The request leaves the template with no files"),
+        (status = 201, description = "Project written to `output_dir`", body = local_write::LocalWriteSummary),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 413, description = "Estimated project size over ZEROHUB_MAX_OUTPUT_BYTES", body = ErrorBody),
+        (status = 422, description = "No files left from a `strict_extra` template", body = ErrorBody),
+        (status = 500, description = "Template could not be built", body = ErrorBody),
+    )
+)]
+async fn generate_server_zip(
+    State(state): State<AppState>,
+    Query(params): Query<GenerateParams>,
+    request_info: RequestInfo,
+    headers: HeaderMap,
+    JsonOrForm(user_info): JsonOrForm<UserInfo>,
+) -> impl IntoResponse {
+    println!("[DEBUG] Received request to generate server zip for user: {}", user_info.username);
+
+    if let Some(output_dir) = params.output_dir.as_deref() {
+        return state
+            .template("server")
+            .and_then(|t| local_write::generate_to_dir(&t, user_info, &state.settings, &request_info, output_dir))
+            .map(|summary| (StatusCode::CREATED, Json(summary)))
+            .into_response();
+    }
+
+    let generated = match state.template("server") {
+        Ok(template) => generate_coalesced(&state, &template, user_info, &request_info, &headers).await,
+        Err(e) => Err(e),
+    };
+    match generated {
+        Ok(generated) => {
+            println!("[DEBUG] Successfully created server zip: {}, size: {} bytes", generated.filename, generated.bytes.len());
+            deliver(&state.settings, generated, &params).await.into_response()
+        }
+        Err(e) => {
+            eprintln!("[ERROR] Server zip creation error: {}", e);
+            println!("[ERROR] Full error details: {:?}", e);
+            e.context("Failed to create server zip file").into_response()
+        }
+    }
+}
+
+// Generate client zip file endpoint
+#[utoipa::path(
+    post,
+    path = "/generate-client-zip",
+    tag = "generate",
+    params(
+        GenerateParams,
+        ("Idempotency-Key" = Option<String>, Header, description = "Identical requests with the same key share one build while it runs"),
+    ),
+    request_body(content(
+        (UserInfo = "application/json"),
+        (UserInfo = "application/x-www-form-urlencoded"),
+        (UserInfo = "application/json5"),
+        (UserInfo = "application/yaml"),
+    )),
+    responses(
+        (status = 200, description = "Generated project zip, or base64 JSON with `encoding=base64`", content_type = "application/zip",
+            headers(("X-Template-Version" = String), ("X-Zip-Entries" = String, description = "With `list_entries=true`"),
+                    ("X-Substitutions-Total" = String, description = "With `debug=true`"),
+                    ("X-Signature" = String, description = "Base64 Ed25519 signature of the zip, when ZEROHUB_SIGNING_KEY is set"))),
+        (status = 204, description = "The request leaves the template with no files"),
+        (status = 201, description = "Project written to `output_dir`", body = local_write::LocalWriteSummary),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 413, description = "Estimated project size over ZEROHUB_MAX_OUTPUT_BYTES", body = ErrorBody),
+        (status = 422, description = "No files left from a `strict_extra` template", body = ErrorBody),
+        (status = 500, description = "Template could not be built", body = ErrorBody),
+    )
+)]
+async fn generate_client_zip(
+    State(state): State<AppState>,
+    Query(params): Query<GenerateParams>,
+    request_info: RequestInfo,
+    headers: HeaderMap,
+    JsonOrForm(user_info): JsonOrForm<UserInfo>,
+) -> impl IntoResponse {
+    println!("[DEBUG] Received request to generate client zip for user: {}", user_info.username);
+
+    if let Some(output_dir) = params.output_dir.as_deref() {
+        return state
+            .template("client")
+            .and_then(|t| local_write::generate_to_dir(&t, user_info, &state.settings, &request_info, output_dir))
+            .map(|summary| (StatusCode::CREATED, Json(summary)))
+            .into_response();
+    }
+
+    let generated = match state.template("client") {
+        Ok(template) => generate_coalesced(&state, &template, user_info, &request_info, &headers).await,
+        Err(e) => Err(e),
+    };
+    match generated {
+        Ok(generated) => {
+            println!("[DEBUG] Successfully created client zip: {}, size: {} bytes", generated.filename, generated.bytes.len());
+            deliver(&state.settings, generated, &params).await.into_response()
+        }
+        Err(e) => {
+            eprintln!("[ERROR] Client zip creation error: {}", e);
+            println!("[ERROR] Full error details: {:?}", e);
+            e.context("Failed to create client zip file").into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ValidateConfigParams {
+    // Template whose base archive file references are checked against
+    pub template: Option<String>,
+}
+
+// Result of `/validate-template-config`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigValidation {
+    pub valid: bool,
+    pub problems: Vec<String>,
+}
+
+// Check a `zerohub.toml` sent as the request body and list every problem found
+#[utoipa::path(
+    post,
+    path = "/validate-template-config",
+    tag = "templates",
+    params(ValidateConfigParams),
+    request_body(content = String, description = "zerohub.toml source", content_type = "application/toml"),
+    responses(
+        (status = 200, description = "Every problem found; empty when valid", body = ConfigValidation),
+        (status = 404, description = "Unknown template", body = ErrorBody),
+    )
+)]
+async fn validate_template_config(
+    State(state): State<AppState>,
+    Query(params): Query<ValidateConfigParams>,
+    body: String,
+) -> Result<Json<ConfigValidation>, AppError> {
+    let base_entries = match &params.template {
+        Some(template_id) => {
+            let template = state.template(template_id)?;
+            let archive = open_base_archive(&template.id, &template.base_zip)?;
+            Some(archive.file_names().map(str::to_string).collect::<Vec<_>>())
+        }
+        None => None,
+    };
+
+    let problems = template_config::validate_config_source(&body, base_entries.as_deref(), &BUILTIN_PLACEHOLDERS);
+    println!("[DEBUG] Validated template config: {} problems", problems.len());
+    Ok(Json(ConfigValidation {
+        valid: problems.is_empty(),
+        problems,
+    }))
+}
+
+// Report every template once at startup so a bad template deploy shows up in
+// the logs immediately instead of on the first generation request. Loading
+// already opened each base archive, see `load_template`.
+fn run_self_test(cache: &TemplateCache) {
+    println!("[DEBUG] Running template self-test:");
+    let summary = cache.summary();
+    for template in &summary.reloaded {
+        println!("[DEBUG] - {} ({} byte base archive) ok", template.id, template.base_zip_bytes);
+    }
+    for failed in &summary.failed {
+        tracing::error!(template_id = %failed.id, code = failed.code, "Template self-test failed: {}", failed.error);
+    }
+}
+
+// Run the server until it fails; the binary's `main` is just this
+pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let log_level = log_level::init();
+    log_level::spawn_signal_handler(log_level);
+
+    // Print debugging information
+    println!("[DEBUG] ============ Starting ZeroHub Server ============");
+    
+    // Print current working directory
+    let current_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("unknown"));
+    println!("[DEBUG] Current working directory: {:?}", current_dir);
+    
+    // Check if template directories exist
+    let templates_dir = std::path::Path::new("templates");
+    let server_dir = std::path::Path::new("templates/server");
+    let client_dir = std::path::Path::new("templates/client");
+    let static_dir = std::path::Path::new("static");
+    
+    println!("[DEBUG] Checking template directories:");
+    println!("[DEBUG] - templates/ exists: {}", templates_dir.exists());
+    println!("[DEBUG] - templates/server/ exists: {}", server_dir.exists());
+    println!("[DEBUG] - templates/client/ exists: {}", client_dir.exists());
+    println!("[DEBUG] - static/ exists: {}", static_dir.exists());
+    
+    // Check specific template files
+    let files_to_check = [
+        "templates/server/zero.zip",
+        "templates/server/zero.zip.gz",
+        "templates/server/zero.tar.gz",
+        "templates/server/LICENSE",
+        "templates/server/pyproject.toml",
+        "templates/server/README.md",
+        "templates/client/zero-client.zip",
+        "templates/client/zero-client.zip.gz",
+        "templates/client/zero-client.tar.gz",
+        "templates/client/LICENSE",
+        "templates/client/package.json",
+        "templates/client/README.md",
+        "static/index.html",
+    ];
+    
+    println!("[DEBUG] Checking template files:");
+    for file_path in &files_to_check {
+        let exists = std::path::Path::new(file_path).exists();
+        println!("[DEBUG] - {} exists: {}", file_path, exists);
+    }
+    
+    println!("[DEBUG] ===============================================");
+
+    let mut settings = Settings::from_env();
+    settings.static_mounts = match static_mounts::from_env() {
+        Ok(mounts) => mounts,
+        Err(e) => {
+            eprintln!("[ERROR] {}", e);
+            return Err(e.into());
+        }
+    };
+    settings.signer = match signing::ZipSigner::from_env() {
+        Ok(signer) => signer,
+        Err(e) => {
+            eprintln!("[ERROR] {}", e);
+            return Err(e.into());
+        }
+    };
+    #[cfg(feature = "s3")]
+    {
+        settings.s3 = s3_upload::S3Target::from_env().await;
+    }
+    let tls_config = match tls::TlsSettings::from_env().and_then(|tls| tls.map(|tls| tls.server_config()).transpose()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("[ERROR] {}", e);
+            return Err(e.into());
+        }
+    };
+    let cache = TemplateCache::load(settings.read_retry);
+    run_self_test(&cache);
+    if settings.strict_startup {
+        println!("[DEBUG] Rendering every template with probe data ({}=true):", strict_startup::STRICT_STARTUP_ENV);
+        if let Err(e) = strict_startup::check_templates(&cache, &settings) {
+            eprintln!("[ERROR] {}", e);
+            return Err(e.into());
+        }
+    }
+
+    let downloads = DownloadStore::new(settings.signer.clone());
+    let state = AppState {
+        templates: Arc::new(TemplateRegistry::new(cache)),
+        settings: Arc::new(settings),
+        downloads: Arc::new(downloads),
+        maintenance: Arc::new(maintenance::Maintenance::from_env()),
+        throttles: Arc::new(throttle::Throttles::from_env()),
+        coalescer: Arc::default(),
+    };
+    if watch::watch_enabled() {
+        watch::spawn_template_watcher(state.clone());
+    }
+    let app = build_router(state);
+
+    // Start the server, on the socket systemd passed in when socket-activated
+    let listener = listener::bind(listener::DEFAULT_ADDR).await?;
+    let local_addr = listener.local_addr()?;
+    if let Some(config) = tls_config {
+        println!("🚀 Server starting at https://{}", local_addr);
+        tls::serve(listener, app, config).await?;
+        return Ok(());
+    }
+    println!("🚀 Server starting at http://{}", local_addr);
+    // Connect info provides the peer address for `{{client_ip}}`
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await?;
+
+    Ok(())
+}
+
+// Build the router with every route and middleware layer, nested under the
+// configured base path when one is set
+fn build_router(state: AppState) -> Router {
+    let max_body_bytes = state.settings.max_body_bytes;
+    let base_path = state.settings.base_path.clone();
+
+    // Uploads get their own, larger limit instead of the JSON body limit
+    let uploads = Router::new()
+        .route("/generate-custom-base-zip", post(custom_base::generate_custom_base_zip))
+        .route("/template-drift", post(drift::template_drift))
+        .layer(RequestBodyLimitLayer::new(state.settings.max_upload_bytes));
+
+    // Every route that builds a project, and so takes a generation slot
+    let generation = Router::new()
+        .route("/generate", post(profiles::generate_from_profile))
+        .route("/generate-server-zip", post(generate_server_zip))
+        .route("/generate-client-zip", post(generate_client_zip))
+        .route("/generate-nested", post(nested::generate_nested))
+        .route("/generate-partial", post(partial::generate_partial))
+        .route("/generate-async", post(downloads::generate_async))
+        .route("/generate-links", post(links::generate_links))
+        .route("/generate-batch", post(batch::generate_batch))
+        .route("/generate-batch/stream", post(batch::generate_batch_stream))
+        .route("/tree", post(tree::project_tree))
+        .route("/migration-pack", post(drift::migration_pack))
+        .route("/render/:template/*path", get(render::render_file))
+        .layer(RequestBodyLimitLayer::new(max_body_bytes))
+        .merge(uploads)
+        .layer(middleware::from_fn_with_state(state.clone(), throttle::throttle_generation))
+        .layer(middleware::from_fn_with_state(state.clone(), maintenance::reject_during_maintenance));
+
+    let routes = Router::new()
+        .route("/", get(index))
+        .route("/health", get(health))
+        .route("/ping", get(ping))
+        .route("/livez", get(livez))
+        .route("/pubkey", get(signing::public_key))
+        .route("/openapi.json", get(openapi::openapi_json))
+        .route("/templates", get(list_templates))
+        .route("/templates/:id/stats", get(template_stats))
+        .route("/templates/:id/coverage", get(template_coverage))
+        .route("/download/:token", get(downloads::download))
+        .route("/download/:token/*path", get(downloads::download_entry))
+        .route("/license/:spdx_id", get(licenses::render_license))
+        .route("/validate-template-config", post(validate_template_config))
+        .route("/admin/reload", post(admin::reload_templates))
+        .route("/admin/cache", get(admin::list_cache).delete(admin::clear_cache))
+        .route("/admin/maintenance", get(admin::maintenance_status).put(admin::set_maintenance))
+        .nest_service(
+            "/static",
+            ServiceBuilder::new()
+                .layer(middleware::from_fn(cache_headers::static_cache_headers))
+                .service(ServeDir::new("./static")),
+        )
+        .layer(RequestBodyLimitLayer::new(max_body_bytes))
+        .merge(generation);
+    let routes = static_mounts::add_mounts(routes, &state.settings.static_mounts);
+    #[cfg(feature = "debug-delay")]
+    let routes = match debug_delay::DebugDelay::from_env() {
+        Some(delay) => routes.layer(middleware::from_fn_with_state(delay, debug_delay::delay_generate)),
+        None => routes,
+    };
+    let routes = if base_path.is_empty() {
+        routes
+    } else {
+        // Proxies usually forward "/prefix/", which the nested "/" route doesn't match
+        Router::new()
+            .route(&format!("{}/", base_path), get(index))
+            .nest(&base_path, routes)
+    };
+
+    let cors = state.settings.cors.as_ref().map(cors::CorsSettings::layer);
+    routes
+        // Layers run outside-in: bodies are decompressed first, so the size
+        // limits above apply to the decompressed bytes the extractors will see.
+        // Preflights are answered before anything else sees them.
+        .layer(
+            ServiceBuilder::new()
+                .layer(TraceLayer::new_for_http())
+                .option_layer(cors)
+                .layer(middleware::from_fn(problem::problem_json))
+                .layer(RequestDecompressionLayer::new())
+                .layer(DefaultBodyLimit::disable())
+        )
+        .with_state(state)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+    use tower::ServiceExt;
+
+    // Handler state over these templates and settings, fresh otherwise; tests
+    // that keep a handle on e.g. `maintenance` swap it in with `..test_state(..)`
+    pub(crate) fn test_state(cache: TemplateCache, settings: Settings) -> AppState {
+        AppState {
+            templates: Arc::new(TemplateRegistry::new(cache)),
+            downloads: Arc::new(DownloadStore::new(settings.signer.clone())),
+            settings: Arc::new(settings),
+            maintenance: Arc::default(),
+            throttles: Arc::default(),
+            coalescer: Arc::default(),
+        }
+    }
+
+    pub(crate) fn test_router(cache: TemplateCache, settings: Settings) -> Router {
+        build_router(test_state(cache, settings))
+    }
+
+    #[test]
+    fn generation_span_records_the_outcome_on_both_paths() {
+        let output = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || SharedWriter(writer.clone()))
+            .with_ansi(false)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let zip = GeneratedZip {
+                filename: "demo.zip".to_string(),
+                bytes: vec![0; 7],
+                template_version: None,
+                entry_count: 1,
+                substitutions: Vec::new(),
+            };
+            assert!(traced_generation("server", "demo", || Ok(zip)).is_ok());
+            assert!(traced_generation("client", "demo", || Err(AppError::BadRequest("bad".to_string()))).is_err());
+        });
+
+        let logged = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        let lines = logged.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2, "{}", logged);
+        assert!(lines[0].contains("generate{template=\"server\" project_name=\"demo\" duration_ms="), "{}", lines[0]);
+        assert!(lines[0].contains("outcome=\"ok\" zip_bytes=7}"), "{}", lines[0]);
+        assert!(lines[1].contains("outcome=\"error\""), "{}", lines[1]);
+        assert!(lines[1].contains("code=\"BAD_REQUEST\""), "{}", lines[1]);
+    }
+
+    #[test]
+    fn self_test_logs_each_failed_template_with_its_code() {
+        let output = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || SharedWriter(writer.clone()))
+            .with_ansi(false)
+            .finish();
+        let corrupt = AppError::BaseArchiveCorrupt { template_id: "server".to_string(), reason: "invalid Zip archive".to_string() };
+        let cache = TemplateCache::default().with_error("server", corrupt);
+        tracing::subscriber::with_default(subscriber, || run_self_test(&cache));
+
+        let logged = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        assert!(logged.contains("ERROR"), "{}", logged);
+        assert!(logged.contains("template_id=server") && logged.contains("code=\"BASE_ARCHIVE_CORRUPT\""), "{}", logged);
+        assert!(logged.contains("Base archive for template 'server' is corrupt: invalid Zip archive"), "{}", logged);
+    }
+
+    struct SharedWriter(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn gzip_request(uri: &str, body: &[u8]) -> Request<Body> {
+        Request::post(uri)
+            .header(header::CONTENT_ENCODING, "gzip")
+            .body(Body::from(gzip(body)))
+            .unwrap()
+    }
+
+    // Static mounts can't shadow a route as long as every route's first
+    // segment is reserved
+    #[test]
+    fn every_endpoint_segment_is_reserved_from_static_mounts() {
+        let description = api_description("");
+        for endpoint in description["endpoints"].as_array().unwrap() {
+            let path = endpoint["path"].as_str().unwrap();
+            let segment = path.split(['/', '?']).nth(1).unwrap();
+            assert!(segment.is_empty() || static_mounts::RESERVED_SEGMENTS.contains(&segment), "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn routes_are_nested_under_the_base_path() {
+        let app = test_router(TemplateCache::default(), Settings { base_path: "/zerohub".to_string(), ..Default::default() });
+
+        let response = app.clone().oneshot(Request::get("/zerohub/health").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.clone().oneshot(Request::get("/zerohub/").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.oneshot(Request::get("/health").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn nested_extra_objects_flatten_to_dotted_keys() {
+        let user_info: UserInfo = serde_json::from_value(serde_json::json!({
+            "username": "alice", "email": "a@b.c", "project_name": "demo",
+            "extra": { "author": { "name": "Ada", "links": { "site": "ada.dev" } }, "year": 2026, "oss": true }
+        }))
+        .unwrap();
+        let keys = user_info.extra.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect::<Vec<_>>();
+        assert_eq!(keys, [("author.links.site", "ada.dev"), ("author.name", "Ada"), ("oss", "true"), ("year", "2026")]);
+
+        let with_list = serde_json::json!({ "username": "a", "email": "a@b.c", "project_name": "p", "extra": { "tags": ["x"] } });
+        let error = serde_json::from_value::<UserInfo>(with_list).unwrap_err();
+        assert!(error.to_string().contains("extra.tags must be"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn index_explains_unavailable_templates_instead_of_serving_the_form() {
+        let cache = TemplateCache::default().with_error("server", AppError::Internal("templates/server/zero.zip: <missing>".to_string()));
+        let app = test_router(cache, Settings::default());
+
+        let response = app.clone().oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::CACHE_CONTROL], NO_STORE);
+        let page = String::from_utf8(to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()).unwrap();
+        assert!(page.contains("<li><strong>server</strong>: templates/server/zero.zip: &lt;missing&gt;</li>"), "{}", page);
+        assert!(!page.contains("{{problems}}") && !page.contains("<form"));
+
+        let response = app.oneshot(Request::get("/health").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["status"], "degraded");
+        assert_eq!(body["unavailable_templates"][0]["id"], "server");
+
+        let response = test_router(TemplateCache::default(), Settings::default()).oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn ping_returns_plaintext_pong() {
+        let app = test_router(TemplateCache::default(), Settings::default());
+        let response = app.oneshot(Request::get("/ping").body(Body::empty()).unwrap()).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain; charset=utf-8");
+        assert_eq!(to_bytes(response.into_body(), usize::MAX).await.unwrap(), "pong");
+    }
+
+    #[tokio::test]
+    async fn maintenance_pauses_generation_but_not_health_checks() {
+        let maintenance = Arc::new(maintenance::Maintenance::default());
+        let settings = Settings { admin_token: Some("secret".to_string()), ..Default::default() };
+        let app = build_router(AppState { maintenance: maintenance.clone(), ..test_state(TemplateCache::default(), settings) });
+        let toggle = |enabled: bool| {
+            Request::put("/admin/maintenance")
+                .header(header::AUTHORIZATION, "Bearer secret")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(format!("{{\"enabled\":{}}}", enabled)))
+                .unwrap()
+        };
+        let generate = || Request::post("/generate-batch").body(Body::from("{}")).unwrap();
+
+        assert_eq!(app.clone().oneshot(toggle(true)).await.unwrap().status(), StatusCode::OK);
+        assert!(maintenance.is_enabled());
+        let response = app.clone().oneshot(generate()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "120");
+        for (method, uri) in [
+            ("GET", "/render/server/README.md"),
+            ("POST", "/tree"),
+            ("POST", "/migration-pack"),
+            ("POST", "/template-drift"),
+        ] {
+            let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+            assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE, "{}", uri);
+        }
+        for path in ["/livez", "/health", "/ping"] {
+            let response = app.clone().oneshot(Request::get(path).body(Body::empty()).unwrap()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", path);
+        }
+
+        app.clone().oneshot(toggle(false)).await.unwrap();
+        assert_ne!(app.oneshot(generate()).await.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn every_project_building_route_takes_a_slot_held_until_it_finishes() {
+        use futures_util::StreamExt;
+
+        let slots = Arc::new(tokio::sync::Semaphore::new(1));
+        let throttles = throttle::Throttles { rate_limiter: None, generation_slots: Some(slots.clone()) };
+        let base_zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new())).finish().unwrap().into_inner();
+        let template = LoadedTemplate::for_test("server", base_zip, vec![("README.md".to_string(), "# {{project_name}}".to_string())]);
+        let cache = TemplateCache::from_templates(vec![template]);
+        let app = build_router(AppState { throttles: Arc::new(throttles), ..test_state(cache, Settings::default()) });
+
+        let busy = slots.clone().try_acquire_owned().unwrap();
+        for (method, uri) in [
+            ("POST", "/generate-server-zip"),
+            ("GET", "/render/server/README.md"),
+            ("POST", "/tree"),
+            ("POST", "/migration-pack"),
+            ("POST", "/template-drift"),
+            ("POST", "/generate-custom-base-zip"),
+        ] {
+            let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE, "{}", uri);
+        }
+        let health = app.clone().oneshot(Request::get("/health").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(health.status(), StatusCode::OK);
+        drop(busy);
+
+        // More projects than the event channel holds, so the batch can't
+        // finish before its events are read
+        let project = serde_json::json!({ "username": "a", "email": "a@b.c", "project_name": "Held", "project_description": "d" });
+        let batch = serde_json::json!({ "template": "server", "projects": vec![project; 20] });
+        let request = Request::post("/generate-batch/stream")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(batch.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut events = response.into_body().into_data_stream();
+        let first = events.next().await.unwrap().unwrap();
+        assert!(String::from_utf8_lossy(&first).contains("progress"));
+        assert_eq!(slots.available_permits(), 0);
+        while events.next().await.is_some() {}
+        assert_eq!(slots.available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_requests_get_retry_after_and_retry_at() {
+        let throttles = throttle::Throttles { rate_limiter: Some(throttle::RateLimiter::new(1)), generation_slots: None };
+        let app = build_router(AppState { throttles: Arc::new(throttles), ..test_state(TemplateCache::default(), Settings::default()) });
+        let generate = || Request::post("/generate-batch").body(Body::from("{}")).unwrap();
+
+        assert_eq!(app.clone().oneshot(generate()).await.unwrap().status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let response = app.clone().oneshot(generate()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "60");
+        let body: serde_json::Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["code"], "RATE_LIMITED");
+        assert_eq!(body["retry_after_secs"], 60);
+        assert!(body["retry_at"].as_str().unwrap().ends_with('Z'));
+        let health = app.oneshot(Request::get("/health").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(health.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn composed_and_decomposed_values_produce_identical_zips() {
+        let base_zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new())).finish().unwrap().into_inner();
+        let fill_files = vec![("README.md".to_string(), "# {{project_name}} ({{project_slug}}) by {{username}}".to_string())];
+        let template = LoadedTemplate::for_test("server", base_zip, fill_files);
+        let generate = |name: &str, settings: &Settings| {
+            let user_info = UserInfo {
+                username: format!("{}-user", name),
+                email: "cafe@example.com".to_string(),
+                project_name: name.to_string(),
+                project_description: "A café".to_string(),
+                deterministic: true,
+                ..Default::default()
+            };
+            let (data, build_options) = prepare_project(&template, user_info, settings, &RequestInfo::default()).unwrap();
+            let entries = collect_entries(&template, &data, &build_options).unwrap();
+            (project_filename(&template, &data), write_zip(&entries, &build_options).unwrap())
+        };
+        let (composed, decomposed) = ("Caf\u{e9}", "Cafe\u{301}");
+
+        let settings = Settings::default();
+        assert_eq!(generate(composed, &settings), generate(decomposed, &settings));
+        let settings = Settings { normalize_unicode: false, ..Default::default() };
+        assert_ne!(generate(composed, &settings).1, generate(decomposed, &settings).1);
+    }
+
+    #[test]
+    fn zip_comments_come_from_the_request_or_the_template() {
+        let base_zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new())).finish().unwrap().into_inner();
+        let mut template = LoadedTemplate::for_test("server", base_zip, vec![("README.md".to_string(), "# {{project_name}}".to_string())]);
+        template.config.zip_comment = Some("Generated by ZeroHub for {{project_name}}".to_string());
+        let comment = |zip_comment: Option<&str>| {
+            let user_info = UserInfo {
+                username: "alice".to_string(),
+                email: "a@b.c".to_string(),
+                project_name: "Demo".to_string(),
+                project_description: "d".to_string(),
+                zip_comment: zip_comment.map(str::to_string),
+                ..Default::default()
+            };
+            let (data, build_options) =
+                prepare_project(&template, user_info, &Settings::default(), &RequestInfo::default()).unwrap();
+            let zip_data = write_zip(&collect_entries(&template, &data, &build_options).unwrap(), &build_options).unwrap();
+            let archive = zip::ZipArchive::new(std::io::Cursor::new(zip_data)).unwrap();
+            String::from_utf8(archive.comment().to_vec()).unwrap()
+        };
+
+        assert_eq!(comment(None), "Generated by ZeroHub for Demo");
+        assert_eq!(comment(Some("{{username}}'s {{project_slug}}")), "alice's demo");
+    }
+
+    #[test]
+    fn filename_patterns_are_filled_and_checked() {
+        let mut template = LoadedTemplate::for_test("client", Vec::new(), Vec::new());
+        template.filename_suffix = "-client".to_string();
+        let data = TemplateData::from(UserInfo {
+            username: "a/b".to_string(),
+            project_name: "My App".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(project_filename(&template, &data), "my-app-client.zip");
+
+        template.config.filename = Some("{{project_slug}}-{{template_id}}-{{date:%Y}}.zip".to_string());
+        assert_eq!(project_filename(&template, &data), format!("my-app-client-{}.zip", chrono::Utc::now().format("%Y")));
+        template.config.filename = Some("{{username}}.zip".to_string());
+        assert_eq!(project_filename(&template, &data), "my-app-client.zip");
+
+        template.config.filename = None;
+        template.filename_suffix = String::new();
+        let data = TemplateData::from(UserInfo { project_name: "!!!".to_string(), ..Default::default() });
+        assert_eq!(project_filename(&template, &data), "client.zip");
+    }
+
+    #[tokio::test]
+    async fn cors_preflights_are_answered_with_a_max_age() {
+        let cors = cors::CorsSettings::parse("https://app.example", 3600);
+        let app = test_router(TemplateCache::default(), Settings { cors, ..Default::default() });
+        let preflight = |origin: &str| {
+            Request::options("/generate-server-zip")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type,idempotency-key")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(preflight("https://app.example")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.example");
+        assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "3600");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_HEADERS], "content-type,idempotency-key");
+        let methods = headers[header::ACCESS_CONTROL_ALLOW_METHODS].to_str().unwrap();
+        assert!(methods.split(',').any(|method| method.trim() == "POST"), "{}", methods);
+
+        let response = app.oneshot(preflight("https://other.example")).await.unwrap();
+        assert!(!response.headers().contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[test]
+    fn project_name_cases_are_derived() {
+        let cases = ProjectNameCases::of("my cool-App 2");
+        assert_eq!(cases.upper, "MY COOL-APP 2");
+        assert_eq!(cases.lower, "my cool-app 2");
+        assert_eq!(cases.kebab, "my-cool-app-2");
+        assert_eq!(cases.snake, "my_cool_app_2");
+        assert_eq!(cases.title, "My Cool App 2");
+    }
+
+    #[tokio::test]
+    async fn empty_generations_are_204_or_422_when_strict() {
+        let mut empty_zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        empty_zip.start_file("docs/guide.md", zip::write::SimpleFileOptions::default()).unwrap();
+        let base_zip = empty_zip.finish().unwrap().into_inner();
+        let status = |strict_extra: bool| {
+            let mut template = LoadedTemplate::for_test("server", base_zip.clone(), Vec::new());
+            template.config.strict_extra = strict_extra;
+            let app = test_router(TemplateCache::from_templates(vec![template]), Settings::default());
+            let body = r#"{"username":"alice","email":"a@b.c","project_name":"demo","project_description":"d","exclude":["docs/**"]}"#;
+            let request = Request::post("/generate-server-zip")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            async move { app.oneshot(request).await.unwrap() }
+        };
+
+        let response = status(false).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(to_bytes(response.into_body(), usize::MAX).await.unwrap().is_empty());
+        assert_eq!(status(true).await.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn debug_requests_count_substitutions_per_file() {
+        let mut base = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        base.start_file("LICENSE", zip::write::SimpleFileOptions::default()).unwrap();
+        let fill_files = vec![("README.md".to_string(), "# {{project_name}} by {{username}}, {{unknown}}".to_string())];
+        let template = LoadedTemplate::for_test("server", base.finish().unwrap().into_inner(), fill_files);
+        let app = test_router(TemplateCache::from_templates(vec![template]), Settings::default());
+        let generate = |uri: &str| {
+            let body = r#"{"username":"alice","email":"a@b.c","project_name":"demo","project_description":"d"}"#;
+            let request = Request::post(uri).header(header::CONTENT_TYPE, "application/json").body(Body::from(body)).unwrap();
+            app.clone().oneshot(request)
+        };
+
+        let response = generate("/generate-server-zip?debug=true").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[listing::SUBSTITUTIONS_TOTAL_HEADER], "2");
+        assert_eq!(response.headers()[listing::SUBSTITUTIONS_HEADER], "README.md=2");
+        let response = generate("/generate-server-zip").await.unwrap();
+        assert!(!response.headers().contains_key(listing::SUBSTITUTIONS_TOTAL_HEADER));
+    }
+
+    #[tokio::test]
+    async fn generated_links_serve_each_format_and_file() {
+        let app = test_router(TemplateCache::load(templates::ReadRetry::default()), Settings::default());
+        let body = r#"{"username":"alice","email":"a@b.c","project_name":"Links","project_description":"d"}"#;
+        let request = Request::post("/generate-links?template=server")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let links: serde_json::Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        let get = |url: &str| app.clone().oneshot(Request::get(url).body(Body::empty()).unwrap());
+
+        let formats = links["formats"].as_array().unwrap();
+        assert_eq!(formats[1]["filename"], "links.tar.gz");
+        let response = get(formats[0]["url"].as_str().unwrap()).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/zip");
+        assert_eq!(get(formats[0]["url"].as_str().unwrap()).await.unwrap().status(), StatusCode::NOT_FOUND);
+        let response = get(formats[1]["url"].as_str().unwrap()).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/gzip");
+
+        let readme = links["files"].as_array().unwrap().iter().find(|file| file["path"] == "README.md").unwrap();
+        for _ in 0..2 {
+            let response = get(readme["url"].as_str().unwrap()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let content = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert!(String::from_utf8_lossy(&content).contains("Links"));
+        }
+    }
+
+    #[tokio::test]
+    async fn every_delivery_path_signs_with_the_published_key() {
+        use base64::Engine;
+        use ed25519_dalek::pkcs8::DecodePublicKey;
+        use ed25519_dalek::{Signature, SigningKey, VerifyingKey};
+
+        let signer = signing::ZipSigner::new(SigningKey::from_bytes(&[7; 32])).unwrap();
+        let settings = Settings { signer: Some(signer), ..Default::default() };
+        let app = test_router(TemplateCache::load(templates::ReadRetry::default()), settings);
+        let send = |request: Request<Body>| async {
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let signature = response.headers().get(signing::SIGNATURE_HEADER).map(|value| value.to_str().unwrap().to_string());
+            (signature, to_bytes(response.into_body(), usize::MAX).await.unwrap())
+        };
+        let post = |uri: &str, body: String| {
+            Request::post(uri).header(header::CONTENT_TYPE, "application/json").body(Body::from(body)).unwrap()
+        };
+
+        let (_, pem) = send(Request::get("/pubkey").body(Body::empty()).unwrap()).await;
+        let public_key = VerifyingKey::from_public_key_pem(std::str::from_utf8(&pem).unwrap()).unwrap();
+        let verify = |(signature, bytes): (Option<String>, axum::body::Bytes)| {
+            let signature = base64::engine::general_purpose::STANDARD.decode(signature.expect("X-Signature")).unwrap();
+            public_key.verify_strict(&bytes, &Signature::from_slice(&signature).unwrap()).is_ok()
+        };
+
+        let project = r#"{"username":"alice","email":"a@b.c","project_name":"Signed","project_description":"d"}"#;
+        assert!(verify(send(post("/generate-server-zip", project.to_string())).await));
+        let (_, body) = send(post("/generate-async", project.to_string())).await;
+        let stored: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let download = Request::get(stored["download_url"].as_str().unwrap()).body(Body::empty()).unwrap();
+        assert!(verify(send(download).await));
+        let batch = serde_json::json!({ "template": "server", "projects": [serde_json::from_str::<serde_json::Value>(project).unwrap()] });
+        assert!(verify(send(post("/generate-batch", batch.to_string())).await));
+
+        let (signature, zip) = send(post("/generate-server-zip", project.to_string())).await;
+        assert!(!verify((signature, axum::body::Bytes::from([zip.as_ref(), b"tampered"].concat()))));
+    }
+
+    #[tokio::test]
+    async fn batch_zip_is_streamed_from_disk_with_every_project() {
+        let app = test_router(TemplateCache::load(templates::ReadRetry::default()), Settings::default());
+        let projects = (0..4)
+            .map(|i| serde_json::json!({
+                "username": "batch",
+                "email": "batch@example.com",
+                "project_name": format!("Batch {}", i % 3),
+                "project_description": "One of a batch",
+            }))
+            .collect::<Vec<_>>();
+        let request = Request::post("/generate-batch")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::json!({ "template": "server", "projects": projects }).to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-batch-succeeded"], "4");
+        let length = response.headers()[header::CONTENT_LENGTH].to_str().unwrap().parse::<usize>().unwrap();
+        let zip_data = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(zip_data.len(), length);
+
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(zip_data)).unwrap();
+        assert_eq!(archive.len(), 5);
+        assert!(archive.by_name("batch-0-2.zip").is_ok());
+        let inner = {
+            let mut file = archive.by_name("batch-2.zip").unwrap();
+            let mut content = Vec::new();
+            std::io::Read::read_to_end(&mut file, &mut content).unwrap();
+            content
+        };
+        assert!(zip::ZipArchive::new(std::io::Cursor::new(inner)).unwrap().len() > 1);
+    }
+
+    #[tokio::test]
+    async fn streamed_batch_reports_each_item_and_the_zip_can_be_redeemed() {
+        let base_zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new())).finish().unwrap().into_inner();
+        let template = LoadedTemplate::for_test("server", base_zip, vec![("README.md".to_string(), "# {{project_name}}".to_string())]);
+        let app = test_router(TemplateCache::from_templates(vec![template]), Settings::default());
+        let project = |name: &str, email: &str| {
+            serde_json::json!({ "username": "a", "email": email, "project_name": name, "project_description": "d" })
+        };
+        let batch = serde_json::json!({ "template": "server", "projects": [project("One", "a@b.c"), project("Bad", ""), project("Two", "a@b.c")] });
+        let request = Request::post("/generate-batch/stream")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(batch.to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+        let body = String::from_utf8(to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()).unwrap();
+
+        let events = body
+            .split("\n\n")
+            .filter_map(|event| {
+                let name = event.lines().find_map(|line| line.strip_prefix("event: "))?;
+                let data = event.lines().find_map(|line| line.strip_prefix("data: "))?;
+                Some((name, serde_json::from_str::<serde_json::Value>(data).unwrap()))
+            })
+            .collect::<Vec<_>>();
+        let names = events.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(names, ["progress", "item_error", "progress", "complete"]);
+        assert_eq!((events[1].1["index"].as_u64(), events[1].1["completed"].as_u64()), (Some(1), Some(2)));
+        let complete = &events[3].1;
+        assert_eq!((complete["succeeded"].as_u64(), complete["failed"].as_u64()), (Some(2), Some(1)));
+
+        let download = || Request::get(complete["download_url"].as_str().unwrap()).body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(download()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let zip_data = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let archive = zip::ZipArchive::new(std::io::Cursor::new(zip_data)).unwrap();
+        let mut names = archive.file_names().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, [batch::BATCH_REPORT_ENTRY, "one.zip", "two.zip"]);
+        assert_eq!(app.oneshot(download()).await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    // Generate each bundled template through the router, then extract the zip
+    // and check it entry by entry rather than by size
+    #[tokio::test]
+    async fn generated_zips_extract_with_every_value_substituted() {
+        let cache = TemplateCache::load(templates::ReadRetry::default());
+        let templates = ["server", "client"].map(|id| cache.get(id).unwrap());
+        let app = test_router(cache, Settings::default());
+
+        for template in templates {
+            let body = serde_json::json!({
+                "username": "Roundtrip User",
+                "email": "roundtrip@example.com",
+                "project_name": "Roundtrip Demo",
+                "project_description": "Checked after extraction",
+            });
+            let request = Request::post(format!("/generate-{}-zip", template.id))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", template.id);
+            let zip_data = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+            let mut archive = zip::ZipArchive::new(std::io::Cursor::new(zip_data)).unwrap();
+            let base_entries = open_base_archive(&template.id, &template.base_zip).unwrap().len();
+            assert_eq!(archive.len(), base_entries + template.fill_files.len(), "{}", template.id);
+
+            let mut contents = BTreeMap::new();
+            for i in 0..archive.len() {
+                let mut file = archive.by_index(i).unwrap();
+                let mut content = Vec::new();
+                // Reading to the end verifies each entry's CRC
+                std::io::Read::read_to_end(&mut file, &mut content).unwrap();
+                contents.insert(file.name().to_string(), content);
+            }
+            for (name, _) in &template.fill_files {
+                let text = std::str::from_utf8(&contents[name]).unwrap();
+                let leftover = builder::placeholder_names(text);
+                assert!(leftover.is_empty(), "{} {} still has {:?}", template.id, name, leftover);
+            }
+            let filled = template.fill_files.iter().map(|(name, _)| String::from_utf8_lossy(&contents[name])).collect::<String>();
+            assert!(filled.contains("Roundtrip User"), "{}", template.id);
+            assert!(filled.contains("Roundtrip Demo") || filled.contains("roundtrip-demo"), "{}", template.id);
+        }
+    }
+
+    #[tokio::test]
+    async fn errors_are_problem_documents_when_accepted() {
+        let request = |accept: &str| {
+            Request::get("/templates/nope/stats")
+                .header(header::ACCEPT, accept)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = test_router(TemplateCache::default(), Settings::default())
+            .oneshot(request("application/problem+json"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], problem::PROBLEM_JSON);
+        assert_eq!(response.headers()[header::CACHE_CONTROL], NO_STORE);
+        let body: serde_json::Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["type"], "urn:zerohub:problem:not-found");
+        assert_eq!(body["status"], 404);
+        assert_eq!(body["instance"], "/templates/nope/stats");
+        assert_eq!(body["detail"], "Unknown template: nope");
+
+        let response = test_router(TemplateCache::default(), Settings::default()).oneshot(request("application/json")).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    }
+
+    #[tokio::test]
+    async fn gzip_request_bodies_are_decompressed() {
+        let app = test_router(TemplateCache::default(), Settings::default());
+        let response = app
+            .oneshot(gzip_request("/validate-template-config", b"placeholders = [\"python_version\"]"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["valid"], true);
+    }
+
+    #[tokio::test]
+    async fn body_limit_applies_to_decompressed_size() {
+        let app = test_router(TemplateCache::default(), Settings { max_body_bytes: 1024, ..Default::default() });
+        // Compresses to far less than the limit but expands well past it
+        let body = format!("placeholders = []\n{}", "#".repeat(64 * 1024));
+        assert!(gzip(body.as_bytes()).len() < 1024);
+
+        let response = app.oneshot(gzip_request("/validate-template-config", body.as_bytes())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn body_limit_applies_to_yaml_bodies() {
+        let app = test_router(TemplateCache::default(), Settings { max_body_bytes: 1024, ..Default::default() });
+        let body = format!("username: alice\nproject_description: {}\n", "x".repeat(2048));
+        let request = Request::post("/generate-server-zip")
+            .header(header::CONTENT_TYPE, "application/yaml")
+            .body(Body::from(body))
+            .unwrap();
+        assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -0,0 +1,118 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::fs;
+use utoipa::IntoParams;
+
+use crate::builder::fill_template_content;
+use crate::cache_headers::NO_STORE;
+use crate::error::{AppError, ErrorBody};
+use crate::templates::read_with_retry;
+use crate::{AppState, TemplateData, UserInfo};
+
+// License texts, one file per SPDX id, e.g. `templates/licenses/MIT`. Each may
+// use `{{username}}` and `{{year}}`.
+pub const LICENSES_DIR: &str = "templates/licenses";
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LicenseParams {
+    // Copyright holder filled into `{{username}}`
+    pub username: String,
+    // Filled into `{{year}}`; the current year when omitted
+    pub year: Option<u16>,
+}
+
+// SPDX ids of the licenses on disk, sorted
+pub fn available_licenses() -> Vec<String> {
+    let mut ids = fs::read_dir(LICENSES_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().is_file())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    ids.sort();
+    ids
+}
+
+// Render one license with the copyright holder and year filled in, without
+// generating a project, e.g. `/license/MIT?username=Ada&year=2026`
+#[utoipa::path(
+    get,
+    path = "/license/{spdx_id}",
+    tag = "templates",
+    params(("spdx_id" = String, Path, description = "SPDX license id, e.g. `MIT`"), LicenseParams),
+    responses(
+        (status = 200, description = "The filled license text", body = String, content_type = "text/plain"),
+        (status = 400, description = "Invalid username or year", body = ErrorBody),
+        (status = 404, description = "Unknown license", body = ErrorBody),
+    )
+)]
+pub async fn render_license(
+    State(state): State<AppState>,
+    Path(spdx_id): Path<String>,
+    Query(params): Query<LicenseParams>,
+) -> Result<Response, AppError> {
+    // Checked against the listing rather than joined onto the path, so the id
+    // can't reach outside the licenses directory
+    let available = available_licenses();
+    if !available.contains(&spdx_id) {
+        return Err(AppError::NotFound(format!(
+            "Unknown license: {}; available: {}",
+            spdx_id,
+            available.join(", ")
+        )));
+    }
+    let username = params.username.trim();
+    if username.is_empty() || username.chars().any(char::is_control) {
+        return Err(AppError::BadRequest("username must be non-empty and free of control characters".to_string()));
+    }
+    let max_len = state.settings.field_limits.max_username_len;
+    if username.chars().count() > max_len {
+        return Err(AppError::BadRequest(format!("username must be at most {} characters", max_len)));
+    }
+
+    let path = std::path::Path::new(LICENSES_DIR).join(&spdx_id);
+    let text = String::from_utf8(read_with_retry(&path, state.settings.read_retry)?)
+        .map_err(|e| AppError::Internal(format!("License {} is not UTF-8: {}", spdx_id, e)))?;
+    let user_info = UserInfo { username: username.to_string(), ..Default::default() };
+    let mut data = TemplateData::new(user_info, state.settings.normalize_unicode);
+    let year = params.year.map_or_else(|| data.generated_at.format("%Y").to_string(), |year| year.to_string());
+    data.extra = vec![("{{year}}".to_string(), year)];
+    println!("[DEBUG] Rendered license {} for {}", spdx_id, username);
+
+    let headers = [(header::CONTENT_TYPE, "text/plain; charset=utf-8"), (header::CACHE_CONTROL, NO_STORE)];
+    Ok((StatusCode::OK, headers, fill_template_content(&text, &data)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use crate::templates::TemplateCache;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn licenses_render_by_spdx_id_and_unknown_ids_are_404() {
+        let app = crate::tests::test_router(TemplateCache::default(), Settings::default());
+        let get = |uri: &str| app.clone().oneshot(Request::get(uri).body(Body::empty()).unwrap());
+
+        let response = get("/license/MIT?username=Ada%20Lovelace&year=1843").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain; charset=utf-8");
+        let text = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&text).contains("Copyright (c) 1843 Ada Lovelace"));
+
+        assert_eq!(get("/license/WTFPL?username=Ada").await.unwrap().status(), StatusCode::NOT_FOUND);
+        assert_eq!(get("/license/..%2Fserver%2FLICENSE?username=Ada").await.unwrap().status(), StatusCode::NOT_FOUND);
+        assert_eq!(get("/license/MIT?username=%20").await.unwrap().status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,104 @@
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std::io::Cursor;
+use utoipa::ToSchema;
+use zip::ZipArchive;
+
+use crate::base_formats::zip_to_tar_gz;
+use crate::cache_headers::NO_STORE;
+use crate::downloads::{download_url, entry_url, AsyncParams, DOWNLOAD_TTL};
+use crate::error::{AppError, ErrorBody};
+use crate::extract::{JsonOrForm, RequestInfo};
+use crate::{generate_project, AppState, UserInfo};
+
+// One way of downloading the whole project
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FormatLink {
+    // "zip" or "tar.gz"
+    pub format: String,
+    pub filename: String,
+    // Single-use, like `/generate-async` links
+    pub url: String,
+}
+
+// One file of the project; reusable until the links expire
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FileLink {
+    pub path: String,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GeneratedLinks {
+    pub formats: Vec<FormatLink>,
+    pub files: Vec<FileLink>,
+    pub expires_in_secs: u64,
+}
+
+// Generate a project once and offer it every way the store can serve it: the
+// zip, a tarball, and each file on its own, so a front-end can let the user
+// pick. Every link expires with the download tokens.
+#[utoipa::path(
+    post,
+    path = "/generate-links",
+    tag = "generate",
+    params(AsyncParams),
+    request_body(content(
+        (UserInfo = "application/json"),
+        (UserInfo = "application/x-www-form-urlencoded"),
+        (UserInfo = "application/json5"),
+        (UserInfo = "application/yaml"),
+    )),
+    responses(
+        (status = 200, description = "Download links for each format and file, valid for ten minutes", body = GeneratedLinks),
+        (status = 204, description = "The request leaves the template with no files"),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Unknown template", body = ErrorBody),
+    )
+)]
+pub async fn generate_links(
+    State(state): State<AppState>,
+    Query(params): Query<AsyncParams>,
+    request_info: RequestInfo,
+    JsonOrForm(user_info): JsonOrForm<UserInfo>,
+) -> Result<Response, AppError> {
+    println!("[DEBUG] Received links {} request for user: {}", params.template, user_info.username);
+    let template = state.template(&params.template)?;
+    let generated = generate_project(&template, user_info, &state.settings, &request_info)
+        .map_err(|e| e.context(&format!("Failed to create {} zip", template.id)))?;
+
+    if generated.entry_count == 0 {
+        return Ok((StatusCode::NO_CONTENT, [(header::CACHE_CONTROL, NO_STORE)]).into_response());
+    }
+    let tar_gz = zip_to_tar_gz(&generated.bytes)?;
+    let paths = ZipArchive::new(Cursor::new(generated.bytes.as_slice()))?
+        .file_names()
+        .filter(|name| !name.ends_with('/'))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    let base_path = &state.settings.base_path;
+    let tar_gz_filename = format!("{}.tar.gz", generated.filename.trim_end_matches(".zip"));
+    let zip_token = state.downloads.insert(generated.filename.clone(), generated.bytes.clone());
+    let tar_gz_token = state.downloads.insert_archive(tar_gz_filename.clone(), "application/gzip", tar_gz);
+    let files_token = state.downloads.insert_entries(generated.filename.clone(), generated.bytes);
+    println!("[DEBUG] Stored {} as zip, tar.gz and {} files", generated.filename, paths.len());
+
+    let body = GeneratedLinks {
+        formats: vec![
+            FormatLink { format: "zip".to_string(), filename: generated.filename, url: download_url(base_path, &zip_token) },
+            FormatLink { format: "tar.gz".to_string(), filename: tar_gz_filename, url: download_url(base_path, &tar_gz_token) },
+        ],
+        files: paths
+            .into_iter()
+            .map(|path| FileLink { url: entry_url(base_path, &files_token, &path), path })
+            .collect(),
+        expires_in_secs: DOWNLOAD_TTL.as_secs(),
+    };
+    Ok(([(header::CACHE_CONTROL, NO_STORE)], Json(body)).into_response())
+}
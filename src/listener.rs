@@ -0,0 +1,44 @@
+use listenfd::ListenFd;
+use std::io;
+use tokio::net::TcpListener;
+
+// Where the server listens unless systemd hands it a socket
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+// The first socket passed in through LISTEN_FDS, when LISTEN_PID names this
+// process. Under socket activation systemd owns the socket, so connections
+// queue up across restarts instead of being refused.
+fn take_inherited(listenfd: &mut ListenFd) -> io::Result<Option<TcpListener>> {
+    let Some(listener) = listenfd.take_tcp_listener(0)? else {
+        return Ok(None);
+    };
+    // Inherited sockets start out blocking, which tokio can't use
+    listener.set_nonblocking(true)?;
+    TcpListener::from_std(listener).map(Some)
+}
+
+// The socket-activated listener when there is one, otherwise a fresh one on
+// `fallback_addr`
+pub async fn bind(fallback_addr: &str) -> io::Result<TcpListener> {
+    let mut listenfd = ListenFd::from_env();
+    if let Some(listener) = take_inherited(&mut listenfd)? {
+        println!("[DEBUG] Listening on the socket passed in by systemd (LISTEN_FDS): {}", listener.local_addr()?);
+        return Ok(listener);
+    }
+    let listener = TcpListener::bind(fallback_addr).await?;
+    println!("[DEBUG] No socket passed in by systemd, listening on {}", listener.local_addr()?);
+    Ok(listener)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn binds_the_fallback_address_without_an_inherited_socket() {
+        assert!(take_inherited(&mut ListenFd::empty()).unwrap().is_none());
+
+        let listener = bind("127.0.0.1:0").await.unwrap();
+        assert!(listener.local_addr().unwrap().ip().is_loopback());
+    }
+}
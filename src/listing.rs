@@ -0,0 +1,104 @@
+use axum::http::{HeaderName, HeaderValue};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::io::Cursor;
+use zip::ZipArchive;
+
+// Comma-separated, percent-encoded entry names of the returned zip, sent when
+// a generate request asks for `list_entries=true`
+pub const ZIP_ENTRIES_HEADER: &str = "x-zip-entries";
+// Sent instead when the listing would be too long for a header
+pub const ZIP_ENTRIES_OMITTED_HEADER: &str = "x-zip-entries-omitted";
+// Proxies commonly reject headers past 8 KiB; stay well under that
+pub const MAX_ZIP_ENTRIES_HEADER_BYTES: usize = 4 * 1024;
+// With `debug=true`: the placeholder tokens replaced across the project, and
+// `name=count` pairs for each entry that had any, percent-encoded like
+// X-Zip-Entries. The per-entry header is left out past the same size limit.
+pub const SUBSTITUTIONS_TOTAL_HEADER: &str = "x-substitutions-total";
+pub const SUBSTITUTIONS_HEADER: &str = "x-substitutions";
+
+// Path separators and common name characters stay readable; commas are
+// always encoded so the list splits cleanly
+pub const ENTRY_NAME: &AsciiSet = &NON_ALPHANUMERIC.remove(b'/').remove(b'.').remove(b'-').remove(b'_');
+
+// The header to attach for a zip: its listing, or a pointer to `/tree` when
+// the listing exceeds the size limit
+pub fn entries_header(zip: &[u8]) -> Option<(HeaderName, HeaderValue)> {
+    let archive = match ZipArchive::new(Cursor::new(zip)) {
+        Ok(archive) => archive,
+        Err(e) => {
+            eprintln!("[ERROR] Cannot list entries of the generated zip: {}", e);
+            return None;
+        }
+    };
+    let listing = (0..archive.len())
+        .filter_map(|i| archive.name_for_index(i))
+        .map(|name| utf8_percent_encode(name, ENTRY_NAME).to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    if listing.len() <= MAX_ZIP_ENTRIES_HEADER_BYTES {
+        let value = HeaderValue::from_str(&listing).ok()?;
+        Some((HeaderName::from_static(ZIP_ENTRIES_HEADER), value))
+    } else {
+        println!("[DEBUG] Zip listing of {} bytes too long for a header, omitting it", listing.len());
+        let value = format!("{} entries; POST /tree for the full listing", archive.len());
+        Some((HeaderName::from_static(ZIP_ENTRIES_OMITTED_HEADER), HeaderValue::from_str(&value).ok()?))
+    }
+}
+
+// The debug headers for the per-entry counts of a generated project
+pub fn substitutions_headers(counts: &[(String, usize)]) -> Vec<(HeaderName, HeaderValue)> {
+    let total = counts.iter().map(|(_, count)| count).sum::<usize>();
+    let mut headers = vec![(HeaderName::from_static(SUBSTITUTIONS_TOTAL_HEADER), HeaderValue::from(total))];
+    let per_entry = counts
+        .iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(name, count)| format!("{}={}", utf8_percent_encode(name, ENTRY_NAME), count))
+        .collect::<Vec<_>>()
+        .join(",");
+    if per_entry.len() > MAX_ZIP_ENTRIES_HEADER_BYTES {
+        println!("[DEBUG] Substitution counts of {} bytes too long for a header, omitting them", per_entry.len());
+    } else if let Ok(value) = HeaderValue::from_str(&per_entry) {
+        headers.push((HeaderName::from_static(SUBSTITUTIONS_HEADER), value));
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::{write::FileOptions, ZipWriter};
+
+    fn zip_with(names: &[String]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for name in names {
+            zip.start_file(name.as_str(), FileOptions::<()>::default()).unwrap();
+            zip.write_all(b"x").unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn lists_encoded_names_or_points_to_tree_when_too_long() {
+        let (name, value) = entries_header(&zip_with(&["src/main.rs".to_string(), "a,b é.txt".to_string()])).unwrap();
+        assert_eq!(name, ZIP_ENTRIES_HEADER);
+        assert_eq!(value, "src/main.rs,a%2Cb%20%C3%A9.txt");
+
+        let many = (0..500).map(|i| format!("src/module_{}.rs", i)).collect::<Vec<_>>();
+        let (name, value) = entries_header(&zip_with(&many)).unwrap();
+        assert_eq!(name, ZIP_ENTRIES_OMITTED_HEADER);
+        assert!(value.to_str().unwrap().starts_with("500 entries"));
+    }
+
+    #[test]
+    fn substitution_headers_total_and_list_entries_with_replacements() {
+        let counts = [("src/main.rs".to_string(), 3), ("logo.png".to_string(), 0), ("a b.md".to_string(), 1)];
+        let headers = substitutions_headers(&counts);
+        assert_eq!(headers[0], (HeaderName::from_static(SUBSTITUTIONS_TOTAL_HEADER), HeaderValue::from(4)));
+        assert_eq!(headers[1].1, "src/main.rs=3,a%20b.md=1");
+
+        let many = (0..500).map(|i| (format!("src/module_{}.rs", i), 1)).collect::<Vec<_>>();
+        assert_eq!(substitutions_headers(&many).len(), 1);
+    }
+}
@@ -0,0 +1,150 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use utoipa::ToSchema;
+
+use crate::builder::{collect_entries, ZipEntry};
+use crate::error::AppError;
+use crate::extract::RequestInfo;
+use crate::settings::{Settings, ALLOW_LOCAL_WRITE_ENV};
+use crate::templates::LoadedTemplate;
+use crate::{prepare_project, UserInfo};
+
+// Returned instead of a zip when a project is written with `?output_dir=`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LocalWriteSummary {
+    pub output_dir: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+// Only plain relative components, so nothing can climb out of the directory
+fn is_plain_relative(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+// Resolve a requested output directory against the allowed root. Relative
+// paths are taken from the root; absolute ones must lie inside it. The target
+// may not exist yet, but if it does it has to be an empty directory.
+pub fn resolve_output_dir(root: &Path, requested: &str) -> Result<PathBuf, AppError> {
+    let requested = Path::new(requested.trim());
+    if requested.as_os_str().is_empty() {
+        return Err(AppError::BadRequest("output_dir must not be empty".to_string()));
+    }
+    if requested.components().any(|c| c == Component::ParentDir) {
+        return Err(AppError::BadRequest("output_dir must not contain '..'".to_string()));
+    }
+    let target = if requested.is_absolute() { requested.to_path_buf() } else { root.join(requested) };
+
+    // Canonicalize the deepest existing ancestor so symlinks can't point outside the root
+    let mut existing = target.as_path();
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        missing.push(existing.file_name().unwrap_or_default().to_owned());
+        existing = existing.parent().unwrap_or(root);
+    }
+    let mut resolved = existing.canonicalize()?;
+    resolved.extend(missing.iter().rev());
+    if !resolved.starts_with(root) {
+        return Err(AppError::BadRequest(format!("output_dir must be inside {}", root.display())));
+    }
+
+    if resolved.is_file() {
+        return Err(AppError::BadRequest(format!("{} is a file", resolved.display())));
+    }
+    if resolved.is_dir() && fs::read_dir(&resolved)?.next().is_some() {
+        return Err(AppError::BadRequest(format!("{} already exists and is not empty", resolved.display())));
+    }
+    Ok(resolved)
+}
+
+// Extract entries into a directory, keeping unix permissions where recorded
+pub fn write_entries(dir: &Path, entries: &[ZipEntry]) -> Result<LocalWriteSummary, AppError> {
+    let mut summary = LocalWriteSummary { output_dir: dir.display().to_string(), files: 0, bytes: 0 };
+    fs::create_dir_all(dir)?;
+    for entry in entries {
+        let relative = Path::new(entry.name.trim_end_matches('/'));
+        if !is_plain_relative(relative) {
+            return Err(AppError::Internal(format!("Refusing to write entry outside the output dir: {}", entry.name)));
+        }
+        let path = dir.join(relative);
+        if entry.name.ends_with('/') {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, &entry.content)?;
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode & 0o7777))?;
+        }
+        summary.files += 1;
+        summary.bytes += entry.content.len() as u64;
+    }
+    Ok(summary)
+}
+
+// Generate a project straight into a directory on the server's filesystem.
+// Only available when `ZEROHUB_ALLOW_LOCAL_WRITE` names an allowed root.
+pub fn generate_to_dir(
+    template: &LoadedTemplate,
+    user_info: UserInfo,
+    settings: &Settings,
+    request: &RequestInfo,
+    output_dir: &str,
+) -> Result<LocalWriteSummary, AppError> {
+    let Some(root) = settings.local_write_root.as_deref() else {
+        return Err(AppError::BadRequest(format!(
+            "Writing to output_dir is disabled; set {} to enable it",
+            ALLOW_LOCAL_WRITE_ENV
+        )));
+    };
+    let dir = resolve_output_dir(root, output_dir)?;
+    let (template_data, build_options) = prepare_project(template, user_info, settings, request)?;
+    let entries = collect_entries(template, &template_data, &build_options)?;
+    let summary = write_entries(&dir, &entries)?;
+    println!(
+        "[DEBUG] Wrote {} project to {}: {} files, {} bytes",
+        template.id, summary.output_dir, summary.files, summary.bytes
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, content: &str) -> ZipEntry {
+        ZipEntry { name: name.to_string(), content: content.as_bytes().to_vec(), unix_mode: None, substitutions: 0 }
+    }
+
+    #[test]
+    fn output_dir_must_stay_inside_the_root() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path().canonicalize().unwrap();
+
+        assert_eq!(resolve_output_dir(&root, "apps/demo").unwrap(), root.join("apps/demo"));
+        let absolute = root.join("demo").display().to_string();
+        assert_eq!(resolve_output_dir(&root, &absolute).unwrap(), root.join("demo"));
+
+        assert!(resolve_output_dir(&root, "../escape").is_err());
+        assert!(resolve_output_dir(&root, "/tmp").is_err());
+
+        fs::create_dir(root.join("taken")).unwrap();
+        fs::write(root.join("taken/file"), "x").unwrap();
+        assert!(resolve_output_dir(&root, "taken").is_err());
+    }
+
+    #[test]
+    fn writes_entries_and_rejects_escaping_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let summary = write_entries(dir.path(), &[entry("src/", ""), entry("src/main.rs", "fn main() {}")]).unwrap();
+        assert_eq!((summary.files, summary.bytes), (1, 12));
+        assert_eq!(fs::read_to_string(dir.path().join("src/main.rs")).unwrap(), "fn main() {}");
+
+        assert!(write_entries(dir.path(), &[entry("../evil", "x")]).is_err());
+    }
+}
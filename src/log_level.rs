@@ -0,0 +1,71 @@
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, Registry};
+
+// Level the server starts at and returns to on SIGUSR2
+pub const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::INFO;
+// Level switched to on SIGUSR1, for live diagnosis
+pub const VERBOSE_LOG_LEVEL: LevelFilter = LevelFilter::DEBUG;
+
+pub type LogLevelHandle = reload::Handle<LevelFilter, Registry>;
+
+// Install the global subscriber behind a reloadable level filter
+pub fn init() -> LogLevelHandle {
+    let (filter, handle) = reload::Layer::new(DEFAULT_LOG_LEVEL);
+    tracing_subscriber::registry().with(filter).with(fmt::layer()).init();
+    handle
+}
+
+pub fn set_level(handle: &LogLevelHandle, level: LevelFilter) {
+    match handle.modify(|filter| *filter = level) {
+        Ok(()) => println!("[DEBUG] Log level set to {}", level),
+        Err(e) => eprintln!("[ERROR] Failed to change log level: {}", e),
+    }
+}
+
+// Bump the level to debug on SIGUSR1 and reset it on SIGUSR2, so production
+// can be diagnosed without a restart
+#[cfg(unix)]
+pub fn spawn_signal_handler(handle: LogLevelHandle) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let (mut verbose, mut reset) = match (signal(SignalKind::user_defined1()), signal(SignalKind::user_defined2())) {
+        (Ok(verbose), Ok(reset)) => (verbose, reset),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("[ERROR] Failed to install log level signal handlers: {}", e);
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                Some(()) = verbose.recv() => set_level(&handle, VERBOSE_LOG_LEVEL),
+                Some(()) = reset.recv() => set_level(&handle, DEFAULT_LOG_LEVEL),
+                else => break,
+            }
+        }
+    });
+    println!("[DEBUG] Send SIGUSR1 for debug logging, SIGUSR2 to reset to {}", DEFAULT_LOG_LEVEL);
+}
+
+#[cfg(not(unix))]
+pub fn spawn_signal_handler(_handle: LogLevelHandle) {
+    println!("[DEBUG] Log level signals are only supported on unix");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reload_handle_changes_the_active_level() {
+        let (filter, handle) = reload::Layer::new(DEFAULT_LOG_LEVEL);
+        let subscriber = tracing_subscriber::registry().with(filter);
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(!tracing::enabled!(tracing::Level::DEBUG));
+            set_level(&handle, VERBOSE_LOG_LEVEL);
+            assert!(tracing::enabled!(tracing::Level::DEBUG));
+            set_level(&handle, DEFAULT_LOG_LEVEL);
+            assert!(!tracing::enabled!(tracing::Level::DEBUG));
+        });
+    }
+}
@@ -0,0 +1,77 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use utoipa::ToSchema;
+
+use crate::throttle::{RetryAfter, Throttle};
+use crate::settings::env_or;
+use crate::AppState;
+
+// "true" starts the server with generation paused
+pub const MAINTENANCE_ENV: &str = "ZEROHUB_MAINTENANCE";
+// Seconds sent in `Retry-After` while generation is paused, see `RetryAfter`
+pub const MAINTENANCE_RETRY_AFTER_SECS_ENV: &str = "ZEROHUB_MAINTENANCE_RETRY_AFTER_SECS";
+
+// Whether the routes that build projects are paused, e.g. during a deploy or
+// template update. Every other route, `/livez`, `/health` and `/ping`
+// included, keeps answering.
+#[derive(Debug, Default)]
+pub struct Maintenance {
+    enabled: AtomicBool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    pub retry_after_secs: u64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MaintenanceToggle {
+    pub enabled: bool,
+}
+
+impl Maintenance {
+    pub fn from_env() -> Self {
+        let maintenance = Maintenance::default();
+        maintenance.set(env_or(MAINTENANCE_ENV, false));
+        maintenance
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    // Switch the mode, logging only real transitions
+    pub fn set(&self, enabled: bool) {
+        if self.enabled.swap(enabled, Ordering::SeqCst) == enabled {
+            return;
+        }
+        if enabled {
+            println!("[WARN] Entering maintenance mode: project generation answers 503");
+        } else {
+            println!("[WARN] Leaving maintenance mode: generation resumed");
+        }
+    }
+
+    pub fn status(&self, retry_after: &RetryAfter) -> MaintenanceStatus {
+        MaintenanceStatus { enabled: self.is_enabled(), retry_after_secs: retry_after.maintenance }
+    }
+}
+
+// Answer 503 with `Retry-After` while paused. Layered on just the routes that
+// build projects, see `build_router`.
+pub async fn reject_during_maintenance(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if state.maintenance.is_enabled() {
+        println!("[DEBUG] Rejecting {} during maintenance", request.uri().path());
+        let retry_after = state.settings.retry_after;
+        return retry_after
+            .error(Throttle::Maintenance, "Generation is paused for maintenance; try again later")
+            .into_response();
+    }
+    next.run(request).await
+}
@@ -0,0 +1,113 @@
+use axum::{
+    extract::{Query, State},
+    response::Response,
+};
+use std::io::{Cursor, Write};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::error::{AppError, ErrorBody};
+use crate::extract::{JsonOrForm, RequestInfo};
+use crate::{generate_project, deliver, AppState, GeneratedZip, GenerateParams, UserInfo};
+
+// Templates packaged by `/generate-nested`, each as `<id>.zip` in the outer zip
+pub const NESTED_TEMPLATES: [&str; 2] = ["server", "client"];
+
+// Package finished project zips into an outer zip. Entries are stored as-is,
+// since the inner zips are already deflated.
+fn create_nested_zip(projects: &[(String, Vec<u8>)]) -> Result<Vec<u8>, AppError> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::<()>::default().compression_method(CompressionMethod::Stored);
+    for (name, bytes) in projects {
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(bytes)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+// Generate the server and client projects for one request and return them as
+// separate zips inside an outer zip, without merging their contents
+#[utoipa::path(
+    post,
+    path = "/generate-nested",
+    tag = "generate",
+    params(GenerateParams),
+    request_body(content(
+        (UserInfo = "application/json"),
+        (UserInfo = "application/x-www-form-urlencoded"),
+        (UserInfo = "application/json5"),
+        (UserInfo = "application/yaml"),
+    )),
+    responses(
+        (status = 200, description = "Outer zip with server.zip and client.zip", content_type = "application/zip"),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 413, description = "Estimated project size over ZEROHUB_MAX_OUTPUT_BYTES", body = ErrorBody),
+        (status = 500, description = "A template could not be built", body = ErrorBody),
+    )
+)]
+pub async fn generate_nested(
+    State(state): State<AppState>,
+    Query(params): Query<GenerateParams>,
+    request_info: RequestInfo,
+    JsonOrForm(user_info): JsonOrForm<UserInfo>,
+) -> Result<Response, AppError> {
+    println!("[DEBUG] Received nested generation request for user: {}", user_info.username);
+    if params.output_dir.is_some() {
+        return Err(AppError::BadRequest("output_dir is not supported for nested generation".to_string()));
+    }
+    let cache = state.templates.snapshot();
+    let templates = NESTED_TEMPLATES
+        .iter()
+        .map(|id| cache.get(id))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let settings = state.settings.clone();
+    let generated = tokio::task::spawn_blocking(move || {
+        let mut projects = Vec::with_capacity(templates.len());
+        let mut filename = None;
+        for template in &templates {
+            let mut user_info = user_info.clone();
+            // The framework only applies to templates that offer a choice
+            if template.config.frameworks.is_empty() {
+                user_info.framework = None;
+            }
+            let project = generate_project(template, user_info, &settings, &request_info)
+                .map_err(|e| e.context(&format!("Failed to create {} zip", template.id)))?;
+            // Named after the first (server) project, e.g. "my-app-nested.zip"
+            filename.get_or_insert_with(|| project.filename.trim_end_matches(".zip").to_string());
+            projects.push((format!("{}.zip", template.id), project.bytes));
+        }
+        Ok::<_, AppError>(GeneratedZip {
+            filename: format!("{}-nested.zip", filename.unwrap_or_default()),
+            bytes: create_nested_zip(&projects)?,
+            template_version: None,
+            entry_count: projects.len(),
+            // Counted per project, not for the zip of zips
+            substitutions: Vec::new(),
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    println!("[DEBUG] Nested zip created: {}, size: {} bytes", generated.filename, generated.bytes.len());
+    deliver(&state.settings, generated, &params).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zip::ZipArchive;
+
+    #[test]
+    fn nested_zip_stores_inner_zips_unchanged() {
+        let projects = vec![
+            ("server.zip".to_string(), b"server bytes".to_vec()),
+            ("client.zip".to_string(), b"client bytes".to_vec()),
+        ];
+        let mut archive = ZipArchive::new(Cursor::new(create_nested_zip(&projects).unwrap())).unwrap();
+        assert_eq!(archive.file_names().collect::<Vec<_>>().len(), 2);
+
+        let client = archive.by_name("client.zip").unwrap();
+        assert_eq!(client.compression(), CompressionMethod::Stored);
+        assert_eq!(client.size(), b"client bytes".len() as u64);
+    }
+}
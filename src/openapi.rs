@@ -0,0 +1,101 @@
+use axum::{extract::State, Json};
+use utoipa::{
+    openapi::{
+        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+        OpenApi as OpenApiDocument, Server,
+    },
+    Modify, OpenApi,
+};
+
+use crate::AppState;
+
+// Declares the bearer scheme used by `/admin/*`
+struct AdminSecurity;
+
+impl Modify for AdminSecurity {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+// OpenAPI 3 description of every endpoint, generated from the handler annotations
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "zerohub", description = "Generate project scaffolds from templates"),
+    paths(
+        crate::index,
+        crate::health,
+        crate::ping,
+        crate::livez,
+        crate::signing::public_key,
+        crate::list_templates,
+        crate::template_stats,
+        crate::template_coverage,
+        crate::profiles::generate_from_profile,
+        crate::generate_server_zip,
+        crate::generate_client_zip,
+        crate::nested::generate_nested,
+        crate::partial::generate_partial,
+        crate::custom_base::generate_custom_base_zip,
+        crate::batch::generate_batch,
+        crate::batch::generate_batch_stream,
+        crate::downloads::generate_async,
+        crate::downloads::download,
+        crate::downloads::download_entry,
+        crate::links::generate_links,
+        crate::tree::project_tree,
+        crate::render::render_file,
+        crate::licenses::render_license,
+        crate::drift::template_drift,
+        crate::drift::migration_pack,
+        crate::validate_template_config,
+        crate::admin::reload_templates,
+        crate::admin::list_cache,
+        crate::admin::clear_cache,
+        crate::admin::maintenance_status,
+        crate::admin::set_maintenance,
+    ),
+    components(schemas(crate::error::ErrorBody, crate::problem::ProblemDocument, crate::ResponseEncoding)),
+    modifiers(&AdminSecurity),
+    tags(
+        (name = "generate", description = "Single project generation"),
+        (name = "batch", description = "Many projects per request"),
+        (name = "templates", description = "Template metadata and config checks"),
+        (name = "admin", description = "Operator endpoints, bearer token required"),
+        (name = "service", description = "Form page and health"),
+    )
+)]
+pub struct ApiDoc;
+
+// Serve the OpenAPI document, with the configured base path as its server URL
+pub async fn openapi_json(State(state): State<AppState>) -> Json<OpenApiDocument> {
+    let mut document = ApiDoc::openapi();
+    document.info.version = env!("CARGO_PKG_VERSION").to_string();
+    // Derived from Cargo.toml, which declares no license
+    document.info.license = None;
+    if !state.settings.base_path.is_empty() {
+        document.servers = Some(vec![Server::new(state.settings.base_path.clone())]);
+    }
+    Json(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_covers_every_route_and_user_info() {
+        let document = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let paths = document["paths"].as_object().unwrap();
+        for path in ["/generate-server-zip", "/generate-custom-base-zip", "/download/{token}", "/admin/reload", "/admin/cache"] {
+            assert!(paths.contains_key(path), "missing {}", path);
+        }
+        let schemas = &document["components"]["schemas"];
+        assert!(schemas["UserInfo"]["properties"]["project_name"].is_object());
+        assert_eq!(schemas["ErrorBody"]["required"], serde_json::json!(["error", "code"]));
+    }
+}
@@ -0,0 +1,131 @@
+use axum::{
+    extract::{Query, State},
+    response::Response,
+    Json,
+};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use utoipa::ToSchema;
+
+use crate::builder::{collect_entries, open_base_archive, write_zip};
+use crate::error::{AppError, ErrorBody};
+use crate::extract::RequestInfo;
+use crate::templates::LoadedTemplate;
+use crate::{check_not_empty, prepare_project, project_filename, substitution_counts, traced_generation, deliver, AppState, GeneratedZip, GenerateParams, UserInfo};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PartialRequest {
+    // Template id to build from, e.g. "server"
+    pub template: String,
+    // Paths of the template's files to include, as listed by
+    // `/templates/{id}/stats` or found in a full build before renaming
+    pub files: Vec<String>,
+    pub user_info: UserInfo,
+}
+
+// Every file a template can produce, by its name in the base archive or its
+// fill file name; directories are left out
+fn template_files(template: &LoadedTemplate) -> Result<BTreeSet<String>, AppError> {
+    let archive = open_base_archive(&template.id, &template.base_zip)?;
+    let mut files = archive
+        .file_names()
+        .filter(|name| !name.ends_with('/'))
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>();
+    files.extend(template.fill_files.iter().map(|(name, _)| name.clone()));
+    Ok(files)
+}
+
+// The requested set, or a 400 naming every path the template doesn't have
+fn requested_files(requested: &[String], known: &BTreeSet<String>) -> Result<BTreeSet<String>, AppError> {
+    if requested.is_empty() {
+        return Err(AppError::BadRequest("files must not be empty".to_string()));
+    }
+    let unknown = requested
+        .iter()
+        .filter(|path| !known.contains(path.as_str()))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if !unknown.is_empty() {
+        return Err(AppError::BadRequest(format!("Unknown template files: {}", unknown.join(", "))));
+    }
+    Ok(requested.iter().cloned().collect())
+}
+
+// Generate only the listed files of a template, filled as in a full build
+#[utoipa::path(
+    post,
+    path = "/generate-partial",
+    tag = "generate",
+    params(GenerateParams),
+    request_body = PartialRequest,
+    responses(
+        (status = 200, description = "Zip with just the requested files, or base64 JSON with `encoding=base64`", content_type = "application/zip",
+            headers(("X-Template-Version" = String), ("X-Zip-Entries" = String, description = "With `list_entries=true`"),
+                    ("X-Substitutions-Total" = String, description = "With `debug=true`"))),
+        (status = 204, description = "Every requested file is excluded by the request"),
+        (status = 400, description = "Invalid request, or paths the template doesn't have", body = ErrorBody),
+        (status = 404, description = "Unknown template", body = ErrorBody),
+        (status = 413, description = "Estimated project size over ZEROHUB_MAX_OUTPUT_BYTES", body = ErrorBody),
+        (status = 422, description = "No files left from a `strict_extra` template", body = ErrorBody),
+    )
+)]
+pub async fn generate_partial(
+    State(state): State<AppState>,
+    Query(params): Query<GenerateParams>,
+    request_info: RequestInfo,
+    Json(request): Json<PartialRequest>,
+) -> Result<Response, AppError> {
+    println!(
+        "[DEBUG] Received partial {} request for {} files, user: {}",
+        request.template,
+        request.files.len(),
+        request.user_info.username
+    );
+    if params.output_dir.is_some() {
+        return Err(AppError::BadRequest("output_dir is not supported for partial generation".to_string()));
+    }
+    let template = state.template(&request.template)?;
+    let only_entries = requested_files(&request.files, &template_files(&template)?)?;
+
+    let settings = state.settings.clone();
+    let generated = tokio::task::spawn_blocking(move || {
+        let project_name = request.user_info.project_name.clone();
+        traced_generation(&template.id, &project_name, || {
+            let (template_data, mut build_options) = prepare_project(&template, request.user_info, &settings, &request_info)?;
+            build_options.only_entries = Some(only_entries);
+            let entries = collect_entries(&template, &template_data, &build_options)?;
+            check_not_empty(&template, &entries)?;
+            Ok(GeneratedZip {
+                filename: project_filename(&template, &template_data),
+                bytes: write_zip(&entries, &build_options)?,
+                template_version: template.version().map(str::to_string),
+                entry_count: entries.len(),
+                substitutions: substitution_counts(&entries),
+            })
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+    .map_err(|e| e.context("Failed to create partial zip"))?;
+
+    println!("[DEBUG] Partial zip created: {}, {} entries", generated.filename, generated.entry_count);
+    deliver(&state.settings, generated, &params).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_paths_are_all_listed() {
+        let known = ["LICENSE", "README.md", "src/main.py"].map(str::to_string).into();
+        let files = |paths: &[&str]| paths.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+
+        let only = requested_files(&files(&["README.md", "LICENSE"]), &known).unwrap();
+        assert_eq!(only.len(), 2);
+        let err = requested_files(&files(&["README.md", "setup.py", "src/"]), &known).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown template files: setup.py, src/");
+        assert!(requested_files(&[], &known).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+// Form fields `GET /` can pre-fill from its query string, e.g.
+// `/?username=alice&project_name=Demo`. Other parameters are ignored.
+pub const PREFILL_FIELDS: [&str; 6] = ["username", "email", "project_name", "project_slug", "project_description", "framework"];
+
+// Escape a value for use in HTML text and double- or single-quoted attributes
+pub fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Fill the form in `html` with the known fields of `query`: a `value` for
+// inputs, the text of the textarea, and `selected` on the matching option.
+// Fields are found by their `id="x" name="x"` attributes; a field the page
+// doesn't have is skipped. Without any known field the page is unchanged.
+pub fn prefill<'a>(html: &'a str, query: &BTreeMap<String, String>) -> Cow<'a, str> {
+    let values = PREFILL_FIELDS
+        .iter()
+        .filter_map(|field| query.get(*field).map(|value| (*field, value.trim())))
+        .filter(|(_, value)| !value.is_empty())
+        .collect::<Vec<_>>();
+    if values.is_empty() {
+        return Cow::Borrowed(html);
+    }
+
+    let mut html = html.to_string();
+    for (field, value) in values {
+        let marker = format!("id=\"{}\" name=\"{}\"", field, field);
+        let Some(start) = html.find(&marker) else {
+            println!("[WARN] Not pre-filling {}: the form has no such field", field);
+            continue;
+        };
+        let tag = &html[html[..start].rfind('<').unwrap_or(0)..start];
+        let (is_textarea, is_select) = (tag.starts_with("<textarea"), tag.starts_with("<select"));
+        let escaped = escape_html(value);
+        if is_textarea {
+            if let Some(close) = html[start..].find("</textarea>") {
+                html.insert_str(start + close, &escaped);
+            }
+        } else if is_select {
+            let end = html[start..].find("</select>").map_or(html.len(), |end| start + end);
+            let option = format!("<option value=\"{}\"", escaped);
+            if let Some(at) = html[start..end].find(&option) {
+                html.insert_str(start + at + option.len(), " selected");
+            }
+        } else {
+            html.insert_str(start + marker.len(), &format!(" value=\"{}\"", escaped));
+        }
+    }
+    Cow::Owned(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORM: &str = concat!(
+        "<input type=\"text\" id=\"username\" name=\"username\" required>",
+        "<textarea id=\"project_description\" name=\"project_description\"></textarea>",
+        "<select id=\"framework\" name=\"framework\"><option value=\"react\">React</option><option value=\"vue\">Vue</option></select>",
+    );
+
+    fn query(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn known_fields_are_filled_and_escaped() {
+        let filled = prefill(
+            FORM,
+            &query(&[
+                ("username", "\"><script>x</script>"),
+                ("project_description", " A <b>demo</b> "),
+                ("framework", "vue"),
+                ("secret_key", "nope"),
+            ]),
+        );
+        assert!(filled.contains("name=\"username\" value=\"&quot;&gt;&lt;script&gt;x&lt;/script&gt;\" required>"));
+        assert!(filled.contains("name=\"project_description\">A &lt;b&gt;demo&lt;/b&gt;</textarea>"));
+        assert!(filled.contains("<option value=\"vue\" selected>Vue"));
+        assert!(!filled.contains("nope"));
+    }
+
+    #[test]
+    fn unknown_or_empty_values_leave_the_page_unchanged() {
+        assert!(matches!(prefill(FORM, &query(&[("other", "x"), ("username", " ")])), Cow::Borrowed(_)));
+        assert_eq!(prefill(FORM, &query(&[("framework", "angular")])), FORM);
+    }
+}
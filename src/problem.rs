@@ -0,0 +1,134 @@
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::error::ErrorBody;
+use crate::validation::FieldError;
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+// RFC 7807 problem document, sent instead of `ErrorBody` to clients that
+// accept `application/problem+json`. `code`, `fields`, `template_id` and the
+// retry members are extension members carrying the same values as the plain
+// envelope.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProblemDocument {
+    // URN naming the error kind, e.g. "urn:zerohub:problem:validation-failed"
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: &'static str,
+    pub status: u16,
+    pub detail: String,
+    // Path of the request that failed
+    pub instance: String,
+    pub code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<FieldError>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_at: Option<String>,
+}
+
+// Short summary for each `AppError` code, the same for every occurrence
+fn title_for(code: &str) -> &'static str {
+    match code {
+        "BAD_REQUEST" => "Bad request",
+        "VALIDATION_FAILED" => "Request validation failed",
+        "UNAUTHORIZED" => "Missing or wrong admin token",
+        "NOT_FOUND" => "Not found",
+        "PAYLOAD_TOO_LARGE" => "Payload too large",
+        "UNPROCESSABLE" => "Nothing to generate",
+        "BASE_ARCHIVE_CORRUPT" => "Template base archive is corrupt",
+        "SERVICE_UNAVAILABLE" => "Paused for maintenance",
+        "RATE_LIMITED" => "Too many requests",
+        "QUEUE_FULL" => "Server busy",
+        _ => "Internal server error",
+    }
+}
+
+impl ProblemDocument {
+    pub fn new(body: ErrorBody, status: u16, instance: String) -> Self {
+        ProblemDocument {
+            problem_type: format!("urn:zerohub:problem:{}", body.code.to_lowercase().replace('_', "-")),
+            title: title_for(body.code),
+            status,
+            detail: body.error,
+            instance,
+            code: body.code,
+            template_id: body.template_id,
+            fields: body.fields,
+            retry_after_secs: body.retry_after_secs,
+            retry_at: body.retry_at,
+        }
+    }
+}
+
+// Whether the Accept header lists problem+json with a non-zero quality
+pub fn wants_problem_json(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|range| {
+            let mut params = range.split(';').map(str::trim);
+            let is_problem = params.next().is_some_and(|media| media.eq_ignore_ascii_case(PROBLEM_JSON));
+            let refused = params.any(|p| matches!(p.strip_prefix("q="), Some(q) if q.parse::<f32>().is_ok_and(|q| q == 0.0)));
+            is_problem && !refused
+        })
+}
+
+// Middleware turning `AppError` responses into problem documents for clients
+// that ask for them. Errors carry their `ErrorBody` in the response
+// extensions, so nothing is re-parsed; other responses pass through.
+pub async fn problem_json(request: Request, next: Next) -> Response {
+    let wanted = wants_problem_json(request.headers());
+    let instance = request.uri().path().to_string();
+    let mut response = next.run(request).await;
+    if !wanted {
+        return response;
+    }
+    let Some(body) = response.extensions_mut().remove::<ErrorBody>() else {
+        return response;
+    };
+
+    let status = response.status();
+    let problem = ProblemDocument::new(body, status.as_u16(), instance);
+    let mut problem_response = (status, Json(problem)).into_response();
+    for (name, value) in response.headers() {
+        if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+            problem_response.headers_mut().insert(name, value.clone());
+        }
+    }
+    problem_response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+    problem_response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accept(value: &str) -> HeaderMap {
+        HeaderMap::from_iter([(header::ACCEPT, HeaderValue::from_str(value).unwrap())])
+    }
+
+    #[test]
+    fn problem_json_is_picked_from_the_accept_header() {
+        assert!(wants_problem_json(&accept("application/problem+json")));
+        assert!(wants_problem_json(&accept("application/json, application/problem+json;q=0.9")));
+        assert!(!wants_problem_json(&accept("application/problem+json;q=0")));
+        assert!(!wants_problem_json(&accept("application/json")));
+        assert!(!wants_problem_json(&HeaderMap::new()));
+    }
+}
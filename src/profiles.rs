@@ -0,0 +1,218 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::builder::LineEndings;
+use crate::error::{AppError, ErrorBody};
+use crate::extract::{JsonOrForm, RequestInfo};
+use crate::{generate_project, local_write, deliver, AppState, GenerateParams, UserInfo};
+
+// Environment variable with a path to a TOML file of named profiles
+pub const PROFILES_FILE_ENV: &str = "ZEROHUB_PROFILES_FILE";
+
+// A named preset: which template to build and defaults for everything but the
+// personal fields. A request naming the profile only has to send what differs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub template: String,
+    pub project_description: Option<String>,
+    pub framework: Option<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    pub deterministic: Option<bool>,
+    pub sort_entries: Option<bool>,
+    pub include_checksums: Option<bool>,
+    pub line_endings: Option<LineEndings>,
+    pub include_provenance: Option<bool>,
+    pub include_getting_started: Option<bool>,
+    pub include_funding: Option<bool>,
+    pub json_indent: Option<u8>,
+    pub canonicalize_json: Option<bool>,
+    pub include_banner: Option<bool>,
+    pub default_placeholder_value: Option<String>,
+    pub compression: Option<String>,
+    #[serde(default)]
+    pub extra: BTreeMap<String, String>,
+}
+
+impl Profile {
+    // Fill what the request left out. Request values win; switches can only be
+    // turned on by the request, and `exclude` patterns add to the profile's.
+    pub fn apply(&self, mut user_info: UserInfo) -> UserInfo {
+        if user_info.project_description.trim().is_empty() {
+            if let Some(description) = &self.project_description {
+                user_info.project_description = description.clone();
+            }
+        }
+        user_info.framework = user_info.framework.or_else(|| self.framework.clone());
+        user_info.exclude.splice(0..0, self.exclude.iter().cloned());
+        user_info.deterministic |= self.deterministic.unwrap_or(false);
+        user_info.sort_entries = user_info.sort_entries.or(self.sort_entries);
+        user_info.include_checksums |= self.include_checksums.unwrap_or(false);
+        user_info.line_endings = user_info.line_endings.or(self.line_endings);
+        user_info.include_provenance |= self.include_provenance.unwrap_or(false);
+        user_info.include_getting_started |= self.include_getting_started.unwrap_or(false);
+        user_info.include_funding |= self.include_funding.unwrap_or(false);
+        user_info.json_indent = user_info.json_indent.or(self.json_indent);
+        user_info.canonicalize_json |= self.canonicalize_json.unwrap_or(false);
+        user_info.include_banner |= self.include_banner.unwrap_or(false);
+        user_info.default_placeholder_value =
+            user_info.default_placeholder_value.or_else(|| self.default_placeholder_value.clone());
+        user_info.compression = user_info.compression.or_else(|| self.compression.clone());
+        for (key, value) in &self.extra {
+            user_info.extra.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        user_info
+    }
+}
+
+// Profiles by name, as loaded from `ZEROHUB_PROFILES_FILE`
+#[derive(Debug, Clone, Default)]
+pub struct Profiles {
+    profiles: BTreeMap<String, Profile>,
+}
+
+impl Profiles {
+    // Each top-level table is one profile, e.g. `[python-api]`
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let profiles = toml::from_str(source).map_err(|e| e.to_string())?;
+        Ok(Profiles { profiles })
+    }
+
+    // Read the file named by `ZEROHUB_PROFILES_FILE`; no profiles when it is
+    // unset or can't be used
+    pub fn from_env() -> Self {
+        let Ok(path) = std::env::var(PROFILES_FILE_ENV) else {
+            return Profiles::default();
+        };
+        match fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|s| Profiles::parse(&s)) {
+            Ok(profiles) => {
+                println!("[DEBUG] Loaded {} profiles from {}", profiles.profiles.len(), path);
+                profiles
+            }
+            Err(e) => {
+                eprintln!("[ERROR] Failed to load {} ({}): {}", PROFILES_FILE_ENV, path, e);
+                Profiles::default()
+            }
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Profile, AppError> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| AppError::BadRequest(format!("Unknown profile: {}", name)))
+    }
+
+    // Resolve the request's profile, if any, for a build of `template_id`
+    pub fn apply(&self, template_id: &str, user_info: UserInfo) -> Result<UserInfo, AppError> {
+        let Some(name) = user_info.profile.clone() else {
+            return Ok(user_info);
+        };
+        let profile = self.get(&name)?;
+        if profile.template != template_id {
+            return Err(AppError::BadRequest(format!(
+                "Profile {} is for the {} template, not {}",
+                name, profile.template, template_id
+            )));
+        }
+        println!("[DEBUG] Applying profile {} to {} request", name, template_id);
+        Ok(profile.apply(user_info))
+    }
+}
+
+// Generate a project from a profile, with its template taken from the profile
+#[utoipa::path(
+    post,
+    path = "/generate",
+    tag = "generate",
+    params(GenerateParams),
+    request_body(
+        description = "UserInfo with `profile` set; the profile supplies the template and defaults",
+        content(
+            (UserInfo = "application/json"),
+            (UserInfo = "application/x-www-form-urlencoded"),
+            (UserInfo = "application/json5"),
+            (UserInfo = "application/yaml"),
+        )
+    ),
+    responses(
+        (status = 200, description = "Generated project zip, or base64 JSON with `encoding=base64`", content_type = "application/zip",
+            headers(("X-Template-Version" = String), ("X-Zip-Entries" = String, description = "With `list_entries=true`"),
+                    ("X-Substitutions-Total" = String, description = "With `debug=true`"))),
+        (status = 204, description = "The request leaves the template with no files"),
+        (status = 201, description = "Project written to `output_dir`", body = local_write::LocalWriteSummary),
+        (status = 400, description = "Missing or unknown profile, or invalid request", body = ErrorBody),
+        (status = 413, description = "Estimated project size over ZEROHUB_MAX_OUTPUT_BYTES", body = ErrorBody),
+        (status = 422, description = "No files left from a `strict_extra` template", body = ErrorBody),
+    )
+)]
+pub async fn generate_from_profile(
+    State(state): State<AppState>,
+    Query(params): Query<GenerateParams>,
+    request_info: RequestInfo,
+    JsonOrForm(user_info): JsonOrForm<UserInfo>,
+) -> Result<Response, AppError> {
+    let name = user_info
+        .profile
+        .as_deref()
+        .ok_or_else(|| AppError::BadRequest("profile is required".to_string()))?;
+    let template = state.template(&state.settings.profiles.get(name)?.template)?;
+    println!("[DEBUG] Received {} profile request for user: {}", name, user_info.username);
+
+    if let Some(output_dir) = params.output_dir.as_deref() {
+        let summary = local_write::generate_to_dir(&template, user_info, &state.settings, &request_info, output_dir)?;
+        return Ok((StatusCode::CREATED, Json(summary)).into_response());
+    }
+    let generated = generate_project(&template, user_info, &state.settings, &request_info)
+        .map_err(|e| e.context(&format!("Failed to create {} zip", template.id)))?;
+    println!("[DEBUG] Successfully created {} zip: {}, size: {} bytes", template.id, generated.filename, generated.bytes.len());
+    deliver(&state.settings, generated, &params).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+        [python-api]
+        template = "server"
+        project_description = "A Python API"
+        exclude = ["docs/**"]
+        deterministic = true
+        extra = { license = "MIT" }
+    "#;
+
+    #[test]
+    fn profile_fills_defaults_and_request_values_win() {
+        let profiles = Profiles::parse(SOURCE).unwrap();
+        let request = UserInfo {
+            username: "alice".to_string(),
+            profile: Some("python-api".to_string()),
+            exclude: vec!["tests/**".to_string()],
+            extra: [("license".to_string(), "Apache-2.0".to_string())].into(),
+            ..Default::default()
+        };
+
+        let resolved = profiles.apply("server", request.clone()).unwrap();
+        assert_eq!(resolved.project_description, "A Python API");
+        assert_eq!(resolved.exclude, ["docs/**", "tests/**"]);
+        assert!(resolved.deterministic);
+        assert_eq!(resolved.extra["license"], "Apache-2.0");
+
+        assert!(profiles.apply("client", request.clone()).is_err());
+        let unknown = UserInfo { profile: Some("nope".to_string()), ..request };
+        assert!(profiles.apply("server", unknown).is_err());
+    }
+
+    #[test]
+    fn unknown_profile_keys_are_rejected() {
+        assert!(Profiles::parse("[p]\ntemplate = \"server\"\ncompresion = 9\n").is_err());
+    }
+}
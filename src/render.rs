@@ -0,0 +1,128 @@
+use axum::{
+    extract::{Path, RawQuery, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use flate2::{write::GzEncoder, Compression};
+use serde::Deserialize;
+use std::io::Write;
+
+use crate::builder::collect_entries;
+use crate::cache_headers::NO_STORE;
+use crate::error::{AppError, ErrorBody};
+use crate::extract::RequestInfo;
+use crate::{form, prepare_project, AppState, UserInfo};
+
+#[derive(Debug, Default, Deserialize)]
+struct RenderParams {
+    // Send the file gzip-compressed with `Content-Encoding: gzip`
+    #[serde(default)]
+    gzip: bool,
+}
+
+pub fn gzip(content: &[u8]) -> Result<Vec<u8>, AppError> {
+    let mut encoder = GzEncoder::new(Vec::with_capacity(content.len() / 2), Compression::default());
+    encoder.write_all(content)?;
+    Ok(encoder.finish()?)
+}
+
+// Render one file of a template's output. The UserInfo fields come from the
+// query string, decoded like a form body, e.g.
+// `/render/server/README.md?username=alice&email=a@b.c&project_name=Demo&project_description=d`
+#[utoipa::path(
+    get,
+    path = "/render/{template}/{path}",
+    tag = "generate",
+    params(
+        ("template" = String, Path, description = "Template id, e.g. `server`"),
+        ("path" = String, Path, description = "Entry name inside the generated project, e.g. `README.md`"),
+        ("gzip" = Option<bool>, Query, description = "Gzip the file and set `Content-Encoding: gzip`"),
+    ),
+    responses(
+        (status = 200, description = "The filled file, typed by its extension"),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Unknown template or file", body = ErrorBody),
+    )
+)]
+pub async fn render_file(
+    State(state): State<AppState>,
+    Path((template_id, path)): Path<(String, String)>,
+    RawQuery(query): RawQuery,
+    request_info: RequestInfo,
+) -> Result<Response, AppError> {
+    let query = query.unwrap_or_default();
+    let invalid = |e: serde::de::value::Error| AppError::BadRequest(format!("Invalid query string: {}", e));
+    let params: RenderParams = form::from_bytes(query.as_bytes()).map_err(invalid)?;
+    let user_info: UserInfo = form::from_bytes(query.as_bytes()).map_err(invalid)?;
+
+    let template = state.template(&template_id)?;
+    let (template_data, build_options) = prepare_project(&template, user_info, &state.settings, &request_info)?;
+    let entry = collect_entries(&template, &template_data, &build_options)?
+        .into_iter()
+        .find(|entry| entry.name == path)
+        .ok_or_else(|| AppError::NotFound(format!("Template {} has no file {}", template_id, path)))?;
+    println!("[DEBUG] Rendered {}/{} ({} bytes, gzip: {})", template_id, path, entry.content.len(), params.gzip);
+
+    let content_type = mime_guess::from_path(&entry.name).first_or_octet_stream().to_string();
+    let headers = [(header::CONTENT_TYPE, content_type), (header::CACHE_CONTROL, NO_STORE.to_string())];
+    if params.gzip {
+        let body = gzip(&entry.content)?;
+        return Ok((StatusCode::OK, headers, [(header::CONTENT_ENCODING, "gzip")], body).into_response());
+    }
+    Ok((StatusCode::OK, headers, entry.content).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use crate::templates::{LoadedTemplate, TemplateCache};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use tower::ServiceExt;
+
+    #[test]
+    fn gzip_round_trips() {
+        let content = b"# Demo\n".repeat(100);
+        let compressed = gzip(&content).unwrap();
+        assert!(compressed.len() < content.len());
+
+        let mut decoded = Vec::new();
+        GzDecoder::new(compressed.as_slice()).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, content);
+    }
+
+    #[tokio::test]
+    async fn files_render_by_nested_path_optionally_gzipped() {
+        let mut base = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        base.start_file("src/app/settings.json", zip::write::SimpleFileOptions::default()).unwrap();
+        base.write_all(b"{}\n").unwrap();
+        let readme = vec![("README.md".to_string(), "# {{project_name}}\n".to_string())];
+        let template = LoadedTemplate::for_test("server", base.finish().unwrap().into_inner(), readme);
+        let app = crate::tests::test_router(TemplateCache::from_templates(vec![template]), Settings::default());
+        let get = |path: &str, gzip: bool| {
+            let query = "username=alice&email=a%40b.c&project_name=Demo&project_description=d";
+            let uri = format!("/render/server/{}?{}&gzip={}", path, query, gzip);
+            app.clone().oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        };
+
+        let response = get("README.md", true).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/markdown");
+        let compressed = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut decoded = String::new();
+        GzDecoder::new(compressed.as_ref()).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, "# Demo\n");
+
+        let response = get("src/app/settings.json", false).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(to_bytes(response.into_body(), usize::MAX).await.unwrap(), "{}\n");
+
+        assert_eq!(get("src/app/missing.json", false).await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,103 @@
+use aws_sdk_s3::{error::DisplayErrorContext, primitives::ByteStream, Client};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::settings::env_or;
+use crate::GeneratedZip;
+
+// Bucket generated zips are uploaded to with `upload_s3=true`; uploads are off
+// when unset. Credentials, region and the endpoint of an S3-compatible store
+// (`AWS_ENDPOINT_URL`) come from the usual AWS_* variables.
+pub const S3_BUCKET_ENV: &str = "ZEROHUB_S3_BUCKET";
+// Prefix for object keys, e.g. "zerohub/"; a trailing '/' is added if missing
+pub const S3_PREFIX_ENV: &str = "ZEROHUB_S3_PREFIX";
+// "true" for stores that only support path-style URLs, such as MinIO
+pub const S3_FORCE_PATH_STYLE_ENV: &str = "ZEROHUB_S3_FORCE_PATH_STYLE";
+pub const DEFAULT_S3_PREFIX: &str = "zerohub/";
+// User metadata key holding the zip's signature, read back as
+// `x-amz-meta-signature`
+pub const SIGNATURE_METADATA: &str = "signature";
+
+// Where uploads go, set up once at startup
+#[derive(Debug, Clone)]
+pub struct S3Target {
+    client: Client,
+    bucket: String,
+    prefix: String,
+}
+
+// Location of an uploaded zip, returned instead of the zip itself
+#[derive(Debug, Serialize, ToSchema)]
+pub struct S3Upload {
+    pub bucket: String,
+    pub key: String,
+    // "s3://<bucket>/<key>"
+    pub url: String,
+    pub bytes: usize,
+    pub template_version: Option<String>,
+    // Base64 Ed25519 signature of the zip when signing is on, as `X-Signature`
+    // would carry it; also stored as the object's `signature` metadata
+    pub signature: Option<String>,
+}
+
+// "<prefix><id>/<filename>", so projects of the same name never overwrite each other
+pub fn object_key(prefix: &str, id: &str, filename: &str) -> String {
+    let separator = if prefix.is_empty() || prefix.ends_with('/') { "" } else { "/" };
+    format!("{}{}{}/{}", prefix, separator, id, filename)
+}
+
+impl S3Target {
+    pub async fn from_env() -> Option<Self> {
+        let bucket = std::env::var(S3_BUCKET_ENV).ok().filter(|bucket| !bucket.trim().is_empty())?;
+        let shared = aws_config::load_from_env().await;
+        let config = aws_sdk_s3::config::Builder::from(&shared)
+            .force_path_style(env_or(S3_FORCE_PATH_STYLE_ENV, false))
+            .build();
+        let prefix = std::env::var(S3_PREFIX_ENV).unwrap_or_else(|_| DEFAULT_S3_PREFIX.to_string());
+        println!("[DEBUG] S3 uploads enabled to s3://{}/{}", bucket, prefix);
+        Some(S3Target { client: Client::from_conf(config), bucket, prefix })
+    }
+
+    pub async fn upload(&self, generated: GeneratedZip, signature: Option<String>) -> Result<S3Upload, AppError> {
+        let key = object_key(&self.prefix, &Uuid::new_v4().to_string(), &generated.filename);
+        let url = format!("s3://{}/{}", self.bucket, key);
+        let bytes = generated.bytes.len();
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .content_type("application/zip")
+            .set_metadata(signature.clone().map(|signature| HashMap::from([(SIGNATURE_METADATA.to_string(), signature)])))
+            .body(ByteStream::from(generated.bytes))
+            .send()
+            .await
+            .map_err(|e| {
+                eprintln!("[ERROR] Upload to {} failed: {}", url, DisplayErrorContext(&e));
+                AppError::Internal(format!("Failed to upload {}: {}", url, e))
+            })?;
+        println!("[DEBUG] Uploaded {} ({} bytes)", url, bytes);
+        Ok(S3Upload {
+            bucket: self.bucket.clone(),
+            key,
+            url,
+            bytes,
+            template_version: generated.template_version,
+            signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn object_keys_are_unique_per_upload_under_the_prefix() {
+        assert_eq!(object_key("zerohub/", "id1", "demo.zip"), "zerohub/id1/demo.zip");
+        assert_eq!(object_key("builds", "id1", "demo.zip"), "builds/id1/demo.zip");
+        assert_eq!(object_key("", "id1", "demo.zip"), "id1/demo.zip");
+    }
+}
@@ -0,0 +1,319 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::admin::ADMIN_TOKEN_ENV;
+use crate::blocklist::BlockedNames;
+use crate::cors::CorsSettings;
+use crate::profiles::Profiles;
+use crate::signing::ZipSigner;
+#[cfg(feature = "s3")]
+use crate::s3_upload::S3Target;
+use crate::static_mounts::StaticMount;
+use crate::strict_startup::strict_startup_enabled;
+use crate::temp_files::TempFileSlots;
+use crate::templates::ReadRetry;
+use crate::throttle::RetryAfter;
+use crate::validation::{ExtraLimits, FieldLimits};
+
+// Environment variable capping the request body size, measured after decompression
+pub const MAX_BODY_BYTES_ENV: &str = "ZEROHUB_MAX_BODY_BYTES";
+pub const DEFAULT_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+// Limits for `/generate-custom-base-zip`: the raw upload size, and the total size
+// the uploaded archive may decompress to (zip-bomb guard)
+pub const MAX_UPLOAD_BYTES_ENV: &str = "ZEROHUB_MAX_UPLOAD_BYTES";
+pub const DEFAULT_MAX_UPLOAD_BYTES: usize = 64 * 1024 * 1024;
+pub const MAX_BASE_UNCOMPRESSED_BYTES_ENV: &str = "ZEROHUB_MAX_BASE_UNCOMPRESSED_BYTES";
+pub const DEFAULT_MAX_BASE_UNCOMPRESSED_BYTES: u64 = 256 * 1024 * 1024;
+// Cap on the uncompressed size of any single base archive entry, since each
+// one is read into memory whole
+pub const MAX_BASE_ENTRY_BYTES_ENV: &str = "ZEROHUB_MAX_BASE_ENTRY_BYTES";
+pub const DEFAULT_MAX_BASE_ENTRY_BYTES: u64 = 50 * 1024 * 1024;
+// Cap on the estimated uncompressed size of a generated project
+pub const MAX_OUTPUT_BYTES_ENV: &str = "ZEROHUB_MAX_OUTPUT_BYTES";
+pub const DEFAULT_MAX_OUTPUT_BYTES: u64 = 100 * 1024 * 1024;
+// Retries for template file reads, see `ReadRetry`
+pub const READ_ATTEMPTS_ENV: &str = "ZEROHUB_READ_ATTEMPTS";
+pub const READ_RETRY_DELAY_MS_ENV: &str = "ZEROHUB_READ_RETRY_DELAY_MS";
+// Bounds on the request's `extra` map, see `ExtraLimits`
+pub const MAX_EXTRA_ENTRIES_ENV: &str = "ZEROHUB_MAX_EXTRA_ENTRIES";
+pub const MAX_EXTRA_KEY_LEN_ENV: &str = "ZEROHUB_MAX_EXTRA_KEY_LEN";
+pub const MAX_EXTRA_VALUE_LEN_ENV: &str = "ZEROHUB_MAX_EXTRA_VALUE_LEN";
+// Bounds on UserInfo's string fields, see `FieldLimits`
+pub const MAX_USERNAME_LEN_ENV: &str = "ZEROHUB_MAX_USERNAME_LEN";
+pub const MAX_EMAIL_LEN_ENV: &str = "ZEROHUB_MAX_EMAIL_LEN";
+pub const MAX_PROJECT_NAME_LEN_ENV: &str = "ZEROHUB_MAX_PROJECT_NAME_LEN";
+pub const MAX_DESCRIPTION_LEN_ENV: &str = "ZEROHUB_MAX_DESCRIPTION_LEN";
+pub const MAX_OPTION_LEN_ENV: &str = "ZEROHUB_MAX_OPTION_LEN";
+// strftime pattern for generation timestamps, e.g. "%Y-%m-%dT%H:%M:%SZ" for ISO 8601
+pub const TIMESTAMP_FORMAT_ENV: &str = "ZEROHUB_TIMESTAMP_FORMAT";
+pub const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S UTC";
+// Set to false to keep substituted values in the form they were sent instead
+// of NFC-normalizing them
+pub const NORMALIZE_UNICODE_ENV: &str = "ZEROHUB_NORMALIZE_UNICODE";
+// Directory that `?output_dir=` may write generated projects into; local
+// writes are disabled unless it is set to an existing directory
+pub const ALLOW_LOCAL_WRITE_ENV: &str = "ZEROHUB_ALLOW_LOCAL_WRITE";
+// Artificial response delay for load testing; only honored in builds with the
+// `debug-delay` feature, see `debug_delay`
+pub const DEBUG_DELAY_MS_ENV: &str = "ZEROHUB_DEBUG_DELAY_MS";
+pub const DEBUG_JITTER_MS_ENV: &str = "ZEROHUB_DEBUG_JITTER_MS";
+// Environment variable with a path prefix to serve everything under, e.g. "/zerohub"
+pub const BASE_PATH_ENV: &str = "ZEROHUB_BASE_PATH";
+// HTML file served at `/` instead of the form while templates are unavailable;
+// `{{problems}}` in it becomes one `<li>` per unavailable template
+pub const UNAVAILABLE_PAGE_ENV: &str = "ZEROHUB_UNAVAILABLE_PAGE";
+
+// Server-wide settings, read once from the environment at startup
+#[derive(Debug, Clone)]
+pub struct Settings {
+    // Bearer token for `/admin/*`; admin endpoints are disabled when unset
+    pub admin_token: Option<String>,
+    pub max_body_bytes: usize,
+    pub max_upload_bytes: usize,
+    pub max_base_uncompressed_bytes: u64,
+    pub max_base_entry_bytes: u64,
+    pub max_output_bytes: u64,
+    // Normalized route prefix: empty for the root, otherwise "/segment" without a trailing slash
+    pub base_path: String,
+    // Project names rejected with 400, see `blocklist`
+    pub blocked_names: BlockedNames,
+    pub read_retry: ReadRetry,
+    // Validated strftime pattern, see `format_timestamp`
+    pub timestamp_format: String,
+    pub extra_limits: ExtraLimits,
+    pub field_limits: FieldLimits,
+    // NFC-normalize request values before they are substituted, see `TemplateData::new`
+    pub normalize_unicode: bool,
+    // Canonicalized root for `?output_dir=`, see `local_write`
+    pub local_write_root: Option<PathBuf>,
+    // Named request presets, see `profiles`
+    pub profiles: Profiles,
+    // Extra directories served next to `/static`, see `static_mounts`. Loaded
+    // by the caller, since a bad mounts file stops startup.
+    pub static_mounts: Vec<StaticMount>,
+    // Browser origins allowed to call the API, see `cors`
+    pub cors: Option<CorsSettings>,
+    // `Retry-After` seconds for each reason a request is throttled
+    pub retry_after: RetryAfter,
+    // Bound on the temp files open at once, see `temp_files`
+    pub temp_files: TempFileSlots,
+    // Page for `/` while templates are unavailable; the built-in one when unset
+    pub unavailable_page: Option<String>,
+    // Every template must render cleanly, at startup and on every reload; see
+    // `strict_startup`
+    pub strict_startup: bool,
+    // Key generated zips are signed with, see `signing`. Loaded by the caller,
+    // since a bad key stops startup.
+    pub signer: Option<ZipSigner>,
+    // Bucket for `upload_s3=true`, see `s3_upload`. Set up by the caller, since
+    // the AWS configuration is loaded asynchronously.
+    #[cfg(feature = "s3")]
+    pub s3: Option<S3Target>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            admin_token: None,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            max_upload_bytes: DEFAULT_MAX_UPLOAD_BYTES,
+            max_base_uncompressed_bytes: DEFAULT_MAX_BASE_UNCOMPRESSED_BYTES,
+            max_base_entry_bytes: DEFAULT_MAX_BASE_ENTRY_BYTES,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            base_path: String::new(),
+            blocked_names: BlockedNames::default(),
+            read_retry: ReadRetry::default(),
+            timestamp_format: DEFAULT_TIMESTAMP_FORMAT.to_string(),
+            extra_limits: ExtraLimits::default(),
+            field_limits: FieldLimits::default(),
+            normalize_unicode: true,
+            local_write_root: None,
+            profiles: Profiles::default(),
+            static_mounts: Vec::new(),
+            cors: None,
+            retry_after: RetryAfter::default(),
+            temp_files: TempFileSlots::default(),
+            unavailable_page: None,
+            strict_startup: false,
+            signer: None,
+            #[cfg(feature = "s3")]
+            s3: None,
+        }
+    }
+}
+
+// Parse an environment variable, keeping the default when it is unset or invalid
+pub fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    match std::env::var(name) {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            println!("[WARN] Ignoring invalid {}={:?}, using the default", name, value);
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+// The custom unavailable page, read once; a page that can't be read falls back
+// to the built-in one rather than stopping startup
+fn unavailable_page_from_env() -> Option<String> {
+    let path = std::env::var(UNAVAILABLE_PAGE_ENV).ok().filter(|p| !p.trim().is_empty())?;
+    match std::fs::read_to_string(&path) {
+        Ok(page) => {
+            println!("[DEBUG] Serving {} at / while templates are unavailable", path);
+            Some(page)
+        }
+        Err(e) => {
+            println!("[WARN] Ignoring {}={}: {}, using the built-in page", UNAVAILABLE_PAGE_ENV, path, e);
+            None
+        }
+    }
+}
+
+// The allowed root for local writes, canonicalized so later prefix checks hold
+fn local_write_root_from_env() -> Option<PathBuf> {
+    let root = std::env::var(ALLOW_LOCAL_WRITE_ENV).ok().filter(|r| !r.trim().is_empty())?;
+    match PathBuf::from(root.trim()).canonicalize() {
+        Ok(path) if path.is_dir() => Some(path),
+        Ok(path) => {
+            println!("[WARN] {}={} is not a directory, local writes disabled", ALLOW_LOCAL_WRITE_ENV, path.display());
+            None
+        }
+        Err(e) => {
+            println!("[WARN] Cannot use {}={:?} ({}), local writes disabled", ALLOW_LOCAL_WRITE_ENV, root, e);
+            None
+        }
+    }
+}
+
+// Whether chrono can format with the pattern; unknown specifiers make it fail
+pub fn is_valid_timestamp_format(format: &str) -> bool {
+    !chrono::format::StrftimeItems::new(format).any(|item| matches!(item, chrono::format::Item::Error))
+}
+
+// Format the current UTC time, falling back to the default pattern if formatting fails
+pub fn format_timestamp(format: &str) -> String {
+    use std::fmt::Write;
+
+    let now = chrono::Utc::now();
+    let mut formatted = String::new();
+    if write!(formatted, "{}", now.format(format)).is_ok() {
+        return formatted;
+    }
+    println!("[WARN] Could not format timestamp with {:?}, using the default", format);
+    now.format(DEFAULT_TIMESTAMP_FORMAT).to_string()
+}
+
+fn timestamp_format_from_env() -> String {
+    match std::env::var(TIMESTAMP_FORMAT_ENV) {
+        Ok(format) if is_valid_timestamp_format(&format) => format,
+        Ok(format) => {
+            println!("[WARN] Ignoring invalid {}={:?}, using the default", TIMESTAMP_FORMAT_ENV, format);
+            DEFAULT_TIMESTAMP_FORMAT.to_string()
+        }
+        Err(_) => DEFAULT_TIMESTAMP_FORMAT.to_string(),
+    }
+}
+
+// Turn "zerohub/", "/zerohub" or "/" into "/zerohub" or "" respectively
+pub fn normalize_base_path(raw: &str) -> String {
+    let trimmed = raw.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", trimmed)
+    }
+}
+
+impl Settings {
+    pub fn from_env() -> Self {
+        let defaults = Settings::default();
+        let retry_after = RetryAfter::from_env();
+        let settings = Settings {
+            admin_token: std::env::var(ADMIN_TOKEN_ENV).ok().filter(|t| !t.is_empty()),
+            max_body_bytes: env_or(MAX_BODY_BYTES_ENV, defaults.max_body_bytes),
+            max_upload_bytes: env_or(MAX_UPLOAD_BYTES_ENV, defaults.max_upload_bytes),
+            max_base_uncompressed_bytes: env_or(MAX_BASE_UNCOMPRESSED_BYTES_ENV, defaults.max_base_uncompressed_bytes),
+            max_base_entry_bytes: env_or(MAX_BASE_ENTRY_BYTES_ENV, defaults.max_base_entry_bytes),
+            max_output_bytes: env_or(MAX_OUTPUT_BYTES_ENV, defaults.max_output_bytes),
+            base_path: normalize_base_path(&std::env::var(BASE_PATH_ENV).unwrap_or_default()),
+            blocked_names: BlockedNames::from_env(),
+            read_retry: ReadRetry {
+                // At least one attempt, whatever the environment says
+                attempts: env_or(READ_ATTEMPTS_ENV, defaults.read_retry.attempts).max(1),
+                delay: Duration::from_millis(env_or(
+                    READ_RETRY_DELAY_MS_ENV,
+                    defaults.read_retry.delay.as_millis() as u64,
+                )),
+            },
+            timestamp_format: timestamp_format_from_env(),
+            extra_limits: ExtraLimits {
+                max_entries: env_or(MAX_EXTRA_ENTRIES_ENV, defaults.extra_limits.max_entries),
+                max_key_len: env_or(MAX_EXTRA_KEY_LEN_ENV, defaults.extra_limits.max_key_len),
+                max_value_len: env_or(MAX_EXTRA_VALUE_LEN_ENV, defaults.extra_limits.max_value_len),
+            },
+            field_limits: FieldLimits {
+                max_username_len: env_or(MAX_USERNAME_LEN_ENV, defaults.field_limits.max_username_len),
+                max_email_len: env_or(MAX_EMAIL_LEN_ENV, defaults.field_limits.max_email_len),
+                max_project_name_len: env_or(MAX_PROJECT_NAME_LEN_ENV, defaults.field_limits.max_project_name_len),
+                max_description_len: env_or(MAX_DESCRIPTION_LEN_ENV, defaults.field_limits.max_description_len),
+                max_option_len: env_or(MAX_OPTION_LEN_ENV, defaults.field_limits.max_option_len),
+            },
+            normalize_unicode: env_or(NORMALIZE_UNICODE_ENV, defaults.normalize_unicode),
+            local_write_root: local_write_root_from_env(),
+            profiles: Profiles::from_env(),
+            static_mounts: Vec::new(),
+            cors: CorsSettings::from_env(),
+            retry_after,
+            temp_files: TempFileSlots::from_env(retry_after),
+            unavailable_page: unavailable_page_from_env(),
+            strict_startup: strict_startup_enabled(),
+            signer: None,
+            #[cfg(feature = "s3")]
+            s3: None,
+        };
+
+        if settings.admin_token.is_none() {
+            println!("[DEBUG] {} not set, admin endpoints disabled", ADMIN_TOKEN_ENV);
+        }
+        println!(
+            "[DEBUG] Effective base path: {}",
+            if settings.base_path.is_empty() { "/" } else { &settings.base_path }
+        );
+        println!("[DEBUG] Max request body: {} bytes (after decompression)", settings.max_body_bytes);
+        println!("[DEBUG] Max generated project size: {} bytes (estimated, uncompressed)", settings.max_output_bytes);
+        println!("[DEBUG] Max base archive entry size: {} bytes (uncompressed)", settings.max_base_entry_bytes);
+        println!("[DEBUG] Timestamp format: {}", settings.timestamp_format);
+        #[cfg(not(feature = "debug-delay"))]
+        for name in [DEBUG_DELAY_MS_ENV, DEBUG_JITTER_MS_ENV] {
+            if std::env::var(name).is_ok() {
+                println!("[WARN] Ignoring {}: built without the debug-delay feature", name);
+            }
+        }
+        if let Some(root) = &settings.local_write_root {
+            println!("[WARN] Local writes enabled: output_dir may write under {}", root.display());
+        }
+        settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_timestamp_formats_are_detected() {
+        assert!(is_valid_timestamp_format(DEFAULT_TIMESTAMP_FORMAT));
+        assert!(is_valid_timestamp_format("%Y-%m-%dT%H:%M:%SZ"));
+        assert!(!is_valid_timestamp_format("%Y-%Q"));
+        assert_eq!(format_timestamp("%Y").len(), 4);
+    }
+
+    #[test]
+    fn base_path_is_normalized() {
+        assert_eq!(normalize_base_path(""), "");
+        assert_eq!(normalize_base_path("/"), "");
+        assert_eq!(normalize_base_path("zerohub/"), "/zerohub");
+        assert_eq!(normalize_base_path(" /tools/zerohub/ "), "/tools/zerohub");
+    }
+}
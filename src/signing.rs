@@ -0,0 +1,115 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+use ed25519_dalek::pkcs8::{DecodePrivateKey, EncodePublicKey};
+use ed25519_dalek::{Signer, SigningKey};
+
+use crate::error::{AppError, ErrorBody};
+use crate::AppState;
+
+// Path to a PKCS#8 PEM Ed25519 private key, e.g. from
+// `openssl genpkey -algorithm ed25519 -out zerohub.pem`. Generated zips are
+// signed, and `/pubkey` answered, only when it is set.
+pub const SIGNING_KEY_ENV: &str = "ZEROHUB_SIGNING_KEY";
+// Base64 detached Ed25519 signature over the archive bytes as served; for
+// `encoding=base64`, over the decoded zip. Sent with every generated archive,
+// whether answered directly, fetched from `/download/{token}` or streamed by
+// `/generate-batch`; S3 uploads carry it in their result and object metadata.
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+// The server-held key generated zips are signed with
+#[derive(Debug, Clone)]
+pub struct ZipSigner {
+    key: SigningKey,
+    // PEM SubjectPublicKeyInfo, as served by `/pubkey`
+    public_key_pem: String,
+}
+
+impl ZipSigner {
+    pub fn new(key: SigningKey) -> Result<Self, String> {
+        let public_key_pem = key
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .map_err(|e| format!("Could not encode the signing public key: {}", e))?;
+        Ok(ZipSigner { key, public_key_pem })
+    }
+
+    // The configured signer; an unreadable or invalid key stops startup rather
+    // than quietly serving unsigned zips
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Some(path) = std::env::var(SIGNING_KEY_ENV).ok().filter(|p| !p.trim().is_empty()) else {
+            println!("[DEBUG] {} not set, generated zips are not signed", SIGNING_KEY_ENV);
+            return Ok(None);
+        };
+        let pem = std::fs::read_to_string(&path).map_err(|e| format!("Could not read {}={}: {}", SIGNING_KEY_ENV, path, e))?;
+        let key = SigningKey::from_pkcs8_pem(&pem)
+            .map_err(|e| format!("{}={} is not a PKCS#8 PEM Ed25519 private key: {}", SIGNING_KEY_ENV, path, e))?;
+        println!("[DEBUG] Signing generated zips with the Ed25519 key in {}", path);
+        Self::new(key).map(Some)
+    }
+
+    pub fn sign(&self, bytes: &[u8]) -> String {
+        base64::engine::general_purpose::STANDARD.encode(self.key.sign(bytes).to_bytes())
+    }
+
+    pub fn public_key_pem(&self) -> &str {
+        &self.public_key_pem
+    }
+}
+
+// The signature of an archive's bytes, when signing is on
+pub fn sign(signer: Option<&ZipSigner>, bytes: &[u8]) -> Option<String> {
+    signer.map(|signer| signer.sign(bytes))
+}
+
+// The header carrying a signature from `sign`
+pub fn signature_header(signature: Option<&str>) -> Option<(HeaderName, HeaderValue)> {
+    let signature = HeaderValue::from_str(signature?).ok()?;
+    Some((HeaderName::from_static(SIGNATURE_HEADER), signature))
+}
+
+// Public half of the signing key, for checking `X-Signature`: base64-decode it
+// into `project.sig`, then
+// `openssl pkeyutl -verify -pubin -inkey pubkey.pem -rawin -in project.zip -sigfile project.sig`
+#[utoipa::path(
+    get,
+    path = "/pubkey",
+    tag = "service",
+    responses(
+        (status = 200, description = "PEM Ed25519 public key", body = String, content_type = "application/x-pem-file"),
+        (status = 404, description = "Signing is not configured", body = ErrorBody),
+    )
+)]
+pub async fn public_key(State(state): State<AppState>) -> Result<Response, AppError> {
+    let signer = state.settings.signer.as_ref().ok_or_else(|| {
+        AppError::NotFound(format!("Generated zips are not signed; set {} to enable signing", SIGNING_KEY_ENV))
+    })?;
+    let headers = [(header::CONTENT_TYPE, "application/x-pem-file")];
+    Ok((StatusCode::OK, headers, signer.public_key_pem().to_string()).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::pkcs8::DecodePublicKey;
+    use ed25519_dalek::{Signature, VerifyingKey};
+
+    #[test]
+    fn signatures_verify_against_the_published_public_key() {
+        let signer = ZipSigner::new(SigningKey::from_bytes(&[7; 32])).unwrap();
+        let zip = b"PK\x05\x06 not much of a zip";
+        let (name, value) = signature_header(sign(Some(&signer), zip).as_deref()).unwrap();
+        assert_eq!(name, SIGNATURE_HEADER);
+
+        let signature = base64::engine::general_purpose::STANDARD.decode(value.as_bytes()).unwrap();
+        let signature = Signature::from_slice(&signature).unwrap();
+        let public_key = VerifyingKey::from_public_key_pem(signer.public_key_pem()).unwrap();
+        assert!(public_key.verify_strict(zip, &signature).is_ok());
+        assert!(public_key.verify_strict(b"tampered", &signature).is_err());
+        assert!(signature_header(sign(None, zip).as_deref()).is_none());
+    }
+}
@@ -0,0 +1,171 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::{self, Next},
+    response::Response,
+    Router,
+};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use tower::ServiceBuilder;
+use tower_http::services::ServeDir;
+
+use crate::cache_headers;
+use crate::AppState;
+
+// Environment variable with a path to a TOML file of extra static mounts
+pub const STATIC_MOUNTS_FILE_ENV: &str = "ZEROHUB_STATIC_MOUNTS_FILE";
+
+// First path segment of every route `build_router` registers. A mount under
+// any of them would clash with the route, which axum reports by panicking at
+// startup, so `parse` rejects it. Kept in step with the router by a test there.
+pub const RESERVED_SEGMENTS: [&str; 24] = [
+    "admin",
+    "download",
+    "generate",
+    "generate-async",
+    "generate-batch",
+    "generate-client-zip",
+    "generate-custom-base-zip",
+    "generate-links",
+    "generate-nested",
+    "generate-partial",
+    "generate-server-zip",
+    "health",
+    "license",
+    "livez",
+    "migration-pack",
+    "openapi.json",
+    "ping",
+    "pubkey",
+    "render",
+    "static",
+    "template-drift",
+    "templates",
+    "tree",
+    "validate-template-config",
+];
+
+// One `[[mount]]` of the file: files under `dir` served at `prefix`, e.g.
+//
+//   [[mount]]
+//   prefix = "/branding"
+//   dir = "/srv/zerohub/branding"
+//   cache_control = "public, max-age=86400"
+//
+// Without `cache_control`, responses get the same policy as `/static`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StaticMount {
+    pub prefix: String,
+    pub dir: PathBuf,
+    pub cache_control: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MountsFile {
+    #[serde(default)]
+    mount: Vec<StaticMount>,
+}
+
+// Parse and check the mounts: each prefix is a plain "/segment" path not used
+// by the server itself, and each directory exists
+pub fn parse(source: &str) -> Result<Vec<StaticMount>, String> {
+    let mounts = toml::from_str::<MountsFile>(source).map_err(|e| e.to_string())?.mount;
+    let mut seen = HashSet::new();
+    for mount in &mounts {
+        let prefix = mount.prefix.as_str();
+        let is_plain = prefix.len() > 1
+            && prefix.starts_with('/')
+            && !prefix.ends_with('/')
+            && !prefix.contains([':', '*', '{', '}'])
+            && prefix.split('/').skip(1).all(|part| !part.is_empty() && part != "." && part != "..");
+        if !is_plain {
+            return Err(format!("mount prefix {:?} must look like \"/assets\"", prefix));
+        }
+        if prefix.split('/').nth(1).is_some_and(|segment| RESERVED_SEGMENTS.contains(&segment)) {
+            return Err(format!("mount prefix {} is already used by the server", prefix));
+        }
+        if !seen.insert(prefix) {
+            return Err(format!("mount prefix {} is listed more than once", prefix));
+        }
+        if !mount.dir.is_dir() {
+            return Err(format!("mount {} directory {} does not exist", prefix, mount.dir.display()));
+        }
+        if let Some(cache_control) = &mount.cache_control {
+            HeaderValue::from_str(cache_control)
+                .map_err(|_| format!("mount {} cache_control {:?} is not a valid header value", prefix, cache_control))?;
+        }
+    }
+    Ok(mounts)
+}
+
+// Read the file named by `ZEROHUB_STATIC_MOUNTS_FILE`; no mounts when unset.
+// Unlike most settings a bad file is an error, so a typo doesn't silently
+// leave the front-end without its assets.
+pub fn from_env() -> Result<Vec<StaticMount>, String> {
+    let Ok(path) = std::env::var(STATIC_MOUNTS_FILE_ENV) else {
+        return Ok(Vec::new());
+    };
+    let mounts = fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|source| parse(&source))
+        .map_err(|e| format!("Invalid {} ({}): {}", STATIC_MOUNTS_FILE_ENV, path, e))?;
+    for mount in &mounts {
+        println!("[DEBUG] Serving {} from {}", mount.prefix, mount.dir.display());
+    }
+    Ok(mounts)
+}
+
+async fn fixed_cache_control(State(cache_control): State<HeaderValue>, request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    if response.status().is_success() {
+        response.headers_mut().insert(header::CACHE_CONTROL, cache_control);
+    }
+    response
+}
+
+// Add every mount to the router, each with its cache policy
+pub fn add_mounts(mut router: Router<AppState>, mounts: &[StaticMount]) -> Router<AppState> {
+    for mount in mounts {
+        let service = ServeDir::new(&mount.dir);
+        router = match mount.cache_control.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+            Some(cache_control) => router.nest_service(
+                &mount.prefix,
+                ServiceBuilder::new()
+                    .layer(middleware::from_fn_with_state(cache_control, fixed_cache_control))
+                    .service(service),
+            ),
+            None => router.nest_service(
+                &mount.prefix,
+                ServiceBuilder::new()
+                    .layer(middleware::from_fn(cache_headers::static_cache_headers))
+                    .service(service),
+            ),
+        };
+    }
+    router
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mounts_are_checked_at_parse_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let mount = |prefix: &str| format!("[[mount]]\nprefix = \"{}\"\ndir = {:?}\n", prefix, dir.path());
+
+        let mounts = parse(&format!("{}cache_control = \"public, max-age=60\"\n", mount("/branding"))).unwrap();
+        assert_eq!(mounts[0].cache_control.as_deref(), Some("public, max-age=60"));
+        for bad in ["/static", "/admin/x", "/health", "/pubkey/keys", "assets", "/assets/", "/a/../b", "/:id"] {
+            assert!(parse(&mount(bad)).is_err(), "{}", bad);
+        }
+        assert!(parse(&mount("/health-pages")).is_ok());
+        assert!(parse(&format!("{}{}", mount("/a"), mount("/a"))).is_err());
+        assert!(parse("[[mount]]\nprefix = \"/a\"\ndir = \"/does/not/exist\"\n").is_err());
+    }
+}
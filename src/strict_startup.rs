@@ -0,0 +1,128 @@
+use std::collections::BTreeSet;
+
+use crate::builder::{collect_entries, placeholder_names, substituted_part, GETTING_STARTED_ENTRY};
+use crate::extract::RequestInfo;
+use crate::settings::Settings;
+use crate::templates::{LoadedTemplate, TemplateCache};
+use crate::UserInfo;
+
+// "true" renders every template with probe data before serving and refuses to
+// boot if any leaves a placeholder unfilled. Reloads are held to the same
+// check and keep the live templates when it fails.
+pub const STRICT_STARTUP_ENV: &str = "ZEROHUB_STRICT_STARTUP";
+// Filled into every declared placeholder, so only undeclared ones are left
+const PROBE_VALUE: &str = "probe";
+
+pub fn strict_startup_enabled() -> bool {
+    std::env::var(STRICT_STARTUP_ENV).is_ok_and(|v| v.trim().eq_ignore_ascii_case("true"))
+}
+
+// A request that sets every field a template can use
+fn probe_user_info(template: &LoadedTemplate) -> UserInfo {
+    UserInfo {
+        username: "Probe User".to_string(),
+        email: "probe@example.com".to_string(),
+        project_name: "Probe Project".to_string(),
+        project_description: "Rendered at startup".to_string(),
+        project_slug: Some("probe-project".to_string()),
+        framework: template.config.frameworks.keys().next().cloned(),
+        include_getting_started: template.config.getting_started.is_some(),
+        extra: template
+            .config
+            .placeholders
+            .iter()
+            .map(|name| (name.clone(), PROBE_VALUE.to_string()))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+// Render one template with probe data and list the placeholders still in the
+// text it fills, by entry; an error when it doesn't render at all
+fn probe_template(template: &LoadedTemplate, settings: &Settings) -> Result<Vec<String>, String> {
+    let user_info = probe_user_info(template);
+    let (data, build_options) = crate::prepare_project(template, user_info, settings, &RequestInfo::default())
+        .map_err(|e| format!("probe request rejected: {}", e))?;
+    let entries = collect_entries(template, &data, &build_options).map_err(|e| format!("render failed: {}", e))?;
+
+    let fill_files = template.fill_files.iter().map(|(name, _)| name.as_str()).collect::<BTreeSet<_>>();
+    let mut unresolved = Vec::new();
+    for entry in &entries {
+        let filled = fill_files.contains(entry.name.as_str())
+            || entry.name == GETTING_STARTED_ENTRY
+            || template.config.fills_base_entry(&entry.name, template.base_is_directory);
+        let Some(text) = filled.then(|| std::str::from_utf8(&entry.content).ok()).flatten() else {
+            continue;
+        };
+        let region = template.config.substitution_region.get(&entry.name).copied();
+        let names = placeholder_names(substituted_part(text, region));
+        if !names.is_empty() {
+            let names = names.into_iter().collect::<Vec<_>>().join(", ");
+            unresolved.push(format!("{} leaves {}", entry.name, names));
+        }
+    }
+    Ok(unresolved)
+}
+
+// Probe every loaded template, plus those that failed to load, and report all
+// of them at once so one deploy fixes every broken template
+pub fn check_templates(cache: &TemplateCache, settings: &Settings) -> Result<(), String> {
+    let mut failures = cache
+        .errors()
+        .map(|(id, error)| format!("{}: failed to load: {}", id, error))
+        .collect::<Vec<_>>();
+    for template in cache.templates() {
+        match probe_template(template, settings) {
+            Ok(unresolved) if unresolved.is_empty() => println!("[DEBUG] - {} renders cleanly", template.id),
+            Ok(unresolved) => failures.push(format!("{}: {}", template.id, unresolved.join("; "))),
+            Err(e) => failures.push(format!("{}: {}", template.id, e)),
+        }
+    }
+    if failures.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "{}=true and {} template(s) are unrenderable:\n  {}",
+        STRICT_STARTUP_ENV,
+        failures.len(),
+        failures.join("\n  ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::ReadRetry;
+    use std::io::{Cursor, Write};
+    use zip::{write::FileOptions, ZipWriter};
+
+    fn template(id: &str, fill: &str) -> LoadedTemplate {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("src/main.py", FileOptions::<()>::default()).unwrap();
+        zip.write_all(b"print('{{not_filled_here}}')").unwrap();
+        LoadedTemplate::for_test(id, zip.finish().unwrap().into_inner(), vec![("README.md".to_string(), fill.to_string())])
+    }
+
+    #[test]
+    fn shipped_templates_render_cleanly() {
+        let cache = TemplateCache::load(ReadRetry::default());
+        assert_eq!(check_templates(&cache, &Settings::default()), Ok(()));
+    }
+
+    #[test]
+    fn every_template_with_unresolved_placeholders_is_reported() {
+        let mut declared = template("declared", "# {{project_name}} {{python_version}}");
+        declared.config.placeholders = vec!["python_version".to_string()];
+        let cache = TemplateCache::from_templates(vec![
+            declared,
+            template("first", "# {{project_name}} by {{maintainer}}"),
+            template("second", "{{licence_year}}"),
+        ]);
+
+        let report = check_templates(&cache, &Settings::default()).unwrap_err();
+        assert!(report.contains("2 template(s)"), "{}", report);
+        assert!(report.contains("first: README.md leaves maintainer"), "{}", report);
+        assert!(report.contains("second: README.md leaves licence_year"), "{}", report);
+        assert!(!report.contains("declared") && !report.contains("not_filled_here"), "{}", report);
+    }
+}
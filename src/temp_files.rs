@@ -0,0 +1,167 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use tempfile::NamedTempFile;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::error::AppError;
+use crate::settings::env_or;
+use crate::throttle::{RetryAfter, Throttle};
+
+// Temp files open at once across every request; creating one more is answered
+// with 503 until another is dropped. Unlimited when unset or 0.
+pub const MAX_OPEN_TEMP_FILES_ENV: &str = "ZEROHUB_MAX_OPEN_TEMP_FILES";
+
+// Bound on the temp files generation keeps open: one per zip being written,
+// upload being spooled and batch being streamed. Each file holds a slot until
+// it is dropped, which also deletes it, on error paths as much as success.
+#[derive(Debug, Clone, Default)]
+pub struct TempFileSlots {
+    slots: Option<Arc<Semaphore>>,
+    retry_after: RetryAfter,
+}
+
+impl TempFileSlots {
+    pub fn new(limit: usize, retry_after: RetryAfter) -> Self {
+        TempFileSlots { slots: (limit > 0).then(|| Arc::new(Semaphore::new(limit))), retry_after }
+    }
+
+    pub fn from_env(retry_after: RetryAfter) -> Self {
+        let limit = env_or(MAX_OPEN_TEMP_FILES_ENV, 0usize);
+        if limit > 0 {
+            println!("[DEBUG] Keeping at most {} temp files open at once", limit);
+        }
+        TempFileSlots::new(limit, retry_after)
+    }
+
+    // Slots not held by an open temp file; None when unlimited
+    pub fn available(&self) -> Option<usize> {
+        self.slots.as_ref().map(|slots| slots.available_permits())
+    }
+
+    fn acquire(&self) -> Result<Option<OwnedSemaphorePermit>, AppError> {
+        let Some(slots) = &self.slots else {
+            return Ok(None);
+        };
+        match Arc::clone(slots).try_acquire_owned() {
+            Ok(permit) => Ok(Some(permit)),
+            Err(_) => {
+                println!("[DEBUG] Every temp file slot is taken, turning the request away");
+                Err(self.retry_after.error(Throttle::QueueFull, "Too many generations in progress; try again shortly"))
+            }
+        }
+    }
+
+    // A named temp file, removed from disk when dropped
+    pub fn named(&self) -> Result<TempFile<NamedTempFile>, AppError> {
+        let slot = self.acquire()?;
+        Ok(TempFile { file: NamedTempFile::new()?, slot })
+    }
+
+    // An anonymous temp file, which the OS removes once it is closed
+    pub fn anonymous(&self) -> Result<TempFile<std::fs::File>, AppError> {
+        let slot = self.acquire()?;
+        Ok(TempFile { file: tempfile::tempfile()?, slot })
+    }
+}
+
+// A temp file holding one of `TempFileSlots`' slots until it is dropped
+#[derive(Debug)]
+pub struct TempFile<F> {
+    file: F,
+    slot: Option<OwnedSemaphorePermit>,
+}
+
+impl<F> TempFile<F> {
+    // The file and its slot, for callers that hand the file on, e.g. to a
+    // response body, and must keep the slot until that is done with it
+    pub fn into_parts(self) -> (F, Option<OwnedSemaphorePermit>) {
+        (self.file, self.slot)
+    }
+}
+
+impl<F> Deref for TempFile<F> {
+    type Target = F;
+
+    fn deref(&self) -> &F {
+        &self.file
+    }
+}
+
+impl<F> DerefMut for TempFile<F> {
+    fn deref_mut(&mut self) -> &mut F {
+        &mut self.file
+    }
+}
+
+impl<F: Read> Read for TempFile<F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl<F: Write> Write for TempFile<F> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl<F: Seek> Seek for TempFile<F> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{write_zip, BuildOptions, ZipEntry};
+    use axum::http::StatusCode;
+
+    #[test]
+    fn saturated_slots_answer_503_until_a_file_is_dropped() {
+        let slots = TempFileSlots::new(1, RetryAfter::default());
+        let held = slots.named().unwrap();
+        let path = held.path().to_path_buf();
+        let error = slots.anonymous().unwrap_err();
+        assert_eq!((error.status(), error.code()), (StatusCode::SERVICE_UNAVAILABLE, "QUEUE_FULL"));
+
+        drop(held);
+        assert!(!path.exists());
+        assert!(slots.anonymous().is_ok());
+    }
+
+    // Many generations at once against a few slots: every one either builds
+    // its zip or is turned away, and every slot and file is given back
+    #[test]
+    fn concurrent_generations_release_every_temp_file() {
+        let slots = TempFileSlots::new(4, RetryAfter::default());
+        let entries = vec![ZipEntry {
+            name: "README.md".to_string(),
+            content: "# stress".repeat(1024).into_bytes(),
+            unix_mode: None,
+            substitutions: 0,
+        }];
+        let results = std::thread::scope(|scope| {
+            let handles = (0..64)
+                .map(|_| {
+                    let build_options = BuildOptions { temp_files: slots.clone(), ..Default::default() };
+                    let entries = &entries;
+                    scope.spawn(move || (0..8).map(|_| write_zip(entries, &build_options)).collect::<Vec<_>>())
+                })
+                .collect::<Vec<_>>();
+            handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect::<Vec<_>>()
+        });
+
+        assert_eq!(results.len(), 64 * 8);
+        assert!(results.iter().any(Result::is_ok));
+        for error in results.iter().filter_map(|result| result.as_ref().err()) {
+            assert_eq!(error.code(), "QUEUE_FULL");
+        }
+        assert_eq!(slots.available(), Some(4));
+    }
+}
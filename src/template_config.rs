@@ -0,0 +1,591 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::builder::placeholder_names;
+use crate::templates::{read_with_retry, ReadRetry};
+use crate::validation::REQUIRABLE_FIELDS;
+
+// Name of the optional per-template configuration file
+pub const TEMPLATE_CONFIG_FILE: &str = "zerohub.toml";
+
+// The zip format stores the archive comment's length in 16 bits
+pub const MAX_ZIP_COMMENT_LEN: usize = u16::MAX as usize;
+
+// Placeholder only `filename` patterns can use, for the template's id
+pub const FILENAME_TEMPLATE_ID: &str = "template_id";
+
+// Per-template settings read from `templates/<id>/zerohub.toml`.
+// A missing file means every setting keeps its default.
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemplateConfig {
+    // Template revision reported with every generated project, e.g. "1.2.0"
+    pub version: Option<String>,
+    // Directory (relative to the template) used as the base instead of the base
+    // zip, e.g. "base". Its text files get placeholder substitution.
+    pub base_dir: Option<String>,
+    // Custom placeholder names the template's files use besides the built-in ones.
+    // Also the allowlist for a request's `extra` values: other keys are dropped.
+    // Dotted names such as "author.name" take values from nested `extra` objects.
+    pub placeholders: Vec<String>,
+    // Fields a request must fill for this template, rejected with 400 when
+    // absent or blank: UserInfo fields such as "framework", or `extra` keys
+    // such as "python_version"
+    pub required: Vec<String>,
+    // Reject requests with `extra` keys outside the allowlist instead of dropping
+    // them. Strict templates also answer 422 rather than 204 when a request
+    // leaves no files, and ignore `default_placeholder_value`.
+    pub strict_extra: bool,
+    // Fill `{{client_ip}}` and `{{user_agent}}` from the request. Off by default,
+    // since it writes personal data into the generated files.
+    pub request_placeholders: bool,
+    // Binary entries of the base zip that opt in to byte-level substitution
+    pub binary_substitution: Vec<BinarySubstitution>,
+    // Base entry name -> new name, which may use placeholders, e.g.
+    // "app_icon.png" -> "{{project_slug}}_icon.png". Only the name changes.
+    pub rename: BTreeMap<String, String>,
+    // File extensions (without the dot) of base entries whose text gets
+    // placeholders filled, e.g. ["md", "toml", "py"]; others are copied
+    // verbatim even when they are UTF-8. When unset, a `base_dir` has every
+    // text file filled and a base zip none.
+    pub text_extensions: Option<Vec<String>>,
+    // File extension (without the dot) -> how entries with it are compressed,
+    // e.g. `png = "stored"` for already-compressed assets. Others are deflated.
+    pub compression: BTreeMap<String, EntryCompression>,
+    // File extension -> line comment prefix for the optional build banner,
+    // added to (or overriding) the built-in ones. An empty prefix disables it.
+    pub banner_comments: BTreeMap<String, String>,
+    // Base entry or template file -> condition for including it, e.g.
+    // `"Dockerfile" = { field = "use_docker", equals = "true" }`. A name ending
+    // in "/" gates everything under it.
+    pub include_if: BTreeMap<String, IncludeCondition>,
+    // Template file -> the part of it placeholders are filled in, so code samples
+    // containing `{{...}}` further down stay as written. Whole file when absent.
+    pub substitution_region: BTreeMap<String, SubstitutionRegion>,
+    // Markdown next steps written into GETTING_STARTED.md for requests with
+    // `include_getting_started`; placeholders are filled in it
+    pub getting_started: Option<String>,
+    // Name of the generated zip, e.g. "{{project_slug}}-{{template_id}}.zip".
+    // Placeholders are filled as in the template's files.
+    pub filename: Option<String>,
+    // Archive comment for the generated zip, e.g. "Generated by ZeroHub for
+    // {{project_name}}"; a request's `zip_comment` replaces it
+    pub zip_comment: Option<String>,
+    // Framework used when the request doesn't pick one
+    pub default_framework: Option<String>,
+    // Dependency sets selectable with the request's `framework` field
+    pub frameworks: BTreeMap<String, Framework>,
+}
+
+// Line comment prefixes the build banner uses unless a template overrides them.
+// Formats without line comments (JSON, Markdown) are left out on purpose.
+const DEFAULT_BANNER_COMMENTS: [(&str, &str); 16] = [
+    ("toml", "#"), ("py", "#"), ("yaml", "#"), ("yml", "#"), ("sh", "#"), ("ini", "#"), ("cfg", "#"),
+    ("rs", "//"), ("js", "//"), ("mjs", "//"), ("cjs", "//"), ("ts", "//"), ("tsx", "//"), ("jsx", "//"),
+    ("go", "//"), ("java", "//"),
+];
+
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Framework {
+    // package name -> version range, rendered into `{{dependencies}}`
+    pub dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BinarySubstitution {
+    // Entry name inside the base zip, e.g. "assets/app.bin"
+    pub path: String,
+    #[serde(default)]
+    pub mode: BinaryMode,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BinaryMode {
+    // Rebuild the buffer, so the entry may grow or shrink
+    #[default]
+    Resize,
+    // Keep every byte offset stable: values are NUL-padded to the token length,
+    // and tokens whose value does not fit are left untouched
+    Fixed,
+}
+
+// Holds when the placeholder `field` (as in `{{field}}`) resolves to exactly
+// `equals`. A placeholder without a value never matches.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct IncludeCondition {
+    pub field: String,
+    pub equals: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubstitutionRegion {
+    // The leading block between two `---` lines; nothing when the file has none
+    Frontmatter,
+    // First and last line, 1-based and inclusive: `{ lines = [1, 3] }`
+    Lines(usize, usize),
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryCompression {
+    #[default]
+    Deflate,
+    Stored,
+}
+
+impl TemplateConfig {
+    // Returns the substitution settings for a base zip entry, if it opted in
+    pub fn binary_substitution_for(&self, entry_name: &str) -> Option<&BinarySubstitution> {
+        self.binary_substitution.iter().find(|b| b.path == entry_name)
+    }
+
+    // Whether placeholders are filled in a base entry's text; `default` applies
+    // when the template sets no `text_extensions`
+    pub fn fills_base_entry(&self, entry_name: &str, default: bool) -> bool {
+        let Some(extensions) = &self.text_extensions else {
+            return default;
+        };
+        Path::new(entry_name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|extension| extensions.iter().any(|listed| listed.eq_ignore_ascii_case(extension)))
+    }
+
+    // Conditions gating an entry: its own and those of the directories it is in
+    pub fn include_conditions_for<'a>(&'a self, entry_name: &'a str) -> impl Iterator<Item = (&'a str, &'a IncludeCondition)> {
+        self.include_if
+            .iter()
+            .filter(move |(name, _)| *name == entry_name || (name.ends_with('/') && entry_name.starts_with(name.as_str())))
+            .map(|(name, condition)| (name.as_str(), condition))
+    }
+
+    // Built-in banner comment prefixes merged with the template's, keyed by
+    // lowercase extension
+    pub fn banner_comment_prefixes(&self) -> BTreeMap<String, String> {
+        let mut prefixes = DEFAULT_BANNER_COMMENTS
+            .iter()
+            .map(|(extension, prefix)| (extension.to_string(), prefix.to_string()))
+            .collect::<BTreeMap<_, _>>();
+        for (extension, prefix) in &self.banner_comments {
+            prefixes.insert(extension.to_lowercase(), prefix.clone());
+        }
+        prefixes.retain(|_, prefix| !prefix.is_empty());
+        prefixes
+    }
+
+    // Pick the requested framework, or the default one. Templates without
+    // frameworks resolve to None and reject an explicit choice.
+    pub fn resolve_framework(&self, requested: Option<&str>) -> Result<Option<(&str, &Framework)>, String> {
+        if self.frameworks.is_empty() {
+            return match requested {
+                Some(name) => Err(format!("This template does not support choosing a framework (got '{}')", name)),
+                None => Ok(None),
+            };
+        }
+
+        let supported = || self.frameworks.keys().cloned().collect::<Vec<_>>().join(", ");
+        let Some(name) = requested.or(self.default_framework.as_deref()) else {
+            return Err(format!("framework is required; supported: {}", supported()));
+        };
+        match self.frameworks.get_key_value(name) {
+            Some((name, framework)) => Ok(Some((name.as_str(), framework))),
+            None => Err(format!("Unsupported framework '{}'; supported: {}", name, supported())),
+        }
+    }
+}
+
+impl TemplateConfig {
+    // Keep the `extra` values this template declared as placeholders, as
+    // (`{{token}}`, value) pairs. Other keys are dropped, or rejected in strict
+    // mode, which also rejects an object missing a declared path: sending
+    // `author` without `author.email` when both are placeholders.
+    pub fn allowed_extras(&self, extra: BTreeMap<String, String>) -> Result<Vec<(String, String)>, String> {
+        if self.strict_extra {
+            let missing = self.placeholders.iter().find(|name| {
+                !extra.contains_key(*name)
+                    && name.rsplit_once('.').is_some_and(|(parent, _)| {
+                        let prefix = format!("{}.", parent);
+                        extra.keys().any(|key| key.starts_with(&prefix))
+                    })
+            });
+            if let Some(name) = missing {
+                return Err(format!("extra is missing '{}', required by the template", name));
+            }
+        }
+        let mut allowed = Vec::with_capacity(extra.len());
+        for (key, value) in extra {
+            if self.placeholders.contains(&key) {
+                allowed.push((format!("{{{{{}}}}}", key), value));
+            } else if self.strict_extra {
+                return Err(format!(
+                    "extra key '{}' is not an allowed placeholder; allowed: {}",
+                    key,
+                    self.placeholders.join(", ")
+                ));
+            } else {
+                println!("[DEBUG] Ignoring extra key not in the placeholder allowlist: {}", key);
+            }
+        }
+        Ok(allowed)
+    }
+}
+
+// Render a dependency set as the JSON object that replaces `{{dependencies}}`,
+// indented to sit at the top level of a 2-space package.json
+pub fn render_dependencies(dependencies: &BTreeMap<String, String>) -> String {
+    if dependencies.is_empty() {
+        return "{}".to_string();
+    }
+    let lines = dependencies
+        .iter()
+        .map(|(name, version)| {
+            // serde_json takes care of escaping quotes and control characters
+            format!(
+                "    {}: {}",
+                serde_json::Value::from(name.as_str()),
+                serde_json::Value::from(version.as_str())
+            )
+        })
+        .collect::<Vec<_>>();
+    format!("{{\n{}\n  }}", lines.join(",\n"))
+}
+
+// Placeholder names are lowercase identifiers, e.g. `python_version`
+pub fn is_valid_placeholder_name(name: &str) -> bool {
+    name.split('.').all(|segment| {
+        let mut chars = segment.chars();
+        matches!(chars.next(), Some(c) if c.is_ascii_lowercase() || c == '_')
+            && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    })
+}
+
+// Check a config source and report every problem found, not just the first.
+// `base_entries` are the entry names of the template's base archive, when known,
+// so file references can be checked against them.
+pub fn validate_config_source(source: &str, base_entries: Option<&[String]>, builtin_placeholders: &[&str]) -> Vec<String> {
+    let config: TemplateConfig = match toml::from_str(source) {
+        Ok(config) => config,
+        Err(e) => return vec![format!("Config does not parse: {}", e)],
+    };
+
+    let mut problems = Vec::new();
+
+    let mut seen = std::collections::HashSet::new();
+    for name in &config.placeholders {
+        if !is_valid_placeholder_name(name) {
+            problems.push(format!(
+                "Placeholder '{}' is not a valid name: use lowercase letters, digits and '_', with '.' between segments",
+                name
+            ));
+        } else if builtin_placeholders.contains(&name.as_str()) {
+            problems.push(format!("Placeholder '{}' is built in and must not be redeclared", name));
+        }
+        if !seen.insert(name) {
+            problems.push(format!("Placeholder '{}' is declared more than once", name));
+        }
+    }
+
+    for name in &config.required {
+        if !REQUIRABLE_FIELDS.contains(&name.as_str()) && !config.placeholders.contains(name) {
+            problems.push(format!("required field '{}' is neither a request field nor a declared placeholder", name));
+        }
+    }
+
+    if let Some(default) = &config.default_framework {
+        if !config.frameworks.contains_key(default) {
+            problems.push(format!("default_framework '{}' is not declared under [frameworks]", default));
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    for binary in &config.binary_substitution {
+        if !seen.insert(&binary.path) {
+            problems.push(format!("binary_substitution path '{}' is listed more than once", binary.path));
+        }
+        if let Some(entries) = base_entries {
+            if !entries.contains(&binary.path) {
+                problems.push(format!(
+                    "binary_substitution path '{}' does not exist in the base archive",
+                    binary.path
+                ));
+            }
+        }
+    }
+
+    for (from, to) in &config.rename {
+        if let Some(entries) = base_entries {
+            if !entries.contains(from) {
+                problems.push(format!("rename source '{}' does not exist in the base archive", from));
+            }
+        }
+        if from.ends_with('/') || to.trim().is_empty() || to.ends_with('/') {
+            problems.push(format!("rename '{}' -> '{}' must map a file to a file name", from, to));
+        }
+    }
+    for extension in config.compression.keys() {
+        if extension.is_empty() || extension.contains(['.', '/']) {
+            problems.push(format!("compression key '{}' must be a file extension without the dot", extension));
+        }
+    }
+    for extension in config.text_extensions.iter().flatten() {
+        if extension.is_empty() || extension.contains(['.', '/']) {
+            problems.push(format!("text_extensions entry '{}' must be a file extension without the dot", extension));
+        }
+    }
+
+    for (name, region) in &config.substitution_region {
+        if let SubstitutionRegion::Lines(first, last) = region {
+            if *first == 0 || first > last {
+                problems.push(format!(
+                    "substitution_region '{}' lines [{}, {}] must start at 1 or later and not end before they start",
+                    name, first, last
+                ));
+            }
+        }
+    }
+
+    for (name, condition) in &config.include_if {
+        let field = condition.field.as_str();
+        if name.is_empty() {
+            problems.push("include_if needs an entry name".to_string());
+        }
+        if !builtin_placeholders.contains(&field) && !config.placeholders.iter().any(|p| p == field) {
+            problems.push(format!(
+                "include_if '{}' tests '{}', which is neither built in nor a declared placeholder",
+                name, field
+            ));
+        }
+    }
+
+    if let Some(steps) = &config.getting_started {
+        for name in placeholder_names(steps) {
+            if !builtin_placeholders.contains(&name.as_str()) && !config.placeholders.contains(&name) {
+                problems.push(format!(
+                    "getting_started uses '{{{{{}}}}}', which is neither built in nor a declared placeholder",
+                    name
+                ));
+            }
+        }
+    }
+
+    if let Some(filename) = &config.filename {
+        if !filename.ends_with(".zip") {
+            problems.push(format!("filename '{}' must end in \".zip\"", filename));
+        }
+        if filename.contains(['/', '\\']) {
+            problems.push(format!("filename '{}' must not contain a path separator", filename));
+        }
+        for name in placeholder_names(filename) {
+            if name != FILENAME_TEMPLATE_ID && !builtin_placeholders.contains(&name.as_str()) && !config.placeholders.contains(&name) {
+                problems.push(format!(
+                    "filename uses '{{{{{}}}}}', which is neither built in nor a declared placeholder",
+                    name
+                ));
+            }
+        }
+    }
+
+    if let Some(comment) = &config.zip_comment {
+        if comment.len() > MAX_ZIP_COMMENT_LEN {
+            problems.push(format!("zip_comment must be at most {} bytes", MAX_ZIP_COMMENT_LEN));
+        }
+        for name in placeholder_names(comment) {
+            if !builtin_placeholders.contains(&name.as_str()) && !config.placeholders.contains(&name) {
+                problems.push(format!(
+                    "zip_comment uses '{{{{{}}}}}', which is neither built in nor a declared placeholder",
+                    name
+                ));
+            }
+        }
+    }
+
+    let mut targets = std::collections::HashSet::new();
+    for to in config.rename.values() {
+        if !targets.insert(to) {
+            problems.push(format!("rename target '{}' is used more than once", to));
+        }
+    }
+
+    problems
+}
+
+// Load the config from a template directory, falling back to defaults when absent
+pub fn load_template_config(template_dir: &str, retry: ReadRetry) -> Result<TemplateConfig, Box<dyn std::error::Error>> {
+    let config_path = Path::new(template_dir).join(TEMPLATE_CONFIG_FILE);
+    if !config_path.exists() {
+        return Ok(TemplateConfig::default());
+    }
+
+    let content = String::from_utf8(read_with_retry(&config_path, retry)?)?;
+    let config: TemplateConfig = toml::from_str(&content).map_err(|e| {
+        let error_msg = format!("Invalid {}: {}", config_path.display(), e);
+        eprintln!("[ERROR] {}", error_msg);
+        error_msg
+    })?;
+    println!(
+        "[DEBUG] Loaded {} (version {}, {} binary substitution entries)",
+        config_path.display(),
+        config.version.as_deref().unwrap_or("unset"),
+        config.binary_substitution.len()
+    );
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_every_problem_in_a_config() {
+        let source = r#"
+placeholders = ["python_version", "Bad-Name", "username", "python_version"]
+
+[[binary_substitution]]
+path = "assets/app.bin"
+
+[[binary_substitution]]
+path = "missing.bin"
+"#;
+        let entries = vec!["assets/app.bin".to_string()];
+        let problems = validate_config_source(source, Some(&entries), &["username"]);
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+        assert!(problems.iter().any(|p| p.contains("'Bad-Name' is not a valid name")));
+        assert!(problems.iter().any(|p| p.contains("'username' is built in")));
+        assert!(problems.iter().any(|p| p.contains("'python_version' is declared more than once")));
+        assert!(problems.iter().any(|p| p.contains("'missing.bin' does not exist")));
+    }
+
+    #[test]
+    fn filename_and_zip_comment_placeholders_are_checked() {
+        let problems = |filename: &str| validate_config_source(&format!("filename = {:?}", filename), None, &["project_slug"]);
+        assert!(problems("{{project_slug}}-{{template_id}}.zip").is_empty());
+        assert_eq!(problems("{{project_slug}}.tar").len(), 1);
+        assert_eq!(problems("out/{{nope}}.zip").len(), 2);
+
+        let source = "zip_comment = \"Generated for {{project_name}} by {{nope}}\"";
+        assert_eq!(validate_config_source(source, None, &["project_name"]).len(), 1);
+    }
+
+    #[test]
+    fn required_fields_must_be_request_fields_or_placeholders() {
+        let source = "placeholders = [\"python_version\"]\nrequired = [\"framework\", \"python_version\", \"node_version\"]";
+        let problems = validate_config_source(source, None, &[]);
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].contains("'node_version'"), "{}", problems[0]);
+    }
+
+    #[test]
+    fn resolves_requested_and_default_frameworks() {
+        let config: TemplateConfig = toml::from_str(
+            r#"
+default_framework = "react"
+[frameworks.react.dependencies]
+react = "^19.0.0"
+[frameworks.vue.dependencies]
+vue = "^3.5.0"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.resolve_framework(None).unwrap().unwrap().0, "react");
+        assert_eq!(config.resolve_framework(Some("vue")).unwrap().unwrap().0, "vue");
+        let error = config.resolve_framework(Some("angular")).unwrap_err();
+        assert_eq!(error, "Unsupported framework 'angular'; supported: react, vue");
+        assert!(TemplateConfig::default().resolve_framework(Some("vue")).is_err());
+        assert!(TemplateConfig::default().resolve_framework(None).unwrap().is_none());
+    }
+
+    #[test]
+    fn renders_dependencies_as_escaped_json() {
+        let dependencies = BTreeMap::from([
+            ("react".to_string(), "^19.0.0".to_string()),
+            ("we\"ird".to_string(), "1.0".to_string()),
+        ]);
+        let rendered = render_dependencies(&dependencies);
+        assert_eq!(rendered, "{\n    \"react\": \"^19.0.0\",\n    \"we\\\"ird\": \"1.0\"\n  }");
+        let parsed: BTreeMap<String, String> = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(parsed, dependencies);
+    }
+
+    #[test]
+    fn extras_outside_the_allowlist_are_dropped_or_rejected() {
+        let mut config = TemplateConfig {
+            placeholders: vec!["python_version".to_string()],
+            ..Default::default()
+        };
+        let extra = BTreeMap::from([
+            ("python_version".to_string(), "3.12".to_string()),
+            ("username".to_string(), "mallory".to_string()),
+        ]);
+
+        let allowed = config.allowed_extras(extra.clone()).unwrap();
+        assert_eq!(allowed, [("{{python_version}}".to_string(), "3.12".to_string())]);
+
+        config.strict_extra = true;
+        let error = config.allowed_extras(extra).unwrap_err();
+        assert!(error.starts_with("extra key 'username' is not an allowed placeholder"));
+    }
+
+    #[test]
+    fn substitution_regions_parse_and_line_ranges_are_checked() {
+        let source = "[substitution_region]\n\"README.md\" = \"frontmatter\"\n\"docs/guide.md\" = { lines = [3, 1] }\n";
+        let config: TemplateConfig = toml::from_str(source).unwrap();
+        assert_eq!(config.substitution_region["README.md"], SubstitutionRegion::Frontmatter);
+        assert_eq!(config.substitution_region["docs/guide.md"], SubstitutionRegion::Lines(3, 1));
+        let problems = validate_config_source(source, None, &[]);
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].contains("'docs/guide.md' lines [3, 1]"));
+    }
+
+    #[test]
+    fn getting_started_placeholders_must_be_known() {
+        let source = "placeholders = [\"python_version\"]\ngetting_started = \"Install {{python_version}} for {{username}}, then {{run_command}}\"\n";
+        let problems = validate_config_source(source, None, &["username"]);
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].contains("'{{run_command}}'"));
+    }
+
+    #[test]
+    fn strict_mode_rejects_missing_nested_paths() {
+        let mut config = TemplateConfig {
+            placeholders: vec!["author.name".to_string(), "author.email".to_string(), "license".to_string()],
+            ..Default::default()
+        };
+        let extra = BTreeMap::from([("author.name".to_string(), "Ada".to_string())]);
+
+        let allowed = config.allowed_extras(extra.clone()).unwrap();
+        assert_eq!(allowed, [("{{author.name}}".to_string(), "Ada".to_string())]);
+
+        config.strict_extra = true;
+        assert_eq!(config.allowed_extras(extra).unwrap_err(), "extra is missing 'author.email', required by the template");
+        assert!(config.allowed_extras(BTreeMap::new()).is_ok());
+        assert!(is_valid_placeholder_name("author.name") && !is_valid_placeholder_name("author..name"));
+    }
+
+    #[test]
+    fn rename_sources_and_targets_are_checked() {
+        let source = r#"
+[rename]
+"assets/app_icon.png" = "assets/{{project_slug}}_icon.png"
+"assets/missing.png" = "assets/{{project_slug}}_icon.png"
+"#;
+        let entries = vec!["assets/app_icon.png".to_string()];
+        let problems = validate_config_source(source, Some(&entries), &[]);
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].contains("'assets/missing.png' does not exist"));
+        assert!(problems[1].contains("used more than once"));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let problems = validate_config_source("binary_substitutions = []", None, &[]);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("Config does not parse"));
+    }
+}
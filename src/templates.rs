@@ -0,0 +1,781 @@
+use arc_swap::ArcSwap;
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use utoipa::ToSchema;
+use walkdir::WalkDir;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::base_formats::BASE_FORMATS;
+use crate::builder::{as_text, open_base_archive, placeholder_names, substituted_part, BUILTIN_PLACEHOLDERS};
+use crate::error::AppError;
+use crate::functions;
+use crate::template_config::{load_template_config, TemplateConfig, FILENAME_TEMPLATE_ID};
+
+// Static description of a template: where it lives and which files get filled
+pub struct TemplateSpec {
+    pub id: &'static str,
+    pub dir: &'static str,
+    pub base_zip: &'static str,
+    pub fill_files: &'static [&'static str],
+    // Appended to the project slug to form the download filename
+    pub filename_suffix: &'static str,
+    // The slug becomes an npm package name and must follow npm naming rules
+    pub npm_package: bool,
+}
+
+pub const TEMPLATE_SPECS: [TemplateSpec; 2] = [
+    TemplateSpec {
+        id: "server",
+        dir: "templates/server",
+        base_zip: "zero.zip",
+        fill_files: &["LICENSE", "pyproject.toml", "README.md"],
+        filename_suffix: "",
+        npm_package: false,
+    },
+    TemplateSpec {
+        id: "client",
+        dir: "templates/client",
+        base_zip: "zero-client.zip",
+        fill_files: &["LICENSE", "package.json", "README.md"],
+        filename_suffix: "-client",
+        npm_package: true,
+    },
+];
+
+// A template read into memory: base archive bytes, config and raw fill files
+#[derive(Debug)]
+pub struct LoadedTemplate {
+    pub id: String,
+    pub base_zip: Vec<u8>,
+    pub config: TemplateConfig,
+    // (entry name, unfilled content) in the order they are appended to the zip
+    pub fill_files: Vec<(String, String)>,
+    pub filename_suffix: String,
+    pub npm_package: bool,
+    // `base_zip` was built from the config's `base_dir`; its text files are
+    // filled like the fill files
+    pub base_is_directory: bool,
+}
+
+// How often a failed template file read is retried, for templates on slow or
+// networked filesystems. The delay doubles after every failed attempt.
+#[derive(Debug, Clone, Copy)]
+pub struct ReadRetry {
+    pub attempts: u32,
+    pub delay: Duration,
+}
+
+impl Default for ReadRetry {
+    fn default() -> Self {
+        ReadRetry {
+            attempts: 3,
+            delay: Duration::from_millis(100),
+        }
+    }
+}
+
+// `fs::read` with retries; a missing file is reported right away
+pub fn read_with_retry(path: &Path, retry: ReadRetry) -> std::io::Result<Vec<u8>> {
+    let mut delay = retry.delay;
+    let mut attempt = 1;
+    loop {
+        match fs::read(path) {
+            Ok(bytes) => return Ok(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound || attempt >= retry.attempts => return Err(e),
+            Err(e) => {
+                tracing::warn!(
+                    "Reading {} failed (attempt {}/{}): {}; retrying in {:?}",
+                    path.display(),
+                    attempt,
+                    retry.attempts,
+                    e,
+                    delay
+                );
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+// Read a file that must exist, logging a clear message when it does not
+fn read_required(path: &Path, retry: ReadRetry) -> Result<Vec<u8>, AppError> {
+    if !path.exists() {
+        let error_msg = format!("File not found: {}", path.display());
+        eprintln!("[ERROR] {}", error_msg);
+        return Err(error_msg.into());
+    }
+    read_with_retry(path, retry).map_err(|e| {
+        let error_msg = format!("Failed to read {}: {}", path.display(), e);
+        eprintln!("[ERROR] {}", error_msg);
+        error_msg.into()
+    })
+}
+
+#[cfg(unix)]
+fn unix_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn unix_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+// Pack a base directory into an in-memory zip, so directory and zip templates
+// are built the same way. Paths are relative to `root` with `/` separators,
+// files are visited in name order and permissions are kept.
+pub fn zip_directory(root: &Path, retry: ReadRetry) -> Result<Vec<u8>, AppError> {
+    if !root.is_dir() {
+        let error_msg = format!("Base directory not found: {}", root.display());
+        eprintln!("[ERROR] {}", error_msg);
+        return Err(error_msg.into());
+    }
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    // The archive only lives in memory until the builder reads it back
+    let options = FileOptions::<()>::default().compression_method(CompressionMethod::Stored);
+    let mut files = 0;
+    for entry in WalkDir::new(root).sort_by_file_name() {
+        let entry = entry.map_err(|e| format!("Failed to walk {}: {}", root.display(), e))?;
+        if entry.file_type().is_symlink() {
+            println!("[WARN] Skipping symlink in base directory: {}", entry.path().display());
+            continue;
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let relative = entry.path().strip_prefix(root).map_err(|e| e.to_string())?;
+        let name = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let metadata = entry.metadata().map_err(|e| format!("Failed to stat {}: {}", entry.path().display(), e))?;
+        let options = match unix_mode(&metadata) {
+            Some(mode) => options.unix_permissions(mode),
+            None => options,
+        };
+
+        zip.start_file(name, options)?;
+        zip.write_all(&read_required(entry.path(), retry)?)?;
+        files += 1;
+    }
+
+    println!("[DEBUG] Packed base directory {} ({} files)", root.display(), files);
+    Ok(zip.finish()?.into_inner())
+}
+
+// Read a base archive, preferring a gzip-compressed `<name>.gz` next to it so
+// large bases can be stored compressed on disk. Without either, the first of
+// `BASE_FORMATS` found in place of the `.zip`, e.g. `zero.tar.gz`, is used.
+fn read_base_zip(dir: &Path, name: &str, retry: ReadRetry) -> Result<Vec<u8>, AppError> {
+    let gz_path = dir.join(format!("{}.gz", name));
+    if !gz_path.exists() && !dir.join(name).exists() {
+        let stem = name.strip_suffix(".zip").unwrap_or(name);
+        let found = BASE_FORMATS
+            .iter()
+            .map(|format| (format, dir.join(format!("{}{}", stem, format.extension()))))
+            .find(|(_, path)| path.exists());
+        if let Some((format, path)) = found {
+            let bytes = format.to_zip(&read_required(&path, retry)?).map_err(|e| {
+                let e = e.context(&format!("Failed to convert {}", path.display()));
+                eprintln!("[ERROR] {}", e);
+                e
+            })?;
+            println!("[DEBUG] Loaded base archive {} as a {} byte zip", path.display(), bytes.len());
+            return Ok(bytes);
+        }
+    }
+    if !gz_path.exists() {
+        let bytes = read_required(&dir.join(name), retry)?;
+        println!("[DEBUG] Loaded base archive {}", dir.join(name).display());
+        return Ok(bytes);
+    }
+    let compressed = read_required(&gz_path, retry)?;
+    let mut bytes = Vec::new();
+    GzDecoder::new(compressed.as_slice()).read_to_end(&mut bytes).map_err(|e| {
+        let error_msg = format!("Failed to decompress {}: {}", gz_path.display(), e);
+        eprintln!("[ERROR] {}", error_msg);
+        AppError::from(error_msg)
+    })?;
+    println!(
+        "[DEBUG] Loaded gzip-compressed base archive {} ({} bytes, {} decompressed)",
+        gz_path.display(),
+        compressed.len(),
+        bytes.len()
+    );
+    Ok(bytes)
+}
+
+pub fn load_template(spec: &TemplateSpec, retry: ReadRetry) -> Result<LoadedTemplate, AppError> {
+    let dir = Path::new(spec.dir);
+    println!("[DEBUG] Loading template '{}' from {}", spec.id, spec.dir);
+
+    let config = load_template_config(spec.dir, retry)?;
+    let base_zip = match &config.base_dir {
+        Some(base_dir) => zip_directory(&dir.join(base_dir), retry)?,
+        None => read_base_zip(dir, spec.base_zip, retry)?,
+    };
+
+    let mut fill_files = Vec::with_capacity(spec.fill_files.len());
+    for name in spec.fill_files {
+        let path = dir.join(name);
+        let content = String::from_utf8(read_required(&path, retry)?)
+            .map_err(|e| format!("{} is not valid UTF-8: {}", path.display(), e))?;
+        fill_files.push((name.to_string(), content));
+    }
+
+    let template = LoadedTemplate {
+        id: spec.id.to_string(),
+        base_zip,
+        fill_files,
+        filename_suffix: spec.filename_suffix.to_string(),
+        npm_package: spec.npm_package,
+        base_is_directory: config.base_dir.is_some(),
+        config,
+    };
+    // Other templates leave unknown function tokens as written
+    if template.config.strict_extra {
+        template.check_functions()?;
+    }
+    // The bytes never change once loaded, so a base archive that opens now
+    // opens for every request
+    open_base_archive(&template.id, &template.base_zip)?;
+    Ok(template)
+}
+
+impl LoadedTemplate {
+    pub fn version(&self) -> Option<&str> {
+        self.config.version.as_deref()
+    }
+
+    // A zip-based template with the default config, for tests; set any other
+    // fields on the result
+    #[cfg(test)]
+    pub fn for_test(id: &str, base_zip: Vec<u8>, fill_files: Vec<(String, String)>) -> Self {
+        LoadedTemplate {
+            id: id.to_string(),
+            base_zip,
+            config: TemplateConfig::default(),
+            fill_files,
+            filename_suffix: String::new(),
+            npm_package: false,
+            base_is_directory: false,
+        }
+    }
+}
+
+// Public description of a template, as listed by `GET /templates`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TemplateInfo {
+    pub id: String,
+    pub version: Option<String>,
+    pub frameworks: Vec<String>,
+    pub default_framework: Option<String>,
+    pub placeholders: Vec<String>,
+}
+
+impl From<&LoadedTemplate> for TemplateInfo {
+    fn from(template: &LoadedTemplate) -> Self {
+        TemplateInfo {
+            id: template.id.clone(),
+            version: template.config.version.clone(),
+            frameworks: template.config.frameworks.keys().cloned().collect(),
+            default_framework: template.config.default_framework.clone(),
+            placeholders: template.config.placeholders.clone(),
+        }
+    }
+}
+
+// Footprint of a template, as returned by `GET /templates/{id}/stats`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TemplateStats {
+    pub id: String,
+    pub base_entries: usize,
+    pub base_uncompressed_bytes: u64,
+    // Fill files plus the base entries whose text gets filled
+    pub fill_files: usize,
+    // Distinct placeholder names used across those files
+    pub placeholders: usize,
+}
+
+// Where a placeholder that a request may leave unfilled is used
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+pub struct PlaceholderUse {
+    pub name: String,
+    // Template files and base entries, or "zerohub.toml" for the config's
+    // `getting_started`, `filename`, `rename` and `zip_comment` values
+    pub used_in: Vec<String>,
+}
+
+// Which of a template's placeholders a request can fill, as returned by
+// `GET /templates/{id}/coverage`. Only text that gets filled is scanned.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlaceholderCoverage {
+    pub id: String,
+    // Filled from UserInfo, derived from it, or supplied by the template
+    pub satisfied: Vec<String>,
+    // Declared in `placeholders`, so filled only when the request's `extra` has them
+    pub requires_extra: Vec<PlaceholderUse>,
+    // Neither built in nor declared: always left as written, unless a
+    // non-strict template's request sets `default_placeholder_value`
+    pub unresolvable: Vec<PlaceholderUse>,
+}
+
+impl LoadedTemplate {
+    // Placeholder names used in the text this template fills, with where
+    pub fn placeholder_uses(&self) -> Result<BTreeMap<String, BTreeSet<String>>, AppError> {
+        let mut uses = BTreeMap::<String, BTreeSet<String>>::new();
+        self.scan_filled_text(|content, source| {
+            for name in placeholder_names(content) {
+                uses.entry(name).or_default().insert(source.to_string());
+            }
+        })?;
+        Ok(uses)
+    }
+
+    // Fail on any `{{fn:...}}` call to an unknown function in the text this
+    // template fills, listing every one with where
+    pub fn check_functions(&self) -> Result<(), AppError> {
+        let mut problems = Vec::new();
+        self.scan_filled_text(|content, source| {
+            let unknown = functions::unknown_functions(content, functions::builtin());
+            if !unknown.is_empty() {
+                problems.push(format!("{} calls unknown placeholder functions: {}", source, unknown.join(", ")));
+            }
+        })?;
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(problems.join("; ").into())
+    }
+
+    // Call `scan` with each piece of text this template fills and where it is
+    // from: fill files, filled base entries and the config's own text
+    fn scan_filled_text(&self, mut scan: impl FnMut(&str, &str)) -> Result<(), AppError> {
+        self.scan_filled_files(&mut scan)?;
+        let config_text = self
+            .config
+            .getting_started
+            .iter()
+            .chain(self.config.rename.values())
+            .chain(&self.config.zip_comment);
+        for content in config_text {
+            scan(content, "zerohub.toml");
+        }
+        // `{{template_id}}` is only filled in the filename pattern
+        if let Some(filename) = &self.config.filename {
+            scan(&filename.replace(&format!("{{{{{}}}}}", FILENAME_TEMPLATE_ID), ""), "zerohub.toml");
+        }
+        Ok(())
+    }
+
+    // Call `scan` with each fill file and filled text base entry
+    fn scan_filled_files(&self, mut scan: impl FnMut(&str, &str)) -> Result<(), AppError> {
+        for (name, content) in &self.fill_files {
+            scan(substituted_part(content, self.config.substitution_region.get(name).copied()), name);
+        }
+        let mut archive = open_base_archive(&self.id, &self.base_zip)?;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(|e| AppError::BaseArchiveCorrupt {
+                template_id: self.id.clone(),
+                reason: e.to_string(),
+            })?;
+            let name = entry.name().to_string();
+            if entry.is_dir() || !self.config.fills_base_entry(&name, self.base_is_directory) {
+                continue;
+            }
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            if let Some(text) = as_text(&content) {
+                scan(substituted_part(text, self.config.substitution_region.get(&name).copied()), &name);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn coverage(&self) -> Result<PlaceholderCoverage, AppError> {
+        let mut coverage = PlaceholderCoverage {
+            id: self.id.clone(),
+            satisfied: Vec::new(),
+            requires_extra: Vec::new(),
+            unresolvable: Vec::new(),
+        };
+        for (name, used_in) in self.placeholder_uses()? {
+            let supplied = match name.as_str() {
+                "dependencies" => !self.config.frameworks.is_empty(),
+                "client_ip" | "user_agent" => self.config.request_placeholders,
+                name => BUILTIN_PLACEHOLDERS.contains(&name),
+            };
+            let used = PlaceholderUse { name, used_in: used_in.into_iter().collect() };
+            if supplied {
+                coverage.satisfied.push(used.name);
+            } else if self.config.placeholders.contains(&used.name) {
+                coverage.requires_extra.push(used);
+            } else {
+                coverage.unresolvable.push(used);
+            }
+        }
+        Ok(coverage)
+    }
+
+    pub fn stats(&self) -> Result<TemplateStats, AppError> {
+        let mut archive = open_base_archive(&self.id, &self.base_zip)?;
+        let mut base_uncompressed_bytes = 0u64;
+        for i in 0..archive.len() {
+            let entry = archive.by_index_raw(i).map_err(|e| AppError::BaseArchiveCorrupt {
+                template_id: self.id.clone(),
+                reason: e.to_string(),
+            })?;
+            base_uncompressed_bytes += entry.size();
+        }
+
+        // Count filled base entries as fill files, the same ones `coverage` scans
+        let mut fill_files = 0;
+        let mut placeholders = BTreeSet::new();
+        self.scan_filled_files(|content, _| {
+            fill_files += 1;
+            placeholders.extend(placeholder_names(content));
+        })?;
+
+        Ok(TemplateStats {
+            id: self.id.clone(),
+            base_entries: archive.len(),
+            base_uncompressed_bytes,
+            fill_files,
+            placeholders: placeholders.len(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReloadedTemplate {
+    pub id: String,
+    pub base_zip_bytes: usize,
+    pub fill_files: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FailedTemplate {
+    pub id: String,
+    // Error code, as in `ErrorBody`, e.g. "BASE_ARCHIVE_CORRUPT"
+    pub code: &'static str,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReloadSummary {
+    // Registry version of this cache, see `TemplateRegistry`
+    pub version: u64,
+    pub reloaded: Vec<ReloadedTemplate>,
+    pub failed: Vec<FailedTemplate>,
+    pub loaded_at: String,
+}
+
+// In-memory copy of every template, shared by the generate handlers.
+// Templates that failed to load keep their error so requests can report it.
+#[derive(Debug, Default)]
+pub struct TemplateCache {
+    templates: BTreeMap<String, Arc<LoadedTemplate>>,
+    errors: BTreeMap<String, AppError>,
+    loaded_at: String,
+    // Set by `TemplateRegistry` when the cache is installed
+    version: u64,
+}
+
+impl TemplateCache {
+    pub fn load(retry: ReadRetry) -> Self {
+        let mut cache = TemplateCache {
+            loaded_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+            ..Default::default()
+        };
+        for spec in &TEMPLATE_SPECS {
+            match load_template(spec, retry) {
+                Ok(template) => {
+                    cache.templates.insert(spec.id.to_string(), Arc::new(template));
+                }
+                Err(e) => {
+                    eprintln!("[ERROR] Failed to load template '{}': {}", spec.id, e);
+                    cache.errors.insert(spec.id.to_string(), e);
+                }
+            }
+        }
+        cache
+    }
+
+    // A cache holding just these templates, for handler tests
+    #[cfg(test)]
+    pub fn from_templates(templates: Vec<LoadedTemplate>) -> Self {
+        TemplateCache {
+            templates: templates.into_iter().map(|t| (t.id.clone(), Arc::new(t))).collect(),
+            ..Default::default()
+        }
+    }
+
+    // Record a template as failed to load, for handler tests
+    #[cfg(test)]
+    pub fn with_error(mut self, id: &str, error: AppError) -> Self {
+        self.errors.insert(id.to_string(), error);
+        self
+    }
+
+    pub fn get(&self, id: &str) -> Result<Arc<LoadedTemplate>, AppError> {
+        if let Some(template) = self.templates.get(id) {
+            return Ok(Arc::clone(template));
+        }
+        match self.errors.get(id) {
+            Some(error) => Err(AppError::Internal(error.to_string())),
+            None => Err(AppError::NotFound(format!("Unknown template: {}", id))),
+        }
+    }
+
+    pub fn templates(&self) -> impl Iterator<Item = &Arc<LoadedTemplate>> {
+        self.templates.values()
+    }
+
+    // Templates that failed to load, with why
+    pub fn errors(&self) -> impl Iterator<Item = (&String, &AppError)> {
+        self.errors.iter()
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn summary(&self) -> ReloadSummary {
+        ReloadSummary {
+            version: self.version,
+            reloaded: self
+                .templates
+                .values()
+                .map(|t| ReloadedTemplate {
+                    id: t.id.clone(),
+                    base_zip_bytes: t.base_zip.len(),
+                    fill_files: t.fill_files.len(),
+                })
+                .collect(),
+            failed: self
+                .errors
+                .iter()
+                .map(|(id, error)| FailedTemplate { id: id.clone(), code: error.code(), error: error.to_string() })
+                .collect(),
+            loaded_at: self.loaded_at.clone(),
+        }
+    }
+}
+
+// The current template cache, replaced whole on reload. Readers take a
+// snapshot without locking, so a request sees one consistent set of
+// templates however long it runs, and a reload never waits for readers.
+#[derive(Debug)]
+pub struct TemplateRegistry {
+    current: ArcSwap<TemplateCache>,
+    next_version: AtomicU64,
+}
+
+impl TemplateRegistry {
+    // The first cache is version 1
+    pub fn new(cache: TemplateCache) -> Self {
+        let registry = TemplateRegistry { current: ArcSwap::from_pointee(TemplateCache::default()), next_version: AtomicU64::new(0) };
+        registry.replace(cache);
+        registry
+    }
+
+    pub fn snapshot(&self) -> Arc<TemplateCache> {
+        self.current.load_full()
+    }
+
+    // Install an already loaded cache under the next version
+    pub fn replace(&self, cache: TemplateCache) -> Arc<TemplateCache> {
+        self.install(self.reserve(), cache)
+    }
+
+    // The version for a reload about to start. Taken before loading, so when
+    // two reloads race the one that started later wins even if it finishes first.
+    pub fn reserve(&self) -> u64 {
+        self.next_version.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    // Install a cache under its reserved `version`, unless a later one is
+    // already live, and return the cache that is live afterwards
+    pub fn install(&self, version: u64, mut cache: TemplateCache) -> Arc<TemplateCache> {
+        cache.version = version;
+        let cache = Arc::new(cache);
+        let previous = self.current.rcu(|current| if current.version > version { Arc::clone(current) } else { Arc::clone(&cache) });
+        if previous.version > version {
+            previous
+        } else {
+            cache
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_with_retry_gives_up_after_the_configured_attempts() {
+        let retry = ReadRetry { attempts: 3, delay: Duration::from_millis(5) };
+        let dir = tempfile::tempdir().unwrap();
+
+        // Reading a directory fails on every attempt, so all of them are used
+        let started = std::time::Instant::now();
+        assert!(read_with_retry(dir.path(), retry).is_err());
+        assert!(started.elapsed() >= Duration::from_millis(15));
+
+        let file = dir.path().join("base.zip");
+        fs::write(&file, b"zip").unwrap();
+        assert_eq!(read_with_retry(&file, retry).unwrap(), b"zip");
+    }
+
+    #[test]
+    fn gzipped_base_zip_is_preferred_and_decompressed() {
+        use flate2::{write::GzEncoder, Compression};
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("zero.zip"), b"plain").unwrap();
+        assert_eq!(read_base_zip(dir.path(), "zero.zip", ReadRetry::default()).unwrap(), b"plain");
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"compressed").unwrap();
+        fs::write(dir.path().join("zero.zip.gz"), encoder.finish().unwrap()).unwrap();
+        assert_eq!(read_base_zip(dir.path(), "zero.zip", ReadRetry::default()).unwrap(), b"compressed");
+
+        fs::write(dir.path().join("zero.zip.gz"), b"not gzip").unwrap();
+        assert!(read_base_zip(dir.path(), "zero.zip", ReadRetry::default()).is_err());
+    }
+
+    #[test]
+    fn tarball_is_read_when_there_is_no_base_zip() {
+        use flate2::{write::GzEncoder, Compression};
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(2);
+        builder.append_data(&mut header, "README.md", &b"hi"[..]).unwrap();
+        fs::write(dir.path().join("zero.tar.gz"), builder.into_inner().unwrap().finish().unwrap()).unwrap();
+
+        let bytes = read_base_zip(dir.path(), "zero.zip", ReadRetry::default()).unwrap();
+        assert_eq!(open_base_archive("server", &bytes).unwrap().file_names().collect::<Vec<_>>(), ["README.md"]);
+        fs::write(dir.path().join("zero.zip"), b"plain").unwrap();
+        assert_eq!(read_base_zip(dir.path(), "zero.zip", ReadRetry::default()).unwrap(), b"plain");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn base_directory_is_zipped_with_relative_paths_and_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("scripts")).unwrap();
+        fs::write(root.path().join("README.md"), "# {{project_name}}").unwrap();
+        fs::write(root.path().join("scripts/run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(root.path().join("scripts/run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        let zip_data = zip_directory(root.path(), ReadRetry::default()).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(zip_data)).unwrap();
+        assert_eq!(archive.file_names().collect::<BTreeSet<_>>(), BTreeSet::from(["README.md", "scripts/run.sh"]));
+        assert_eq!(archive.by_name("scripts/run.sh").unwrap().unix_mode().unwrap() & 0o777, 0o755);
+    }
+
+    #[test]
+    fn registry_versions_increase_and_old_snapshots_stay_intact() {
+        let registry = TemplateRegistry::new(TemplateCache::default());
+        let before = registry.snapshot();
+        assert_eq!(before.version(), 1);
+
+        let mut reloaded = TemplateCache::default();
+        reloaded.errors.insert("server".to_string(), AppError::Internal("broken".to_string()));
+        assert_eq!(registry.replace(reloaded).version(), 2);
+        assert_eq!(registry.snapshot().summary().failed.len(), 1);
+        assert!(before.errors.is_empty());
+    }
+
+    #[test]
+    fn a_reload_that_started_first_never_replaces_a_later_one() {
+        let registry = TemplateRegistry::new(TemplateCache::default());
+        let slow = registry.reserve();
+        let fast = registry.reserve();
+
+        let mut fresh = TemplateCache::default();
+        fresh.errors.insert("client".to_string(), AppError::Internal("fresh".to_string()));
+        assert_eq!(registry.install(fast, fresh).version(), fast);
+        let mut stale = TemplateCache::default();
+        stale.errors.insert("server".to_string(), AppError::Internal("stale".to_string()));
+        let live = registry.install(slow, stale);
+
+        assert_eq!(live.version(), fast);
+        assert_eq!(registry.snapshot().version(), fast);
+        assert_eq!(registry.snapshot().summary().failed[0].error, "fresh");
+    }
+
+    #[test]
+    fn coverage_sorts_placeholders_by_how_they_get_filled() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("notes.md"), "{{project_name}} {{python_version}} {{licence}}").unwrap();
+        fs::write(root.path().join("logo.png"), "{{not_scanned}}").unwrap();
+        let mut config = TemplateConfig { placeholders: vec!["python_version".to_string()], ..Default::default() };
+        config.text_extensions = Some(vec!["md".to_string()]);
+        config.filename = Some("{{project_slug}}-{{template_id}}.zip".to_string());
+        let base_zip = zip_directory(root.path(), ReadRetry::default()).unwrap();
+        let fill_files = vec![("README.md".to_string(), "{{dependencies}} {{python_version}}".to_string())];
+        let template = LoadedTemplate { base_is_directory: true, config, ..LoadedTemplate::for_test("server", base_zip, fill_files) };
+
+        let coverage = template.coverage().unwrap();
+        assert_eq!(coverage.satisfied, ["project_name", "project_slug"]);
+        let requires_extra = PlaceholderUse { name: "python_version".to_string(), used_in: vec!["README.md".to_string(), "notes.md".to_string()] };
+        assert_eq!(coverage.requires_extra, [requires_extra]);
+        let unresolvable = coverage.unresolvable.iter().map(|u| u.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(unresolvable, ["dependencies", "licence"]);
+    }
+
+    #[test]
+    fn stats_count_the_filled_base_entries_coverage_scans() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("notes.md"), "{{project_name}} {{licence}}").unwrap();
+        fs::write(root.path().join("logo.png"), "{{not_scanned}}").unwrap();
+        let config = TemplateConfig { text_extensions: Some(vec!["md".to_string()]), ..Default::default() };
+        let base_zip = zip_directory(root.path(), ReadRetry::default()).unwrap();
+        let fill_files = vec![("README.md".to_string(), "{{project_name}}".to_string())];
+        let template = LoadedTemplate { base_is_directory: true, config, ..LoadedTemplate::for_test("server", base_zip, fill_files) };
+
+        let stats = template.stats().unwrap();
+        assert_eq!(stats.base_entries, 2);
+        assert_eq!(stats.fill_files, 2);
+        assert_eq!(stats.placeholders, 2);
+    }
+
+    #[test]
+    fn unknown_functions_are_found_in_every_filled_text() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("notes.md"), "{{upper:project_name}} {{shout:project_name}}").unwrap();
+        fs::write(root.path().join("logo.png"), "{{not_scanned:x}}").unwrap();
+        let mut config = TemplateConfig { text_extensions: Some(vec!["md".to_string()]), ..Default::default() };
+        config.zip_comment = Some("{{whisper:project_name}}".to_string());
+        let base_zip = zip_directory(root.path(), ReadRetry::default()).unwrap();
+        let fill_files = vec![("README.md".to_string(), "{{date:%Y}}".to_string())];
+        let mut template = LoadedTemplate { base_is_directory: true, config, ..LoadedTemplate::for_test("server", base_zip, fill_files) };
+
+        let error = template.check_functions().unwrap_err().to_string();
+        assert!(error.contains("notes.md calls unknown placeholder functions: shout"), "{}", error);
+        assert!(error.contains("zerohub.toml calls unknown placeholder functions: whisper"), "{}", error);
+        assert!(!error.contains("not_scanned") && !error.contains("README.md"), "{}", error);
+
+        template.config.zip_comment = None;
+        template.config.text_extensions = Some(Vec::new());
+        assert!(template.check_functions().is_ok());
+    }
+}
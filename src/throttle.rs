@@ -0,0 +1,233 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::error::AppError;
+use crate::maintenance::MAINTENANCE_RETRY_AFTER_SECS_ENV;
+use crate::settings::env_or;
+use crate::AppState;
+
+// Generate requests allowed per client IP per minute; unlimited when unset or 0
+pub const RATE_LIMIT_PER_MINUTE_ENV: &str = "ZEROHUB_RATE_LIMIT_PER_MINUTE";
+// Generate requests handled at once; others are turned away until one ends.
+// Unlimited when unset or 0.
+pub const MAX_CONCURRENT_GENERATIONS_ENV: &str = "ZEROHUB_MAX_CONCURRENT_GENERATIONS";
+// Seconds sent in `Retry-After` for each reason, see `RetryAfter`
+pub const RATE_LIMIT_RETRY_AFTER_SECS_ENV: &str = "ZEROHUB_RATE_LIMIT_RETRY_AFTER_SECS";
+pub const QUEUE_FULL_RETRY_AFTER_SECS_ENV: &str = "ZEROHUB_QUEUE_FULL_RETRY_AFTER_SECS";
+
+// Client IPs tracked before expired windows are dropped
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+// Why a request was turned away for now. Every reason answers with
+// `Retry-After` and says when to retry in the body; see `AppError::Throttled`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Throttle {
+    RateLimited,
+    QueueFull,
+    Maintenance,
+}
+
+impl Throttle {
+    pub fn status(self) -> StatusCode {
+        match self {
+            Throttle::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            Throttle::QueueFull | Throttle::Maintenance => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Throttle::RateLimited => "RATE_LIMITED",
+            Throttle::QueueFull => "QUEUE_FULL",
+            Throttle::Maintenance => "SERVICE_UNAVAILABLE",
+        }
+    }
+}
+
+// Longest `Retry-After` accepted from the environment; larger values are
+// capped, since a client told to wait longer has given up anyway
+pub const MAX_RETRY_AFTER_SECS: u64 = 24 * 60 * 60;
+
+// Default `Retry-After` seconds for each reason, each set by its own variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryAfter {
+    pub rate_limited: u64,
+    pub queue_full: u64,
+    pub maintenance: u64,
+}
+
+impl Default for RetryAfter {
+    fn default() -> Self {
+        RetryAfter { rate_limited: 60, queue_full: 5, maintenance: 120 }
+    }
+}
+
+impl RetryAfter {
+    pub fn from_env() -> Self {
+        let defaults = RetryAfter::default();
+        let secs = |name: &str, default: u64| {
+            let secs = env_or(name, default);
+            if secs > MAX_RETRY_AFTER_SECS {
+                println!("[WARN] Capping {}={} at {} seconds", name, secs, MAX_RETRY_AFTER_SECS);
+            }
+            secs.min(MAX_RETRY_AFTER_SECS)
+        };
+        RetryAfter {
+            rate_limited: secs(RATE_LIMIT_RETRY_AFTER_SECS_ENV, defaults.rate_limited),
+            queue_full: secs(QUEUE_FULL_RETRY_AFTER_SECS_ENV, defaults.queue_full),
+            maintenance: secs(MAINTENANCE_RETRY_AFTER_SECS_ENV, defaults.maintenance),
+        }
+    }
+
+    pub fn secs(&self, reason: Throttle) -> u64 {
+        match reason {
+            Throttle::RateLimited => self.rate_limited,
+            Throttle::QueueFull => self.queue_full,
+            Throttle::Maintenance => self.maintenance,
+        }
+    }
+
+    pub fn error(&self, reason: Throttle, message: &str) -> AppError {
+        AppError::Throttled { reason, message: message.to_string(), retry_after_secs: self.secs(reason) }
+    }
+}
+
+// Fixed one-minute windows of generate requests per client IP
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_minute: u32,
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32) -> Self {
+        RateLimiter { per_minute, windows: Mutex::new(HashMap::new()) }
+    }
+
+    // Count a request from `client`; false once it is over the limit
+    pub fn allow(&self, client: &str, now: Instant) -> bool {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        if windows.len() >= MAX_TRACKED_CLIENTS {
+            windows.retain(|_, (started, _)| now.duration_since(*started) < RATE_WINDOW);
+        }
+        let (started, count) = windows.entry(client.to_string()).or_insert((now, 0));
+        if now.duration_since(*started) >= RATE_WINDOW {
+            *started = now;
+            *count = 0;
+        }
+        *count += 1;
+        *count <= self.per_minute
+    }
+}
+
+// The limits applied to the generate endpoints, both off by default
+#[derive(Debug, Default)]
+pub struct Throttles {
+    pub rate_limiter: Option<RateLimiter>,
+    pub generation_slots: Option<Arc<Semaphore>>,
+}
+
+impl Throttles {
+    pub fn from_env() -> Self {
+        let per_minute = env_or(RATE_LIMIT_PER_MINUTE_ENV, 0u32);
+        let slots = env_or(MAX_CONCURRENT_GENERATIONS_ENV, 0usize);
+        if per_minute > 0 {
+            println!("[DEBUG] Rate limiting generation to {} requests per minute per client", per_minute);
+        }
+        if slots > 0 {
+            println!("[DEBUG] Handling at most {} generate requests at once", slots);
+        }
+        Throttles {
+            rate_limiter: (per_minute > 0).then(|| RateLimiter::new(per_minute)),
+            generation_slots: (slots > 0).then(|| Arc::new(Semaphore::new(slots))),
+        }
+    }
+}
+
+// The generation slot held for a request, in its extensions. Handlers that
+// keep building after they return, like the batch stream, take the permit
+// along so the slot stays taken until they finish.
+#[derive(Debug, Clone)]
+pub struct GenerationSlot(Arc<Mutex<Option<OwnedSemaphorePermit>>>);
+
+impl GenerationSlot {
+    pub fn take(&self) -> Option<OwnedSemaphorePermit> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+// Turn requests that build projects away with 429 or 503 when a client is over
+// its rate or every generation slot is taken. Layered on just those routes,
+// see `build_router`. A slot is held until the handler returns, or longer when
+// it takes the `GenerationSlot`.
+pub async fn throttle_generation(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let retry_after = state.settings.retry_after;
+    if let Some(limiter) = &state.throttles.rate_limiter {
+        let client = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map_or_else(|| "unknown".to_string(), |ConnectInfo(addr)| addr.ip().to_string());
+        if !limiter.allow(&client, Instant::now()) {
+            println!("[DEBUG] Rate limiting {} from {}", request.uri().path(), client);
+            return retry_after.error(Throttle::RateLimited, "Too many generate requests; slow down").into_response();
+        }
+    }
+    let permit = match &state.throttles.generation_slots {
+        Some(slots) => match Arc::clone(slots).try_acquire_owned() {
+            Ok(permit) => Some(permit),
+            Err(_) => {
+                println!("[DEBUG] Every generation slot is busy, rejecting {}", request.uri().path());
+                return retry_after.error(Throttle::QueueFull, "The server is busy generating; try again shortly").into_response();
+            }
+        },
+        None => None,
+    };
+    let slot = GenerationSlot(Arc::new(Mutex::new(permit)));
+    request.extensions_mut().insert(slot.clone());
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limit_windows_reset_after_a_minute() {
+        let limiter = RateLimiter::new(2);
+        let start = Instant::now();
+        assert!(limiter.allow("a", start) && limiter.allow("a", start));
+        assert!(!limiter.allow("a", start + Duration::from_secs(30)));
+        assert!(limiter.allow("b", start));
+        assert!(limiter.allow("a", start + RATE_WINDOW));
+    }
+
+    #[test]
+    fn each_reason_has_its_own_status_and_retry_after() {
+        let retry_after = RetryAfter { rate_limited: 30, ..Default::default() };
+        let error = retry_after.error(Throttle::RateLimited, "slow down");
+        assert_eq!((error.status(), error.code()), (StatusCode::TOO_MANY_REQUESTS, "RATE_LIMITED"));
+        let response = retry_after.error(Throttle::QueueFull, "busy").into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[axum::http::header::RETRY_AFTER], "5");
+    }
+
+    // Set directly, an out-of-range wait still answers instead of panicking
+    #[test]
+    fn huge_retry_after_leaves_retry_at_out() {
+        let retry_after = RetryAfter { queue_full: u64::MAX, ..Default::default() };
+        let response = retry_after.error(Throttle::QueueFull, "busy").into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = response.extensions().get::<crate::error::ErrorBody>().unwrap();
+        assert_eq!((body.retry_after_secs, body.retry_at.as_deref()), (Some(u64::MAX), None));
+    }
+}
@@ -0,0 +1,207 @@
+use axum::{extract::ConnectInfo, Router};
+use hyper::{body::Incoming, Request};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::{
+    self,
+    crypto::{ring, CryptoProvider},
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    ServerConfig, SupportedCipherSuite, SupportedProtocolVersion,
+};
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+
+// PEM certificate chain and private key; HTTPS is served when both are set
+pub const TLS_CERT_ENV: &str = "ZEROHUB_TLS_CERT";
+pub const TLS_KEY_ENV: &str = "ZEROHUB_TLS_KEY";
+// Lowest protocol version accepted: "1.2" (default) or "1.3"
+pub const MIN_TLS_VERSION_ENV: &str = "ZEROHUB_MIN_TLS_VERSION";
+// Comma-separated rustls cipher suite names, e.g.
+// "TLS13_AES_256_GCM_SHA384,TLS13_CHACHA20_POLY1305_SHA256"; all of the
+// provider's suites when unset
+pub const TLS_CIPHER_SUITES_ENV: &str = "ZEROHUB_TLS_CIPHER_SUITES";
+
+static TLS13_ONLY: &[&SupportedProtocolVersion] = &[&rustls::version::TLS13];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MinTlsVersion {
+    #[default]
+    Tls12,
+    Tls13,
+}
+
+impl FromStr for MinTlsVersion {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().trim_start_matches("tls") {
+            "1.2" => Ok(MinTlsVersion::Tls12),
+            "1.3" => Ok(MinTlsVersion::Tls13),
+            _ => Err(format!("expected \"1.2\" or \"1.3\", got {:?}", value)),
+        }
+    }
+}
+
+impl MinTlsVersion {
+    pub fn protocol_versions(self) -> &'static [&'static SupportedProtocolVersion] {
+        match self {
+            MinTlsVersion::Tls12 => rustls::ALL_VERSIONS,
+            MinTlsVersion::Tls13 => TLS13_ONLY,
+        }
+    }
+
+    fn allows(self, suite: &SupportedCipherSuite) -> bool {
+        self.protocol_versions().contains(&suite.version())
+    }
+}
+
+fn suite_name(suite: &SupportedCipherSuite) -> String {
+    format!("{:?}", suite.suite())
+}
+
+// Pick the named suites from the provider, in the order given. Every name must
+// be known and usable with the minimum version.
+pub fn select_cipher_suites(names: &str, min_version: MinTlsVersion) -> Result<Vec<SupportedCipherSuite>, String> {
+    let available = ring::default_provider().cipher_suites;
+    let mut selected = Vec::new();
+    for name in names.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        let suite = available
+            .iter()
+            .find(|suite| suite_name(suite).eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let known = available.iter().map(suite_name).collect::<Vec<_>>().join(", ");
+                format!("unknown cipher suite {:?}; supported: {}", name, known)
+            })?;
+        if !min_version.allows(suite) {
+            return Err(format!("cipher suite {} is not available with TLS 1.3 only", name));
+        }
+        selected.push(*suite);
+    }
+    if selected.is_empty() {
+        return Err("no cipher suites given".to_string());
+    }
+    Ok(selected)
+}
+
+// HTTPS settings, read and validated once at startup
+#[derive(Debug, Clone)]
+pub struct TlsSettings {
+    pub cert_path: String,
+    pub key_path: String,
+    pub min_version: MinTlsVersion,
+    pub cipher_suites: Vec<SupportedCipherSuite>,
+}
+
+impl TlsSettings {
+    // `Ok(None)` serves plain HTTP. Invalid values are errors rather than
+    // falling back to defaults, so a misconfigured server doesn't start.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let var = |name| std::env::var(name).ok().filter(|value: &String| !value.trim().is_empty());
+        let min_version = match var(MIN_TLS_VERSION_ENV) {
+            Some(value) => value.parse().map_err(|e| format!("Invalid {}: {}", MIN_TLS_VERSION_ENV, e))?,
+            None => MinTlsVersion::default(),
+        };
+        let cipher_suites = match var(TLS_CIPHER_SUITES_ENV) {
+            Some(names) => {
+                select_cipher_suites(&names, min_version).map_err(|e| format!("Invalid {}: {}", TLS_CIPHER_SUITES_ENV, e))?
+            }
+            None => ring::default_provider()
+                .cipher_suites
+                .into_iter()
+                .filter(|suite| min_version.allows(suite))
+                .collect(),
+        };
+
+        let (cert_path, key_path) = match (var(TLS_CERT_ENV), var(TLS_KEY_ENV)) {
+            (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+            (None, None) => {
+                for name in [MIN_TLS_VERSION_ENV, TLS_CIPHER_SUITES_ENV] {
+                    if var(name).is_some() {
+                        println!("[WARN] Ignoring {}: TLS is off without {} and {}", name, TLS_CERT_ENV, TLS_KEY_ENV);
+                    }
+                }
+                return Ok(None);
+            }
+            _ => return Err(format!("{} and {} must be set together", TLS_CERT_ENV, TLS_KEY_ENV)),
+        };
+        Ok(Some(TlsSettings { cert_path, key_path, min_version, cipher_suites }))
+    }
+
+    pub fn server_config(&self) -> Result<ServerConfig, String> {
+        let certs = CertificateDer::pem_file_iter(&self.cert_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to read {} ({}): {}", TLS_CERT_ENV, self.cert_path, e))?;
+        let key = PrivateKeyDer::from_pem_file(&self.key_path)
+            .map_err(|e| format!("Failed to read {} ({}): {}", TLS_KEY_ENV, self.key_path, e))?;
+
+        let provider = CryptoProvider { cipher_suites: self.cipher_suites.clone(), ..ring::default_provider() };
+        let mut config = ServerConfig::builder_with_provider(Arc::new(provider))
+            .with_protocol_versions(self.min_version.protocol_versions())
+            .map_err(|e| format!("Invalid TLS configuration: {}", e))?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map_err(|e| format!("Invalid TLS certificate or key: {}", e))?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(config)
+    }
+}
+
+// Accept TLS connections and serve the app on each, with the peer address in
+// `ConnectInfo` like the plain HTTP server
+pub async fn serve(listener: TcpListener, app: Router, config: ServerConfig) -> std::io::Result<()> {
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                eprintln!("[ERROR] Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let app = app.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    println!("[DEBUG] TLS handshake with {} failed: {}", peer, e);
+                    return;
+                }
+            };
+            let service = hyper::service::service_fn(move |mut request: Request<Incoming>| {
+                request.extensions_mut().insert(ConnectInfo::<SocketAddr>(peer));
+                app.clone().oneshot(request)
+            });
+            if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await
+            {
+                println!("[DEBUG] Connection with {} closed with error: {}", peer, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn min_version_and_cipher_suites_are_validated() {
+        assert_eq!("1.3".parse(), Ok(MinTlsVersion::Tls13));
+        assert_eq!("TLS1.2".parse(), Ok(MinTlsVersion::Tls12));
+        assert!("1.1".parse::<MinTlsVersion>().is_err());
+
+        let suites = select_cipher_suites("TLS13_AES_256_GCM_SHA384, TLS13_CHACHA20_POLY1305_SHA256", MinTlsVersion::Tls13).unwrap();
+        assert_eq!(suites.len(), 2);
+        let tls12_suite = "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256";
+        assert!(select_cipher_suites(tls12_suite, MinTlsVersion::Tls12).is_ok());
+        assert!(select_cipher_suites(tls12_suite, MinTlsVersion::Tls13).is_err());
+        assert!(select_cipher_suites("TLS_RSA_WITH_RC4_128_MD5", MinTlsVersion::Tls12).is_err());
+        assert!(select_cipher_suites(" , ", MinTlsVersion::Tls12).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::builder::{collect_entries, ZipEntry};
+use crate::error::{AppError, ErrorBody};
+use crate::extract::{JsonOrForm, RequestInfo};
+use crate::{prepare_project, project_filename, AppState, UserInfo};
+
+fn default_template() -> String {
+    "server".to_string()
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TreeParams {
+    // Template whose generated contents are listed
+    #[serde(default = "default_template")]
+    #[param(default = "server")]
+    pub template: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeType {
+    File,
+    Dir,
+}
+
+// One file or directory of a generated project. Files carry their size in
+// bytes, directories their children: subdirectories first, then files, each
+// sorted by name.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TreeNode {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub node_type: NodeType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(no_recursion)]
+    pub children: Option<Vec<TreeNode>>,
+}
+
+#[derive(Default)]
+struct DirBuilder {
+    dirs: BTreeMap<String, DirBuilder>,
+    files: BTreeMap<String, u64>,
+}
+
+impl DirBuilder {
+    fn into_node(self, name: String) -> TreeNode {
+        let mut children = self
+            .dirs
+            .into_iter()
+            .map(|(name, dir)| dir.into_node(name))
+            .collect::<Vec<_>>();
+        children.extend(self.files.into_iter().map(|(name, size)| TreeNode {
+            name,
+            node_type: NodeType::File,
+            size: Some(size),
+            children: None,
+        }));
+        TreeNode { name, node_type: NodeType::Dir, size: None, children: Some(children) }
+    }
+}
+
+// Parse the entries' slash-separated paths into a tree under a root directory.
+// Explicit directory entries (`name/`) show up even when empty.
+pub fn build_tree(root: &str, entries: &[ZipEntry]) -> TreeNode {
+    let mut tree = DirBuilder::default();
+    for entry in entries {
+        let is_dir = entry.name.ends_with('/');
+        let mut parts = entry.name.split('/').filter(|part| !part.is_empty()).collect::<Vec<_>>();
+        let file = if is_dir { None } else { parts.pop() };
+        let dir = parts
+            .into_iter()
+            .fold(&mut tree, |dir, part| dir.dirs.entry(part.to_string()).or_default());
+        if let Some(file) = file {
+            dir.files.insert(file.to_string(), entry.content.len() as u64);
+        }
+    }
+    tree.into_node(root.to_string())
+}
+
+// Preview a generated project as a file tree, without the file contents
+#[utoipa::path(
+    post,
+    path = "/tree",
+    tag = "templates",
+    params(TreeParams),
+    request_body(content(
+        (UserInfo = "application/json"),
+        (UserInfo = "application/x-www-form-urlencoded"),
+        (UserInfo = "application/json5"),
+        (UserInfo = "application/yaml"),
+    )),
+    responses(
+        (status = 200, description = "Root directory named after the zip, with nested children", body = TreeNode),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Unknown template", body = ErrorBody),
+    )
+)]
+pub async fn project_tree(
+    State(state): State<AppState>,
+    Query(params): Query<TreeParams>,
+    request_info: RequestInfo,
+    JsonOrForm(user_info): JsonOrForm<UserInfo>,
+) -> Result<Json<TreeNode>, AppError> {
+    let template = state.template(&params.template)?;
+    let (template_data, build_options) = prepare_project(&template, user_info, &state.settings, &request_info)?;
+    let entries = collect_entries(&template, &template_data, &build_options)?;
+    let filename = project_filename(&template, &template_data);
+    println!("[DEBUG] Built {} tree for {}: {} entries", template.id, filename, entries.len());
+    Ok(Json(build_tree(filename.trim_end_matches(".zip"), &entries)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, size: usize) -> ZipEntry {
+        ZipEntry { name: name.to_string(), content: vec![0; size], unix_mode: None, substitutions: 0 }
+    }
+
+    #[test]
+    fn nests_entries_with_dirs_before_files() {
+        let entries = [entry("README.md", 3), entry("src/main.rs", 12), entry("src/api/mod.rs", 1), entry("assets/", 0)];
+        let tree = serde_json::to_value(build_tree("demo", &entries)).unwrap();
+        assert_eq!(
+            tree,
+            serde_json::json!({
+                "name": "demo", "type": "dir", "children": [
+                    { "name": "assets", "type": "dir", "children": [] },
+                    { "name": "src", "type": "dir", "children": [
+                        { "name": "api", "type": "dir", "children": [{ "name": "mod.rs", "type": "file", "size": 1 }] },
+                        { "name": "main.rs", "type": "file", "size": 12 },
+                    ] },
+                    { "name": "README.md", "type": "file", "size": 3 },
+                ]
+            })
+        );
+    }
+}
@@ -0,0 +1,426 @@
+use serde::Serialize;
+use std::fmt;
+use utoipa::ToSchema;
+
+use crate::settings::Settings;
+use crate::UserInfo;
+
+// npm's limit, which is also plenty for Python package names
+pub const MAX_SLUG_LEN: usize = 214;
+
+// Upper bounds on the request's string fields, in characters, counted after
+// trimming. `max_option_len` covers the short option fields: profile,
+// framework, compression, default_placeholder_value, zip_comment and each
+// exclude pattern.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldLimits {
+    pub max_username_len: usize,
+    pub max_email_len: usize,
+    pub max_project_name_len: usize,
+    pub max_description_len: usize,
+    pub max_option_len: usize,
+}
+
+impl Default for FieldLimits {
+    fn default() -> Self {
+        FieldLimits {
+            max_username_len: 64,
+            max_email_len: 254,
+            max_project_name_len: 100,
+            max_description_len: 2000,
+            max_option_len: 256,
+        }
+    }
+}
+
+// Bounds on the request's `extra` map, so a huge map can't blow up memory or
+// slow down substitution
+#[derive(Debug, Clone, Copy)]
+pub struct ExtraLimits {
+    pub max_entries: usize,
+    pub max_key_len: usize,
+    pub max_value_len: usize,
+}
+
+impl Default for ExtraLimits {
+    fn default() -> Self {
+        ExtraLimits {
+            max_entries: 50,
+            max_key_len: 64,
+            max_value_len: 4096,
+        }
+    }
+}
+
+// One problem with one request field. `code` is stable so frontends can map
+// it to the form field and localize the message.
+#[derive(Debug, Clone, Serialize, ToSchema, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    // EMPTY, TOO_LONG, TOO_MANY, INVALID_EMAIL, UNSAFE_CHARS, RESERVED or MISSING
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &'static str, message: impl Into<String>) -> Self {
+        FieldError { field: field.to_string(), code, message: message.into() }
+    }
+}
+
+// Every field problem found in a request, not just the first
+#[derive(Debug, Clone, Default)]
+pub struct ValidationError {
+    pub fields: Vec<FieldError>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problems = self
+            .fields
+            .iter()
+            .map(|e| format!("{}: {}", e.field, e.message))
+            .collect::<Vec<_>>();
+        write!(f, "Validation failed: {}", problems.join("; "))
+    }
+}
+
+impl ValidationError {
+    pub fn push(&mut self, error: FieldError) {
+        self.fields.push(error);
+    }
+
+    pub fn into_result(self) -> Result<(), ValidationError> {
+        if self.fields.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+// Control characters, newlines included, would corrupt the LICENSE, README
+// and config files the values are written into, and could inject headers via
+// the Content-Disposition filename
+fn has_unsafe_chars(value: &str) -> bool {
+    value.chars().any(char::is_control)
+}
+
+// Deliberately loose: one `@` with something on both sides and a dot in the domain
+fn is_plausible_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !email.chars().any(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+fn check_text(errors: &mut ValidationError, field: &str, value: &str, max_len: usize) {
+    if value.trim().is_empty() {
+        errors.push(FieldError::new(field, "EMPTY", "must not be empty"));
+    } else if value.chars().count() > max_len {
+        errors.push(FieldError::new(field, "TOO_LONG", format!("must be at most {} characters", max_len)));
+    }
+}
+
+fn check_extra(errors: &mut ValidationError, user_info: &UserInfo, limits: ExtraLimits) {
+    if user_info.extra.len() > limits.max_entries {
+        errors.push(FieldError::new(
+            "extra",
+            "TOO_MANY",
+            format!("may have at most {} entries, got {}", limits.max_entries, user_info.extra.len()),
+        ));
+        // Don't list a problem per entry of an oversized map
+        return;
+    }
+    for (key, value) in &user_info.extra {
+        let field = format!("extra.{}", key.chars().take(limits.max_key_len).collect::<String>());
+        if key.chars().count() > limits.max_key_len {
+            errors.push(FieldError::new(&field, "TOO_LONG", format!("key must be at most {} characters", limits.max_key_len)));
+        }
+        if value.chars().count() > limits.max_value_len {
+            errors.push(FieldError::new(&field, "TOO_LONG", format!("value must be at most {} characters", limits.max_value_len)));
+        }
+        if has_unsafe_chars(key) || has_unsafe_chars(value) {
+            errors.push(FieldError::new(&field, "UNSAFE_CHARS", "must not contain control characters or newlines"));
+        }
+    }
+}
+
+// UserInfo's text fields a template can list under `required`; any other
+// name is looked up in `extra`
+pub const REQUIRABLE_FIELDS: [&str; 6] = ["username", "email", "project_name", "project_description", "project_slug", "framework"];
+
+fn requirable_value<'a>(user_info: &'a UserInfo, name: &str) -> Option<&'a str> {
+    match name {
+        "username" => Some(&user_info.username),
+        "email" => Some(&user_info.email),
+        "project_name" => Some(&user_info.project_name),
+        "project_description" => Some(&user_info.project_description),
+        "project_slug" => user_info.project_slug.as_deref(),
+        "framework" => user_info.framework.as_deref(),
+        _ => user_info.extra.get(name).map(String::as_str),
+    }
+}
+
+// Report each of a template's required fields that is absent or blank. The
+// fixed fields already get EMPTY from `check_text`, so they aren't repeated.
+fn check_required(errors: &mut ValidationError, user_info: &UserInfo, required: &[String]) {
+    for name in required {
+        if requirable_value(user_info, name).is_some_and(|value| !value.trim().is_empty()) {
+            continue;
+        }
+        let field = if REQUIRABLE_FIELDS.contains(&name.as_str()) { name.clone() } else { format!("extra.{}", name) };
+        if !errors.fields.iter().any(|e| e.field == field) {
+            errors.push(FieldError::new(&field, "MISSING", "is required by this template"));
+        }
+    }
+}
+
+// Check a request against the server's limits and the template's `required`
+// fields, see `TemplateConfig::required`
+pub fn validate_user_info(user_info: &UserInfo, settings: &Settings, required: &[String]) -> Result<(), ValidationError> {
+    let blocked_names = &settings.blocked_names;
+    let limits = settings.field_limits;
+    let mut errors = ValidationError::default();
+
+    check_text(&mut errors, "username", &user_info.username, limits.max_username_len);
+    check_text(&mut errors, "email", &user_info.email, limits.max_email_len);
+    check_text(&mut errors, "project_name", &user_info.project_name, limits.max_project_name_len);
+    check_text(&mut errors, "project_description", &user_info.project_description, limits.max_description_len);
+
+    let mut options = Vec::new();
+    options.extend(user_info.profile.as_deref().map(|v| ("profile".to_string(), v)));
+    options.extend(user_info.framework.as_deref().map(|v| ("framework".to_string(), v)));
+    options.extend(user_info.compression.as_deref().map(|v| ("compression".to_string(), v)));
+    options.extend(user_info.default_placeholder_value.as_deref().map(|v| ("default_placeholder_value".to_string(), v)));
+    options.extend(user_info.zip_comment.as_deref().map(|v| ("zip_comment".to_string(), v)));
+    options.extend(user_info.exclude.iter().enumerate().map(|(i, v)| (format!("exclude.{}", i), v.as_str())));
+    for (field, value) in &options {
+        if value.chars().count() > limits.max_option_len {
+            errors.push(FieldError::new(field, "TOO_LONG", format!("must be at most {} characters", limits.max_option_len)));
+        }
+    }
+
+    let mut fields = vec![
+        ("username".to_string(), user_info.username.as_str()),
+        ("email".to_string(), user_info.email.as_str()),
+        ("project_name".to_string(), user_info.project_name.as_str()),
+        ("project_description".to_string(), user_info.project_description.as_str()),
+    ];
+    fields.extend(options);
+    for (field, value) in fields {
+        if has_unsafe_chars(value) {
+            errors.push(FieldError::new(&field, "UNSAFE_CHARS", "must not contain control characters or newlines"));
+        }
+    }
+    if !user_info.email.trim().is_empty() && !is_plausible_email(user_info.email.trim()) {
+        errors.push(FieldError::new("email", "INVALID_EMAIL", "is not a valid email address"));
+    }
+
+    let slug = user_info.project_slug.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    // Without one the slug is derived from the name, which leaves nothing of
+    // a name like "!!!", and an empty slug makes a hidden `.zip` download
+    let name = user_info.project_name.trim();
+    if slug.is_none() && !name.is_empty() && !name.chars().any(char::is_alphanumeric) {
+        errors.push(FieldError::new(
+            "project_name",
+            "NO_SLUG",
+            "must contain a letter or digit, or set project_slug",
+        ));
+    }
+    if let Some(slug) = slug {
+        if slug.chars().count() > MAX_SLUG_LEN {
+            errors.push(FieldError::new("project_slug", "TOO_LONG", format!("must be at most {} characters", MAX_SLUG_LEN)));
+        }
+        if !slug.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) {
+            errors.push(FieldError::new(
+                "project_slug",
+                "UNSAFE_CHARS",
+                "may only contain letters, digits, '-', '_' and '.'",
+            ));
+        }
+    }
+
+    check_extra(&mut errors, user_info, settings.extra_limits);
+    check_required(&mut errors, user_info, required);
+
+    let names = [("project_name", Some(&user_info.project_name)), ("project_slug", user_info.project_slug.as_ref())];
+    for (field, name) in names {
+        if let Some(name) = name.filter(|name| blocked_names.is_blocked(name)) {
+            eprintln!("[ERROR] Rejected blocked project name: {}", name);
+            errors.push(FieldError::new(
+                field,
+                "RESERVED",
+                format!("'{}' is reserved and cannot be used", name.trim()),
+            ));
+        }
+    }
+
+    errors.into_result()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_info() -> UserInfo {
+        UserInfo {
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            project_name: "Demo".to_string(),
+            project_description: "A demo".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn codes(user_info: &UserInfo) -> Vec<(String, &'static str)> {
+        match validate_user_info(user_info, &Settings::default(), &[]) {
+            Ok(()) => Vec::new(),
+            Err(e) => e.fields.into_iter().map(|f| (f.field, f.code)).collect(),
+        }
+    }
+
+    #[test]
+    fn valid_request_passes() {
+        assert!(codes(&user_info()).is_empty());
+    }
+
+    #[test]
+    fn every_field_problem_is_reported_with_its_code() {
+        let request = UserInfo {
+            username: " ".to_string(),
+            email: "not-an-email".to_string(),
+            project_name: "x".repeat(FieldLimits::default().max_project_name_len + 1),
+            project_slug: Some("../etc".to_string()),
+            ..user_info()
+        };
+        assert_eq!(
+            codes(&request),
+            [
+                ("username".to_string(), "EMPTY"),
+                ("project_name".to_string(), "TOO_LONG"),
+                ("email".to_string(), "INVALID_EMAIL"),
+                ("project_slug".to_string(), "UNSAFE_CHARS"),
+            ]
+        );
+
+        let request = UserInfo { project_name: "!!!".to_string(), ..user_info() };
+        assert_eq!(codes(&request), [("project_name".to_string(), "NO_SLUG")]);
+        assert!(codes(&UserInfo { project_slug: Some("bang".to_string()), ..request }).is_empty());
+
+        let request = UserInfo { project_name: "NUL".to_string(), username: "a\u{7}".to_string(), ..user_info() };
+        assert_eq!(
+            codes(&request),
+            [("username".to_string(), "UNSAFE_CHARS"), ("project_name".to_string(), "RESERVED")]
+        );
+    }
+
+    #[test]
+    fn newlines_are_rejected_in_every_string_field() {
+        // Would otherwise end up in the Content-Disposition filename
+        let request = UserInfo { project_name: "Demo\r\nSet-Cookie: a=b".to_string(), ..user_info() };
+        assert_eq!(codes(&request), [("project_name".to_string(), "UNSAFE_CHARS")]);
+
+        let request = UserInfo {
+            project_description: "line one\nline two".to_string(),
+            exclude: vec!["docs/**".to_string(), "a\tb".to_string()],
+            extra: [("notes".to_string(), "x\ny".to_string())].into(),
+            ..user_info()
+        };
+        assert_eq!(
+            codes(&request),
+            [
+                ("project_description".to_string(), "UNSAFE_CHARS"),
+                ("exclude.1".to_string(), "UNSAFE_CHARS"),
+                ("extra.notes".to_string(), "UNSAFE_CHARS"),
+            ]
+        );
+    }
+
+    #[test]
+    fn extra_map_is_bounded() {
+        let limits = ExtraLimits::default();
+        let too_many = (0..=limits.max_entries).map(|i| (format!("key_{}", i), String::new())).collect();
+        assert_eq!(codes(&UserInfo { extra: too_many, ..user_info() }), [("extra".to_string(), "TOO_MANY")]);
+
+        let long_value = [("notes".to_string(), "x".repeat(limits.max_value_len + 1))].into();
+        assert_eq!(codes(&UserInfo { extra: long_value, ..user_info() }), [("extra.notes".to_string(), "TOO_LONG")]);
+    }
+
+    #[test]
+    fn field_limits_are_inclusive_and_configurable() {
+        let limits = FieldLimits::default();
+        let at_limit = UserInfo {
+            username: "u".repeat(limits.max_username_len),
+            project_name: "p".repeat(limits.max_project_name_len),
+            project_description: "d".repeat(limits.max_description_len),
+            framework: Some("f".repeat(limits.max_option_len)),
+            ..user_info()
+        };
+        assert!(codes(&at_limit).is_empty());
+
+        let over_limit = UserInfo {
+            project_description: "d".repeat(limits.max_description_len + 1),
+            exclude: vec!["e".repeat(limits.max_option_len + 1)],
+            ..user_info()
+        };
+        assert_eq!(
+            codes(&over_limit),
+            [("project_description".to_string(), "TOO_LONG"), ("exclude.0".to_string(), "TOO_LONG")]
+        );
+
+        let settings = Settings { field_limits: FieldLimits { max_username_len: 3, ..limits }, ..Settings::default() };
+        let error = validate_user_info(&user_info(), &settings, &[]).unwrap_err();
+        assert_eq!(error.fields[0].field, "username");
+        assert_eq!(error.fields[0].message, "must be at most 3 characters");
+    }
+
+    #[test]
+    fn template_required_fields_must_be_present_and_non_empty() {
+        let required = ["framework", "python_version", "username"].map(str::to_string);
+        let missing = |user_info: &UserInfo| match validate_user_info(user_info, &Settings::default(), &required) {
+            Ok(()) => Vec::new(),
+            Err(e) => e.fields.into_iter().map(|f| (f.field, f.code)).collect(),
+        };
+
+        assert_eq!(
+            missing(&user_info()),
+            [("framework".to_string(), "MISSING"), ("extra.python_version".to_string(), "MISSING")]
+        );
+        let request = UserInfo {
+            username: String::new(),
+            framework: Some("react".to_string()),
+            extra: [("python_version".to_string(), " ".to_string())].into(),
+            ..user_info()
+        };
+        assert_eq!(
+            missing(&request),
+            [("username".to_string(), "EMPTY"), ("extra.python_version".to_string(), "MISSING")]
+        );
+        let complete = UserInfo { extra: [("python_version".to_string(), "3.12".to_string())].into(), ..request };
+        assert!(missing(&UserInfo { username: "alice".to_string(), ..complete }).is_empty());
+    }
+
+    #[test]
+    fn string_fields_are_trimmed_when_read() {
+        let request: UserInfo = serde_json::from_str(
+            r#"{"username": "  alice ", "email": "alice@example.com\t", "project_name": " Demo", "project_description": "A demo ",
+                "framework": " fastapi ", "exclude": [" docs/** "]}"#,
+        )
+        .unwrap();
+        assert_eq!(request.username, "alice");
+        assert_eq!(request.email, "alice@example.com");
+        assert_eq!(request.project_name, "Demo");
+        assert_eq!(request.project_description, "A demo");
+        assert_eq!(request.framework.as_deref(), Some("fastapi"));
+        assert_eq!(request.exclude, ["docs/**"]);
+        assert!(codes(&request).is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+use notify::{RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::admin::reload_cache;
+use crate::AppState;
+
+// Set to "true" to reload templates whenever files under `templates/` change.
+// Meant for template authoring; production should use `/admin/reload`.
+pub const WATCH_TEMPLATES_ENV: &str = "ZEROHUB_WATCH_TEMPLATES";
+pub const TEMPLATES_DIR: &str = "templates";
+// Editors save in bursts (temp file, rename, chmod), so wait for a quiet spell
+pub const DEBOUNCE: Duration = Duration::from_millis(500);
+
+pub fn watch_enabled() -> bool {
+    std::env::var(WATCH_TEMPLATES_ENV).is_ok_and(|v| v.trim().eq_ignore_ascii_case("true"))
+}
+
+// Wait for the next change and return the paths touched by it and by every
+// event that follows within `DEBOUNCE` of the previous one
+async fn next_burst(rx: &mut mpsc::UnboundedReceiver<notify::Event>) -> Option<Vec<PathBuf>> {
+    let mut changed = rx.recv().await?.paths;
+    while let Ok(Some(event)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+        changed.extend(event.paths);
+    }
+    changed.sort();
+    changed.dedup();
+    Some(changed)
+}
+
+// Watch the templates directory and reload the cache once events stop
+// arriving for `DEBOUNCE`. The watcher lives as long as the spawned task.
+pub fn spawn_template_watcher(state: AppState) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher = match notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        match event {
+            Ok(event) if !event.kind.is_access() => {
+                let _ = tx.send(event);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("[ERROR] Template watcher error: {}", e),
+        }
+    }) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("[ERROR] Failed to start template watcher: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = watcher.watch(Path::new(TEMPLATES_DIR), RecursiveMode::Recursive) {
+        eprintln!("[ERROR] Failed to watch {}/: {}", TEMPLATES_DIR, e);
+        return;
+    }
+    println!("[DEBUG] Watching {}/ for changes ({}=true)", TEMPLATES_DIR, WATCH_TEMPLATES_ENV);
+
+    tokio::spawn(async move {
+        let _watcher = watcher;
+        while let Some(changed) = next_burst(&mut rx).await {
+            println!("[DEBUG] Template files changed: {:?}", changed);
+            if let Err(e) = reload_cache(&state).await {
+                eprintln!("[ERROR] Automatic template reload failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{EventKind, ModifyKind};
+
+    fn modified(path: &str) -> notify::Event {
+        notify::Event::new(EventKind::Modify(ModifyKind::Any)).add_path(PathBuf::from(path))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rapid_events_are_debounced_into_one_reload() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tx.send(modified("templates/server/README.md")).unwrap();
+        tx.send(modified("templates/server/README.md")).unwrap();
+        tx.send(modified("templates/server/LICENSE")).unwrap();
+        let later = tx.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(DEBOUNCE * 2).await;
+            later.send(modified("templates/client/LICENSE")).unwrap();
+        });
+
+        let first = next_burst(&mut rx).await.unwrap();
+        assert_eq!(first, [PathBuf::from("templates/server/LICENSE"), PathBuf::from("templates/server/README.md")]);
+        let second = next_burst(&mut rx).await.unwrap();
+        assert_eq!(second, [PathBuf::from("templates/client/LICENSE")]);
+    }
+}